@@ -8,7 +8,10 @@ use crate::deps::rust::infer_rust_dependencies;
 use crate::file_path::nix_file_paths;
 use crate::interactive::InteractiveData;
 use crate::types::{ExpressionInfo, Fetcher, Template, UserConfig, FAKE_SRI_HASH};
-use crate::url::{infer_dotnet_project_file, prefetch_dependency_hash, read_meta_from_url};
+use crate::url::{
+    infer_dotnet_project_file, prefetch_dependency_hash, read_meta_from_url, FetchOptions,
+    TagScheme,
+};
 
 // clap will validate inputs, only use on functions with possible_values defined
 pub fn arg_to_type<T>(arg: Option<&str>) -> T
@@ -19,6 +22,20 @@ where
     arg.unwrap().parse::<T>().unwrap()
 }
 
+/// Build the tag scheme from `--tag-regex` and `--version-transform`
+pub fn tag_scheme_from_matches(matches: &ArgMatches) -> TagScheme {
+    match TagScheme::new(
+        matches.value_of("tag-regex"),
+        matches.value_of("version-transform"),
+    ) {
+        Ok(scheme) => scheme,
+        Err(e) => {
+            eprintln!("Invalid --tag-regex: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 // There is the assert macro, but the panic output does not look great
 pub fn assert(pred: bool, message: &str) {
     if !pred {
@@ -59,7 +76,7 @@ fn shared_args() -> Vec<Arg<'static, 'static>> {
             .use_delimiter(true)
             .require_delimiter(false)
             .help("Comma-separated list of nixpkgs attributes to add to nativeBuildInputs (and the function header). May be repeated. Combined with any inferred entries; duplicates are removed."),
-        Arg::from_usage("-v [version] 'Set version of package'").default_value("0.0.1"),
+        Arg::from_usage("-v [version] 'Set version of package. With a URL, selects that upstream release.'").default_value("0.0.1"),
         Arg::from_usage("-p,--pname [pname] 'Package name to be used in expression'")
             .default_value("CHANGE"),
         Arg::from_usage(
@@ -100,6 +117,32 @@ fn template_args() -> Vec<Arg<'static, 'static>> {
             "--include-prereleases 'Include prerelease versions when fetching from GitLab or other forges.'",
         )
         .takes_value(false),
+        Arg::from_usage(
+            "--unstable 'Pin the default branch HEAD as version 0-unstable-YYYY-MM-DD instead of the latest release. Used automatically when a GitHub/GitLab/Gitea repo has no releases.'",
+        )
+        .takes_value(false),
+        Arg::from_usage(
+            "--rev [rev] 'Commit, branch or tag to pin in unstable mode (implies --unstable).'",
+        ),
+        Arg::from_usage(
+            "--tag [tag] 'Package this release or tag instead of the latest. Hosts that cannot select a release warn and use the latest. -v together with a URL does the same.'",
+        )
+        .conflicts_with_all(&["unstable", "rev"]),
+        Arg::from_usage(
+            "--tag-regex [regex] 'Regex with (?P<prefix>...) and (?P<version>...) captures splitting upstream tags, e.g. ^(?P<prefix>cli-v)(?P<version>.+)$. Non-matching tags are ignored.'",
+        ),
+        Arg::from_usage(
+            "--version-transform [transform] 'Separator upstream tags use instead of dots, e.g. underscores for release_1_2_3 -> 1.2.3.'",
+        )
+        .possible_values(&["underscores", "dashes"]),
+        Arg::from_usage(
+            "--go-module [module] 'Package a Go module by import path, e.g. golang.org/x/tools/gopls. The latest version is resolved through $GOPROXY (default https://proxy.golang.org).'",
+        )
+        .conflicts_with("from-url"),
+        Arg::from_usage(
+            "--follow-repository 'For crates.io URLs, fetch the source from the linked GitHub repository instead of the registry. npm URLs already do so when the registry tarball has no package-lock.json.'",
+        )
+        .takes_value(false),
     ]
 }
 
@@ -186,6 +229,9 @@ pub fn build_cli() -> App<'static, 'static> {
     GITHUB_TOKEN\tToken used during GitHub API calls.
     GITLAB_TOKEN\tToken used during GitLab API calls (uses PRIVATE-TOKEN header).
     GITEA_TOKEN\t\tToken used during Gitea API calls (uses Authorization header).
+    SRHT_TOKEN\t\tToken used during git.sr.ht API calls (uses Authorization header).
+    BITBUCKET_TOKEN\tToken used during Bitbucket Cloud API calls (uses Bearer Authorization header).
+    BITBUCKET_API_URL\tBase URL of the Bitbucket API (default: https://api.bitbucket.org/2.0).
 
 EXAMPLES:
 
@@ -298,12 +344,14 @@ pub fn validate_and_serialize_template_matches(
     let include_meta: bool = !matches.is_present("no-meta");
 
     let nixpkgs_layout = matches.is_present("by-name");
-    let has_url = url_from_positional.is_some() || matches.is_present("from-url");
+    let has_url = url_from_positional.is_some()
+        || matches.is_present("from-url")
+        || matches.is_present("go-module");
     assert(
         !(nixpkgs_layout
             && matches.value_of("pname") == Some("CHANGE")
             && !has_url),
-        "'-p,--pname' or '-u,--from-url' is required when using the --by-name flag",
+        "'-p,--pname', '-u,--from-url' or '--go-module' is required when using the --by-name flag",
     );
 
     if nixpkgs_layout {
@@ -348,6 +396,12 @@ pub fn validate_and_serialize_template_matches(
         include_documentation_links,
         include_meta,
         tag_prefix: "".to_owned(),
+        tag_version_separator: "".to_owned(),
+        src_rev: "".to_owned(),
+        fetch_submodules: false,
+        fetch_lfs: false,
+        main_program: String::new(),
+        executables: Vec::new(),
         owner: "CHANGE".to_owned(),
         src_sha: "0000000000000000000000000000000000000000000000000000".to_owned(),
         description: "CHANGE".to_owned(),
@@ -359,24 +413,42 @@ pub fn validate_and_serialize_template_matches(
         pnpm_deps_hash: FAKE_SRI_HASH.to_owned(),
         project_file: "CHANGE".to_owned(),
         domain: "CHANGE".to_owned(),
+        src_url: "CHANGE".to_owned(),
         build_inputs: Vec::new(),
         native_build_inputs: Vec::new(),
         use_cargo_lock_file: false,
         cargo_lock_git_deps: Vec::new(),
         go_module_path: String::new(),
+        go_mod_root: String::new(),
         python_format: "setuptools".to_owned(),
         mvn_hash: FAKE_SRI_HASH.to_owned(),
         mix_fod_hash: FAKE_SRI_HASH.to_owned(),
         gradle_hash: FAKE_SRI_HASH.to_owned(),
     };
 
-    // Handle URL: either from positional or from --from-url flag
+    // Handle URL: from positional, --from-url or --go-module
+    let go_module_url = matches
+        .value_of("go-module")
+        .map(|module| format!("pkg.go.dev/{}", module));
     let url = url_from_positional
         .as_deref()
-        .or_else(|| matches.value_of("from-url"));
+        .or_else(|| matches.value_of("from-url"))
+        .or(go_module_url.as_deref());
     if let Some(url) = url {
-        let include_prereleases = matches.is_present("include-prereleases");
-        read_meta_from_url(url, &mut info, include_prereleases);
+        let options = FetchOptions {
+            include_prereleases: matches.is_present("include-prereleases"),
+            unstable: matches.is_present("unstable"),
+            rev: matches.value_of("rev").map(|r| r.to_owned()),
+            tag: matches.value_of("tag").map(|t| t.to_owned()).or_else(|| {
+                // An explicit -v selects that upstream release
+                (matches.occurrences_of("v") > 0).then(|| info.version.clone())
+            }),
+            tag_scheme: tag_scheme_from_matches(matches),
+            follow_repository: matches.is_present("follow-repository"),
+            forges: user_config.map(|c| c.forges.clone()).unwrap_or_default(),
+            go_proxy: std::env::var("GOPROXY").ok(),
+        };
+        read_meta_from_url(url, &mut info, &options);
     }
 
     // Auto-detect template when "auto" is selected (either explicitly or as
@@ -454,7 +526,8 @@ pub fn validate_and_serialize_template_matches(
         if let Some(hash) = prefetch_dependency_hash(&info) {
             match &info.template {
                 Template::Rust(_) => info.cargo_hash = hash,
-                Template::Go(_) => info.vendor_hash = hash,
+                Template::Go(_) | Template::Php(_) => info.vendor_hash = hash,
+                Template::Maven(_) => info.mvn_hash = hash,
                 Template::Node(config) => match config.variant {
                     crate::types::NodeVariant::Npm => info.npm_deps_hash = hash,
                     crate::types::NodeVariant::Pnpm => info.pnpm_deps_hash = hash,
@@ -548,6 +621,12 @@ pub fn build_expression_info_from_interactive(
         include_documentation_links: data.include_documentation_links,
         include_meta: data.include_meta,
         tag_prefix: "".to_owned(),
+        tag_version_separator: "".to_owned(),
+        src_rev: "".to_owned(),
+        fetch_submodules: false,
+        fetch_lfs: false,
+        main_program: String::new(),
+        executables: Vec::new(),
         owner: "CHANGE".to_owned(),
         src_sha: "0000000000000000000000000000000000000000000000000000".to_owned(),
         description: data.description,
@@ -559,11 +638,13 @@ pub fn build_expression_info_from_interactive(
         pnpm_deps_hash: FAKE_SRI_HASH.to_owned(),
         project_file: "CHANGE".to_owned(),
         domain: "CHANGE".to_owned(),
+        src_url: "CHANGE".to_owned(),
         build_inputs: Vec::new(),
         native_build_inputs: Vec::new(),
         use_cargo_lock_file: false,
         cargo_lock_git_deps: Vec::new(),
         go_module_path: String::new(),
+        go_mod_root: String::new(),
         python_format: "setuptools".to_owned(),
         mvn_hash: FAKE_SRI_HASH.to_owned(),
         mix_fod_hash: FAKE_SRI_HASH.to_owned(),
@@ -572,7 +653,14 @@ pub fn build_expression_info_from_interactive(
 
     // If URL was provided, fetch metadata
     if let Some(url) = data.url {
-        read_meta_from_url(&url, &mut info, data.include_prereleases);
+        let options = FetchOptions {
+            include_prereleases: data.include_prereleases,
+            tag_scheme: data.tag_scheme,
+            forges: user_config.map(|c| c.forges.clone()).unwrap_or_default(),
+            go_proxy: std::env::var("GOPROXY").ok(),
+            ..Default::default()
+        };
+        read_meta_from_url(&url, &mut info, &options);
     }
 
     // Vendor hash prefetching is enabled by default (opt-out via skip flag).
@@ -581,7 +669,12 @@ pub fn build_expression_info_from_interactive(
         if let Some(hash) = prefetch_dependency_hash(&info) {
             match &info.template {
                 Template::Rust(_) => info.cargo_hash = hash,
-                Template::Go(_) => info.vendor_hash = hash,
+                Template::Go(_) | Template::Php(_) => info.vendor_hash = hash,
+                Template::Maven(_) => info.mvn_hash = hash,
+                Template::Node(config) => match config.variant {
+                    crate::types::NodeVariant::Npm => info.npm_deps_hash = hash,
+                    crate::types::NodeVariant::Pnpm => info.pnpm_deps_hash = hash,
+                },
                 _ => {}
             }
         }
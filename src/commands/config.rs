@@ -23,6 +23,7 @@ pub fn run(
                 user_config = Some(UserConfig {
                     maintainer: name,
                     nixpkgs_root: None,
+                    forges: Default::default(),
                 })
             };
         }
@@ -38,6 +39,7 @@ pub fn run(
                 user_config = Some(UserConfig {
                     maintainer: None,
                     nixpkgs_root: root,
+                    forges: Default::default(),
                 })
             };
         }
@@ -192,6 +192,7 @@ fn run_project(
             Some(directory_name.clone()),
             Some(inferred_deps),
             true, // is_local_init
+            Default::default(),
         );
 
         match interactive_result {
@@ -234,6 +235,12 @@ fn run_project(
             include_documentation_links,
             include_meta,
             tag_prefix: "".to_owned(),
+            tag_version_separator: "".to_owned(),
+            src_rev: "".to_owned(),
+            fetch_submodules: false,
+            fetch_lfs: false,
+            main_program: String::new(),
+            executables: Vec::new(),
             owner: "CHANGE".to_owned(),
             src_sha: "0000000000000000000000000000000000000000000000000000".to_owned(),
             description: "CHANGE".to_owned(),
@@ -245,11 +252,13 @@ fn run_project(
             pnpm_deps_hash: crate::types::FAKE_SRI_HASH.to_owned(),
             project_file: "CHANGE".to_owned(),
             domain: "CHANGE".to_owned(),
+            src_url: "CHANGE".to_owned(),
             build_inputs: Vec::new(),
             native_build_inputs: Vec::new(),
             use_cargo_lock_file: false,
             cargo_lock_git_deps: Vec::new(),
             go_module_path: String::new(),
+            go_mod_root: String::new(),
             python_format: "setuptools".to_owned(),
             mvn_hash: crate::types::FAKE_SRI_HASH.to_owned(),
             mix_fod_hash: crate::types::FAKE_SRI_HASH.to_owned(),
@@ -15,7 +15,8 @@ pub fn run(
         && matches.value_of("pname") == Some("CHANGE");
 
     let mut info = if should_use_interactive {
-        match interactive::run_interactive_mode(None, user_config) {
+        let tag_scheme = cli::tag_scheme_from_matches(matches);
+        match interactive::run_interactive_mode(None, user_config, tag_scheme) {
             Ok(interactive_data) => {
                 cli::build_expression_info_from_interactive(interactive_data, user_config)
             }
@@ -82,7 +83,8 @@ pub fn run_interactive(
     _xdg_dirs: &xdg::BaseDirectories,
     user_config: Option<&UserConfig>,
 ) {
-    let info = match interactive::run_interactive_mode(None, user_config) {
+    let tag_scheme = Default::default();
+    let info = match interactive::run_interactive_mode(None, user_config, tag_scheme) {
         Ok(interactive_data) => {
             cli::build_expression_info_from_interactive(interactive_data, user_config)
         }
@@ -0,0 +1,210 @@
+//! Desktop integration of extracted AppImages.
+//!
+//! An AppImage carries a desktop entry and its icon at the root of its
+//! squashfs. This module reads them from an `appimageTools.extract` output
+//! to:
+//! 1. Find and parse the desktop entry (`Name`, `Exec`, `Icon`, `Comment`
+//!    and `X-AppImage-Version`)
+//! 2. Derive the package name installed by `appimageTools.wrapType2`
+//! 3. Locate the icons to install next to the desktop entry
+
+use log::debug;
+use std::path::Path;
+
+const LOG_TARGET: &str = "nix-template::appimage_deps";
+
+/// Keys of the `[Desktop Entry]` group of a desktop entry.
+#[derive(Debug, Default, PartialEq)]
+pub struct DesktopEntry {
+    /// Filename of the entry, e.g. "obsidian.desktop"
+    pub file_name: String,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub exec: Option<String>,
+    pub icon: Option<String>,
+    /// Version recorded by appimagetool (`X-AppImage-Version`)
+    pub version: Option<String>,
+}
+
+impl DesktopEntry {
+    /// Program of `Exec` as written in the entry, quotes included, e.g.
+    /// `"/usr/bin/Joplin App"` for `Exec="/usr/bin/Joplin App" %U`.
+    pub fn exec_command(&self) -> Option<&str> {
+        let exec = self.exec.as_deref()?.trim();
+        let command = match exec.strip_prefix('"') {
+            Some(quoted) => &exec[..quoted.find('"')? + 2],
+            None => exec.split_whitespace().next()?,
+        };
+        (!command.is_empty()).then_some(command)
+    }
+
+    /// Program named by `Exec`, without quotes, arguments or field codes,
+    /// e.g. "AppRun" for `Exec=AppRun --no-sandbox %U`.
+    pub fn exec_program(&self) -> Option<&str> {
+        let command = self.exec_command()?;
+        let program = command.trim_matches('"');
+        (!program.is_empty()).then_some(program)
+    }
+
+    /// Name of the package: the program named by `Exec` unless it is the
+    /// generic `AppRun`, otherwise the last component of the entry's
+    /// filename ("org.kde.krita.desktop" becomes "krita").
+    pub fn pname(&self) -> String {
+        let program = self
+            .exec_program()
+            .map(|program| program.rsplit('/').next().unwrap_or(program))
+            .filter(|program| *program != "AppRun");
+        let name = match program {
+            Some(program) => program,
+            None => {
+                let stem = self.file_name.trim_end_matches(".desktop");
+                stem.rsplit('.').next().unwrap_or(stem)
+            }
+        };
+        name.to_lowercase().replace([' ', '_'], "-")
+    }
+}
+
+/// Parse the unlocalised keys of the `[Desktop Entry]` group.
+pub fn parse_desktop_entry(file_name: &str, contents: &str) -> DesktopEntry {
+    let mut entry = DesktopEntry {
+        file_name: file_name.to_owned(),
+        ..Default::default()
+    };
+    let mut in_group = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_group {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().to_owned()),
+            None => continue,
+        };
+        match key {
+            "Name" => entry.name = Some(value),
+            "Comment" => entry.comment = Some(value),
+            "Exec" => entry.exec = Some(value),
+            "Icon" => entry.icon = Some(value),
+            "X-AppImage-Version" => entry.version = Some(value),
+            _ => (),
+        }
+    }
+
+    entry
+}
+
+/// Read the desktop entry at the root of an extracted AppImage.
+pub fn read_desktop_entry(root: &Path) -> Option<DesktopEntry> {
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!(target: LOG_TARGET, "failed to read {}: {}", root.display(), e);
+            return None;
+        }
+    };
+    let mut desktop_files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".desktop"))
+        .collect();
+    desktop_files.sort();
+
+    let file_name = desktop_files.into_iter().next()?;
+    let contents = match std::fs::read_to_string(root.join(&file_name)) {
+        Ok(contents) => contents,
+        Err(e) => {
+            debug!(target: LOG_TARGET, "failed to read {}: {}", &file_name, e);
+            return None;
+        }
+    };
+    Some(parse_desktop_entry(&file_name, &contents))
+}
+
+/// Icons to install for the desktop entry, relative to the root of the
+/// AppImage: the whole "usr/share/icons" theme when it ships one, otherwise
+/// the icon file named by `Icon` next to the desktop entry.
+pub fn find_icon(root: &Path, entry: &DesktopEntry) -> Option<String> {
+    if root.join("usr/share/icons").is_dir() {
+        return Some("usr/share/icons".to_owned());
+    }
+    let icon = entry.icon.as_deref()?;
+    ["png", "svg", "xpm"]
+        .iter()
+        .map(|extension| format!("{}.{}", icon, extension))
+        .find(|file| root.join(file).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_desktop_entry() {
+        let entry = parse_desktop_entry(
+            "obsidian.desktop",
+            "[Desktop Entry]\n\
+             Name=Obsidian\n\
+             Name[de]=Obsidian DE\n\
+             Comment=Knowledge base\n\
+             Exec=AppRun --no-sandbox %U\n\
+             Icon=obsidian\n\
+             X-AppImage-Version=1.5.3\n\
+             \n\
+             [Desktop Action new-window]\n\
+             Exec=AppRun --new-window\n",
+        );
+        assert_eq!(entry.name.as_deref(), Some("Obsidian"));
+        assert_eq!(entry.comment.as_deref(), Some("Knowledge base"));
+        assert_eq!(entry.exec.as_deref(), Some("AppRun --no-sandbox %U"));
+        assert_eq!(entry.exec_program(), Some("AppRun"));
+        assert_eq!(entry.version.as_deref(), Some("1.5.3"));
+        assert_eq!(entry.pname(), "obsidian");
+    }
+
+    #[test]
+    fn test_desktop_entry_pname() {
+        let entry =
+            parse_desktop_entry("org.kde.krita.desktop", "[Desktop Entry]\nExec=AppRun %F\n");
+        assert_eq!(entry.pname(), "krita");
+
+        let entry = parse_desktop_entry(
+            "joplin.desktop",
+            "[Desktop Entry]\nExec=\"/usr/bin/Joplin App\" %U\n",
+        );
+        assert_eq!(entry.exec_command(), Some("\"/usr/bin/Joplin App\""));
+        assert_eq!(entry.exec_program(), Some("/usr/bin/Joplin App"));
+        assert_eq!(entry.pname(), "joplin-app");
+    }
+
+    #[test]
+    fn test_read_desktop_entry_and_icon() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_desktop_entry(dir.path()), None);
+
+        fs::write(
+            dir.path().join("demo.desktop"),
+            "[Desktop Entry]\nName=Demo\nExec=demo\nIcon=demo\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("demo.png"), "").unwrap();
+        let entry = read_desktop_entry(dir.path()).unwrap();
+        assert_eq!(entry.file_name, "demo.desktop");
+        assert_eq!(find_icon(dir.path(), &entry).as_deref(), Some("demo.png"));
+
+        fs::create_dir_all(dir.path().join("usr/share/icons/hicolor")).unwrap();
+        assert_eq!(
+            find_icon(dir.path(), &entry).as_deref(),
+            Some("usr/share/icons")
+        );
+    }
+}
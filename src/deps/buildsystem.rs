@@ -431,6 +431,12 @@ find_package(OpenSSL)
             include_documentation_links: false,
             include_meta: true,
             tag_prefix: "".to_owned(),
+            tag_version_separator: "".to_owned(),
+            src_rev: "".to_owned(),
+            fetch_submodules: false,
+            fetch_lfs: false,
+            main_program: String::new(),
+            executables: Vec::new(),
             owner: "test".to_owned(),
             src_sha: "sha256-test".to_owned(),
             description: "test".to_owned(),
@@ -442,11 +448,13 @@ find_package(OpenSSL)
             pnpm_deps_hash: "".to_owned(),
             project_file: "".to_owned(),
             domain: "".to_owned(),
+            src_url: "".to_owned(),
             build_inputs: Vec::new(),
             native_build_inputs: Vec::new(),
             use_cargo_lock_file: false,
             cargo_lock_git_deps: Vec::new(),
             go_module_path: String::new(),
+            go_mod_root: String::new(),
             python_format: "setuptools".to_owned(),
             mvn_hash: FAKE_SRI_HASH.to_owned(),
             mix_fod_hash: FAKE_SRI_HASH.to_owned(),
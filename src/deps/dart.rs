@@ -133,7 +133,6 @@ pub fn parse_dart_executables(pubspec_yaml_path: &Path) -> Vec<String> {
 ///
 /// Returns the SDK version string (e.g., ">=3.0.0 <4.0.0") or None.
 /// This is reserved for future version pinning support.
-#[allow(dead_code)]
 pub fn extract_dart_version(pubspec_yaml_path: &Path) -> Option<String> {
     let contents = match std::fs::read_to_string(pubspec_yaml_path) {
         Ok(c) => c,
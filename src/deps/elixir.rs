@@ -111,6 +111,33 @@ fn lookup_mix_package(name: &str) -> Option<(Vec<&'static str>, Vec<&'static str
     }
 }
 
+/// Map Mix package names to native library dependencies.
+///
+/// Returns a tuple of (buildInputs, nativeBuildInputs).
+pub fn map_mix_packages_to_nix<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+) -> (Vec<String>, Vec<String>) {
+    let mut build_inputs = BTreeSet::new();
+    let mut native_build_inputs = BTreeSet::new();
+
+    for pkg_name in names {
+        if let Some((bi, nbi)) = lookup_mix_package(pkg_name) {
+            build_inputs.extend(bi.iter().map(|s| s.to_string()));
+            native_build_inputs.extend(nbi.iter().map(|s| s.to_string()));
+            debug!(
+                target: LOG_TARGET,
+                "detected native deps for {}: buildInputs={:?}, nativeBuildInputs={:?}",
+                pkg_name, bi, nbi
+            );
+        }
+    }
+
+    (
+        build_inputs.into_iter().collect(),
+        native_build_inputs.into_iter().collect(),
+    )
+}
+
 /// Infer native library dependencies from mix.lock.
 ///
 /// Returns a tuple of (buildInputs, nativeBuildInputs).
@@ -126,39 +153,15 @@ pub fn infer_native_dependencies(mix_lock_path: &Path) -> (Vec<String>, Vec<Stri
         }
     };
 
-    let mut build_inputs = BTreeSet::new();
-    let mut native_build_inputs = BTreeSet::new();
-
     // Parse mix.lock for package names
     // Format: "package_name": {:hex, :package_name, ...}
-    // Simple regex-based extraction of package names
-    for line in contents.lines() {
-        let line = line.trim();
-        if line.starts_with('"') || line.starts_with(':') {
-            // Extract package name from lines like: "bcrypt_elixir": {:hex, :bcrypt_elixir, ...}
-            if let Some(pkg_name) = line
-                .split(':')
-                .nth(1)
-                .map(|s| s.trim_matches(|c| c == '"' || c == ','))
-            {
-                let pkg_name = pkg_name.trim();
-                if let Some((bi, nbi)) = lookup_mix_package(pkg_name) {
-                    build_inputs.extend(bi.iter().map(|s| s.to_string()));
-                    native_build_inputs.extend(nbi.iter().map(|s| s.to_string()));
-                    debug!(
-                        target: LOG_TARGET,
-                        "detected native deps for {}: buildInputs={:?}, nativeBuildInputs={:?}",
-                        pkg_name, bi, nbi
-                    );
-                }
-            }
-        }
-    }
+    let names = contents.lines().filter_map(|line| {
+        // Extract package name from lines like: "bcrypt_elixir": {:hex, :bcrypt_elixir, ...}
+        let (name, rest) = line.trim().strip_prefix('"')?.split_once('"')?;
+        rest.starts_with(':').then_some(name)
+    });
 
-    (
-        build_inputs.into_iter().collect(),
-        native_build_inputs.into_iter().collect(),
-    )
+    map_mix_packages_to_nix(names)
 }
 
 #[cfg(test)]
@@ -234,6 +237,30 @@ end
         assert!(bi.contains(&"libsodium"));
     }
 
+    #[test]
+    fn test_infer_native_dependencies_from_mix_lock() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mix_lock = temp_dir.path().join("mix.lock");
+        std::fs::write(
+            &mix_lock,
+            r#"%{
+  "bcrypt_elixir": {:hex, :bcrypt_elixir, "3.1.0", "0b1", [:make, :mix], [], "hexpm", "2ad"},
+  "jason": {:hex, :jason, "1.4.1", "af1", [:mix], [], "hexpm", "fbb"},
+  "rustler": {:hex, :rustler, "0.34.0", "e9a", [:mix], [], "hexpm", "1d0"},
+}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            infer_native_dependencies(&mix_lock),
+            (
+                vec!["libsodium".to_string()],
+                vec!["cargo".to_string(), "rustc".to_string()]
+            )
+        );
+    }
+
     #[test]
     fn test_lookup_mix_package_rustler() {
         let result = lookup_mix_package("rustler");
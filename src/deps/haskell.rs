@@ -62,7 +62,6 @@ pub fn is_executable_package(cabal_path: &Path) -> bool {
 ///
 /// Looks for the `name:` field in the .cabal file.
 /// Returns the package name or None if not found.
-#[allow(dead_code)]
 pub fn extract_package_name(cabal_path: &Path) -> Option<String> {
     let contents = match std::fs::read_to_string(cabal_path) {
         Ok(c) => c,
@@ -82,6 +81,37 @@ pub fn extract_package_name(cabal_path: &Path) -> Option<String> {
     None
 }
 
+/// Extract a top-level field such as `synopsis:` from .cabal file contents.
+///
+/// Field names are case-insensitive; indented continuation lines are joined
+/// with spaces. Returns None if the field is missing or empty.
+pub fn extract_cabal_field(contents: &str, field: &str) -> Option<String> {
+    let prefix = format!("{}:", field.to_lowercase());
+    let mut lines = contents.lines();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with(char::is_whitespace) || !line.to_lowercase().starts_with(&prefix) {
+            continue;
+        }
+
+        let mut value = line[prefix.len()..].trim().to_string();
+        for continuation in lines.by_ref() {
+            if continuation.trim().is_empty() || !continuation.starts_with(char::is_whitespace) {
+                break;
+            }
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(continuation.trim());
+        }
+
+        debug!(target: LOG_TARGET, "extracted cabal field {}: {}", field, value);
+        return if value.is_empty() { None } else { Some(value) };
+    }
+
+    None
+}
+
 /// Infer GHC version from cabal.project file.
 ///
 /// Looks for `with-compiler: ghc-X.Y.Z` directive in cabal.project.
@@ -144,6 +174,30 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_extract_cabal_field() {
+        let contents = r#"cabal-version: 2.2
+Name: mypackage
+Synopsis: A tool that
+  does things
+license:    BSD-3-Clause
+homepage:
+
+executable mypackage
+  synopsis: not top-level
+"#;
+        assert_eq!(
+            extract_cabal_field(contents, "synopsis"),
+            Some("A tool that does things".to_string())
+        );
+        assert_eq!(
+            extract_cabal_field(contents, "license"),
+            Some("BSD-3-Clause".to_string())
+        );
+        assert_eq!(extract_cabal_field(contents, "homepage"), None);
+        assert_eq!(extract_cabal_field(contents, "main-is"), None);
+    }
+
     #[test]
     fn test_executable_package() {
         let temp_dir = TempDir::new().unwrap();
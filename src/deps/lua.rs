@@ -4,6 +4,7 @@
 //! 1. Determine if the package is a library or application
 //! 2. Parse Lua version requirements
 //! 3. Extract build type from rockspec
+//! 4. Read metadata and dependencies of rockspecs published to LuaRocks
 //!
 //! Rockspec files are Lua scripts that define package metadata using a
 //! specific schema. Common fields include:
@@ -13,9 +14,12 @@
 //! - build.modules: for libraries (presence indicates Package variant)
 //! - build.install.bin: for applications (presence indicates Application variant)
 //! - dependencies: including Lua version requirements
+//! - external_dependencies: system libraries, keyed by an uppercase name
 
 use crate::templates::types::{LuaVariant, LuaVersion};
 use log::debug;
+use regex::Regex;
+use std::collections::BTreeSet;
 use std::path::Path;
 
 const LOG_TARGET: &str = "nix-template::lua_deps";
@@ -117,6 +121,217 @@ pub fn infer_lua_version(rockspec_path: &Path) -> LuaVersion {
     LuaVersion::Lua54
 }
 
+/// Metadata, source and dependencies of a rockspec.
+#[derive(Debug, Default, PartialEq)]
+pub struct Rockspec {
+    pub package: Option<String>,
+    /// Rockspec version including its revision, e.g. "1.1.0-1"
+    pub version: Option<String>,
+    pub source_url: Option<String>,
+    pub source_tag: Option<String>,
+    pub summary: Option<String>,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    /// Names of required rocks, without version constraints
+    pub dependencies: Vec<String>,
+    /// Keys of `external_dependencies`, e.g. "OPENSSL"
+    pub external_dependencies: Vec<String>,
+}
+
+/// Remove `--` line comments and `--[[ ]]` block comments.
+fn strip_lua_comments(contents: &str) -> String {
+    let block = Regex::new(r"(?s)--\[(=*)\[.*?\]\]").unwrap();
+    let line = Regex::new(r"(?m)--.*$").unwrap();
+    line.replace_all(&block.replace_all(contents, ""), "")
+        .into_owned()
+}
+
+/// Body of the table assigned to `key`, e.g. `dependencies = { ... }`,
+/// without the enclosing braces.
+fn lua_table<'a>(contents: &'a str, key: &str) -> Option<&'a str> {
+    let re = Regex::new(&format!(
+        r#"(?:\b{0}|\["{0}"\])\s*=\s*\{{"#,
+        regex::escape(key)
+    ))
+    .ok()?;
+    let start = re.find(contents)?.end();
+    let mut depth = 1;
+    let mut quote = None;
+    for (i, c) in contents[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&contents[start..start + i]);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Remove nested tables so that only the fields of the outer table remain.
+fn strip_lua_tables(contents: &str) -> String {
+    let mut stripped = String::with_capacity(contents.len());
+    let mut depth = 0;
+    for c in contents.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 => stripped.push(c),
+            _ => (),
+        }
+    }
+    stripped
+}
+
+/// String assigned to `key` among the fields of `contents`.
+fn lua_string(contents: &str, key: &str) -> Option<String> {
+    let re = Regex::new(&format!(
+        r#"(?s)\b{}\s*=\s*(?:"([^"]*)"|'([^']*)'|\[\[(.*?)\]\])"#,
+        regex::escape(key)
+    ))
+    .ok()?;
+    let captures = re.captures(contents)?;
+    (1..=3)
+        .find_map(|i| captures.get(i))
+        .map(|m| m.as_str().trim().to_owned())
+}
+
+/// Parse a rockspec's package, version, source, description and
+/// dependencies.
+pub fn parse_rockspec(rockspec_path: &Path) -> Option<Rockspec> {
+    let contents = match std::fs::read_to_string(rockspec_path) {
+        Ok(c) => c,
+        Err(e) => {
+            debug!(
+                target: LOG_TARGET,
+                "failed to read rockspec: {}", e
+            );
+            return None;
+        }
+    };
+    let contents = strip_lua_comments(&contents);
+    let fields = strip_lua_tables(&contents);
+    let source = lua_table(&contents, "source").map(strip_lua_tables);
+    let description = lua_table(&contents, "description").map(strip_lua_tables);
+    let string_re = Regex::new(r#""([^"]*)"|'([^']*)'"#).unwrap();
+
+    let mut rockspec = Rockspec {
+        package: lua_string(&fields, "package"),
+        version: lua_string(&fields, "version"),
+        source_url: source.as_deref().and_then(|s| lua_string(s, "url")),
+        source_tag: source.as_deref().and_then(|s| lua_string(s, "tag")),
+        summary: description
+            .as_deref()
+            .and_then(|d| lua_string(d, "summary")),
+        homepage: description
+            .as_deref()
+            .and_then(|d| lua_string(d, "homepage")),
+        license: description
+            .as_deref()
+            .and_then(|d| lua_string(d, "license")),
+        ..Rockspec::default()
+    };
+    if let Some(dependencies) = lua_table(&contents, "dependencies") {
+        // e.g. "lpeg >= 1.0", "luasocket"
+        rockspec.dependencies = string_re
+            .captures_iter(dependencies)
+            .filter_map(|c| c.get(1).or_else(|| c.get(2)))
+            .filter_map(|m| {
+                m.as_str()
+                    .split(|c: char| c.is_whitespace() || "<>=~".contains(c))
+                    .next()
+            })
+            .filter(|name| !name.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+    }
+    if let Some(external) = lua_table(&contents, "external_dependencies") {
+        let key_re = Regex::new(r"\b([A-Za-z_][A-Za-z0-9_]*)\s*=").unwrap();
+        rockspec.external_dependencies = key_re
+            .captures_iter(&strip_lua_tables(external))
+            .map(|c| c[1].to_owned())
+            .filter(|key| key != "platforms")
+            .collect();
+    }
+
+    debug!(target: LOG_TARGET, "parsed rockspec: {:?}", rockspec);
+    Some(rockspec)
+}
+
+/// Versions of `rock` listed in a LuaRocks manifest, e.g. "1.1.0-1".
+pub fn parse_manifest_versions(manifest: &str, rock: &str) -> Vec<String> {
+    let repository = match lua_table(manifest, "repository") {
+        Some(repository) => repository,
+        None => return Vec::new(),
+    };
+    let rock = match lua_table(repository, rock) {
+        Some(rock) => rock,
+        None => return Vec::new(),
+    };
+    Regex::new(r#"\["([^"]+)"\]\s*="#)
+        .unwrap()
+        .captures_iter(&strip_lua_tables(rock))
+        .map(|c| c[1].to_owned())
+        .collect()
+}
+
+/// Map an `external_dependencies` key to a nixpkgs attribute.
+fn lookup_external_dependency(name: &str) -> Option<&'static str> {
+    let package = match name {
+        "OPENSSL" | "CRYPTO" | "SSL" => "openssl",
+        "ZLIB" | "Z" => "zlib",
+        "SQLITE" | "SQLITE3" => "sqlite",
+        "YAML" | "LIBYAML" => "libyaml",
+        "CURL" | "LIBCURL" => "curl",
+        "UV" | "LIBUV" => "libuv",
+        "MYSQL" => "libmysqlclient",
+        "POSTGRES" | "PQ" | "LIBPQ" => "libpq",
+        "MAGIC" | "LIBMAGIC" => "file",
+        "XML2" | "LIBXML2" => "libxml2",
+        "ICONV" => "libiconv",
+        "FFI" | "LIBFFI" => "libffi",
+        "UUID" | "LIBUUID" => "libuuid",
+        _ => return None,
+    };
+    Some(package)
+}
+
+/// Map a rockspec's dependencies to `luaPackages` attributes and its
+/// external dependencies to system packages.
+///
+/// Returns a tuple of (propagatedBuildInputs, buildInputs). Lua itself is
+/// provided by the builder and skipped; external dependencies without a
+/// known mapping are lowercased, which is right for most libraries.
+pub fn map_rockspec_dependencies_to_nix(rockspec: &Rockspec) -> (Vec<String>, Vec<String>) {
+    let mut propagated_build_inputs = BTreeSet::new();
+    let mut build_inputs = BTreeSet::new();
+
+    for dependency in &rockspec.dependencies {
+        if dependency != "lua" && dependency != "luajit" {
+            propagated_build_inputs.insert(dependency.clone());
+        }
+    }
+    for external in &rockspec.external_dependencies {
+        build_inputs.insert(
+            lookup_external_dependency(external)
+                .map(str::to_owned)
+                .unwrap_or_else(|| external.to_lowercase()),
+        );
+    }
+
+    (
+        propagated_build_inputs.into_iter().collect(),
+        build_inputs.into_iter().collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,4 +457,101 @@ version = "1.0-1"
         let version = infer_lua_version(&rockspec);
         assert_eq!(version, LuaVersion::Lua54); // Default
     }
+
+    #[test]
+    fn test_parse_rockspec() {
+        let temp_dir = TempDir::new().unwrap();
+        let rockspec = temp_dir.path().join("luasec-1.3.2-1.rockspec");
+        fs::write(
+            &rockspec,
+            r#"
+package = "LuaSec"
+version = "1.3.2-1"
+source = {
+  url = "git+https://github.com/brunoos/luasec",
+  tag = "v1.3.2",
+}
+description = {
+   summary = "A binding for OpenSSL library to provide TLS/SSL communication over LuaSocket.",
+   homepage = "https://github.com/brunoos/luasec/wiki",
+   license = "MIT"
+}
+-- LuaSocket provides the transport
+dependencies = {
+   "lua >= 5.1", "LuaSocket"
+}
+external_dependencies = {
+   platforms = {
+      windows = { OPENSSL = { header = "openssl/ssl.h" } }
+   },
+   OPENSSL = {
+      header = "openssl/ssl.h",
+      library = "ssl"
+   }
+}
+build = {
+  type = "builtin",
+  modules = { ssl = "src/ssl.c" }
+}
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_rockspec(&rockspec).unwrap();
+        assert_eq!(parsed.package.as_deref(), Some("LuaSec"));
+        assert_eq!(parsed.version.as_deref(), Some("1.3.2-1"));
+        assert_eq!(
+            parsed.source_url.as_deref(),
+            Some("git+https://github.com/brunoos/luasec")
+        );
+        assert_eq!(parsed.source_tag.as_deref(), Some("v1.3.2"));
+        assert_eq!(parsed.license.as_deref(), Some("MIT"));
+        assert_eq!(parsed.dependencies, vec!["lua", "luasocket"]);
+        assert_eq!(parsed.external_dependencies, vec!["OPENSSL"]);
+
+        let (propagated, build) = map_rockspec_dependencies_to_nix(&parsed);
+        assert_eq!(propagated, vec!["luasocket"]);
+        assert_eq!(build, vec!["openssl"]);
+    }
+
+    #[test]
+    fn test_parse_manifest_versions() {
+        let manifest = r#"
+commands = {}
+modules = {}
+repository = {
+   lpeg = {
+      ["1.0.2-1"] = {
+         {
+            arch = "rockspec"
+         }
+      },
+      ["1.1.0-1"] = {
+         {
+            arch = "rockspec"
+         },
+         {
+            arch = "src"
+         }
+      }
+   },
+   ["lua-cjson"] = {
+      ["2.1.0.10-1"] = {
+         {
+            arch = "rockspec"
+         }
+      }
+   }
+}
+"#;
+        assert_eq!(
+            parse_manifest_versions(manifest, "lpeg"),
+            vec!["1.0.2-1", "1.1.0-1"]
+        );
+        assert_eq!(
+            parse_manifest_versions(manifest, "lua-cjson"),
+            vec!["2.1.0.10-1"]
+        );
+        assert!(parse_manifest_versions(manifest, "luasocket").is_empty());
+    }
 }
@@ -3,6 +3,7 @@
 //! This module reads a Maven project's `pom.xml` and extracts:
 //! 1. JDK version from compiler configuration properties
 //! 2. Native library dependencies for Maven artifacts (e.g., JDBC drivers)
+//! 3. Description, homepage, licenses and SCM of published POMs
 //!
 //! JDK version is inferred from Maven compiler plugin properties.
 //! Most Java dependencies are pure JVM and don't require native libraries,
@@ -141,6 +142,164 @@ pub fn infer_native_dependencies(pom_xml_path: &Path) -> (Vec<String>, Vec<Strin
     )
 }
 
+/// Project metadata of a POM, as published to Maven Central.
+#[derive(Debug, Default, PartialEq)]
+pub struct PomMetadata {
+    pub description: Option<String>,
+    /// Project `<url>`
+    pub url: Option<String>,
+    /// `<name>` of each `<license>`
+    pub licenses: Vec<String>,
+    /// `<scm><connection>` without its `scm:git:` prefix, or `<scm><url>`
+    pub scm_url: Option<String>,
+    /// `<scm><tag>`, unless it is the default "HEAD"
+    pub scm_tag: Option<String>,
+}
+
+/// Remove every `<tag>...</tag>` element from `contents`.
+fn strip_xml_elements(contents: &str, tag: &str) -> String {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut stripped = String::new();
+    let mut rest = contents;
+    while let Some(start) = rest.find(&open) {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start..].find(&close) {
+            Some(end) => &rest[start + end + close.len()..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Text of the first `<tag>` element in `contents`.
+fn xml_element<'a>(contents: &'a str, tag: &str) -> Option<&'a str> {
+    let start = contents.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = contents[start..].find(&format!("</{}>", tag))?;
+    Some(contents[start..start + end].trim())
+}
+
+/// Read description, homepage, licenses and SCM from a POM.
+///
+/// Elements that carry their own `<url>`, `<name>` or `<version>` (parent,
+/// developers, build, ...) are skipped so that only the project's are
+/// read. `${project.version}` and `${project.artifactId}` are expanded in
+/// the SCM URL and tag.
+pub fn parse_pom_metadata(pom_xml_path: &Path) -> Option<PomMetadata> {
+    let contents = match std::fs::read_to_string(pom_xml_path) {
+        Ok(c) => c,
+        Err(e) => {
+            debug!(
+                target: LOG_TARGET,
+                "failed to read pom.xml: {}", e
+            );
+            return None;
+        }
+    };
+
+    let mut project = regex::Regex::new(r"(?s)<!--.*?-->")
+        .unwrap()
+        .replace_all(&contents, "")
+        .into_owned();
+    let licenses = xml_element(&project, "licenses").map(str::to_owned);
+    let scm = xml_element(&project, "scm").map(str::to_owned);
+    for nested in [
+        "parent",
+        "organization",
+        "licenses",
+        "developers",
+        "contributors",
+        "mailingLists",
+        "scm",
+        "issueManagement",
+        "ciManagement",
+        "distributionManagement",
+        "properties",
+        "dependencyManagement",
+        "dependencies",
+        "repositories",
+        "pluginRepositories",
+        "build",
+        "reporting",
+        "profiles",
+        "modules",
+    ] {
+        project = strip_xml_elements(&project, nested);
+    }
+
+    let expand = |value: &str| {
+        let mut value = value.to_owned();
+        if let Some(version) = xml_element(&project, "version") {
+            value = value.replace("${project.version}", version);
+        }
+        if let Some(artifact_id) = xml_element(&project, "artifactId") {
+            value = value.replace("${project.artifactId}", artifact_id);
+        }
+        value
+    };
+
+    let mut metadata = PomMetadata {
+        description: xml_element(&project, "description")
+            .map(|d| d.split_whitespace().collect::<Vec<_>>().join(" ")),
+        url: xml_element(&project, "url").map(str::to_owned),
+        ..PomMetadata::default()
+    };
+    if let Some(licenses) = &licenses {
+        metadata.licenses = licenses
+            .split("</license>")
+            .filter_map(|license| xml_element(license, "name"))
+            .map(str::to_owned)
+            .collect();
+    }
+    if let Some(scm) = &scm {
+        // The connection is clonable, while `<url>` is often a web view
+        metadata.scm_url = xml_element(scm, "connection")
+            .or_else(|| xml_element(scm, "developerConnection"))
+            .and_then(|connection| connection.strip_prefix("scm:git:"))
+            .or_else(|| xml_element(scm, "url").filter(|url| url.starts_with("http")))
+            .map(expand);
+        metadata.scm_tag = xml_element(scm, "tag")
+            .filter(|tag| *tag != "HEAD")
+            .map(expand);
+    }
+
+    debug!(target: LOG_TARGET, "parsed POM metadata: {:?}", metadata);
+    Some(metadata)
+}
+
+/// SPDX identifier for the free-form `<name>` of a POM license, e.g.
+/// "The Apache Software License, Version 2.0".
+pub fn pom_license_to_spdx(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    let has = |words: &[&str]| words.iter().all(|word| name.contains(word));
+    let spdx = if has(&["apache"]) && has(&["2"]) {
+        "Apache-2.0"
+    } else if name.starts_with("mit") || has(&["mit license"]) {
+        "MIT"
+    } else if has(&["eclipse public license"]) || name.starts_with("epl") {
+        match has(&["2"]) {
+            true => "EPL-2.0",
+            false => "EPL-1.0",
+        }
+    } else if has(&["bsd"]) {
+        match has(&["2"]) && !has(&["3"]) {
+            true => "BSD-2-Clause",
+            false => "BSD-3-Clause",
+        }
+    } else if has(&["lesser"]) || name.starts_with("lgpl") {
+        match has(&["3"]) {
+            true => "LGPL-3.0-only",
+            false => "LGPL-2.1-only",
+        }
+    } else if has(&["mozilla public license"]) || name.starts_with("mpl") {
+        "MPL-2.0"
+    } else {
+        return None;
+    };
+    Some(spdx)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,4 +326,80 @@ mod tests {
         let version = infer_jdk_version(Path::new("/nonexistent/pom.xml"));
         assert_eq!(version, "jdk21");
     }
+
+    #[test]
+    fn test_parse_pom_metadata() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let pom = temp_dir.path().join("commons-text-1.12.0.pom");
+        std::fs::write(
+            &pom,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<project>
+  <parent>
+    <groupId>org.apache.commons</groupId>
+    <artifactId>commons-parent</artifactId>
+    <version>69</version>
+  </parent>
+  <artifactId>commons-text</artifactId>
+  <version>1.12.0</version>
+  <name>Apache Commons Text</name>
+  <url>https://commons.apache.org/proper/commons-text</url>
+  <description>Apache Commons Text is a set of utility functions
+    and reusable components.</description>
+  <organization><url>https://www.apache.org/</url></organization>
+  <licenses>
+    <license>
+      <name>Apache-2.0</name>
+      <url>https://www.apache.org/licenses/LICENSE-2.0.txt</url>
+    </license>
+  </licenses>
+  <scm>
+    <connection>scm:git:https://gitbox.apache.org/repos/asf/commons-text</connection>
+    <url>https://gitbox.apache.org/repos/asf?p=commons-text.git</url>
+    <tag>rel/${project.artifactId}-${project.version}</tag>
+  </scm>
+  <dependencies>
+    <dependency>
+      <groupId>org.xerial</groupId>
+      <artifactId>sqlite-jdbc</artifactId>
+    </dependency>
+  </dependencies>
+</project>
+"#,
+        )
+        .unwrap();
+
+        let metadata = parse_pom_metadata(&pom).unwrap();
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("Apache Commons Text is a set of utility functions and reusable components.")
+        );
+        assert_eq!(
+            metadata.url.as_deref(),
+            Some("https://commons.apache.org/proper/commons-text")
+        );
+        assert_eq!(metadata.licenses, vec!["Apache-2.0"]);
+        assert_eq!(
+            metadata.scm_url.as_deref(),
+            Some("https://gitbox.apache.org/repos/asf/commons-text")
+        );
+        assert_eq!(metadata.scm_tag.as_deref(), Some("rel/commons-text-1.12.0"));
+
+        let (build_inputs, _) = infer_native_dependencies(&pom);
+        assert_eq!(build_inputs, vec!["sqlite"]);
+    }
+
+    #[test]
+    fn test_pom_license_to_spdx() {
+        assert_eq!(
+            pom_license_to_spdx("The Apache Software License, Version 2.0"),
+            Some("Apache-2.0")
+        );
+        assert_eq!(pom_license_to_spdx("MIT License"), Some("MIT"));
+        assert_eq!(
+            pom_license_to_spdx("Eclipse Public License - v 2.0"),
+            Some("EPL-2.0")
+        );
+        assert_eq!(pom_license_to_spdx("Proprietary"), None);
+    }
 }
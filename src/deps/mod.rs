@@ -1,3 +1,4 @@
+pub mod appimage;
 pub mod buildsystem;
 pub mod clojure;
 pub mod dart;
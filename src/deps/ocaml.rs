@@ -3,11 +3,13 @@
 //! This module reads OCaml project files to:
 //! 1. Extract package name from dune-project or .opam files
 //! 2. Extract OCaml version constraints (reserved for future use)
+//! 3. Read sources and dependencies from opam files of opam-repository
 //!
 //! OCaml projects typically use the Dune build system and OPAM package manager.
 //! Package metadata can be found in either dune-project (modern) or .opam files (legacy).
 
 use log::debug;
+use std::collections::BTreeSet;
 use std::path::Path;
 
 const LOG_TARGET: &str = "nix-template::ocaml_deps";
@@ -122,6 +124,395 @@ pub fn extract_ocaml_version(dune_project_path: &Path) -> Option<String> {
     None
 }
 
+/// The fields of an opam file needed to package it.
+#[derive(Debug, Default, PartialEq)]
+pub struct OpamFile {
+    pub synopsis: Option<String>,
+    pub homepage: Option<String>,
+    /// SPDX identifiers
+    pub licenses: Vec<String>,
+    /// `src` of the `url { }` section
+    pub src: Option<String>,
+    /// `checksum` of the `url { }` section, e.g. "sha256=<hex>"
+    pub checksums: Vec<String>,
+    pub depends: Vec<OpamDependency>,
+    /// `depexts` for NixOS, which are already nixpkgs attributes
+    pub nixos_depexts: Vec<String>,
+    /// `depexts` for Debian and derivatives
+    pub debian_depexts: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OpamDependency {
+    pub name: String,
+    /// Only needed at build time (`{build}`)
+    pub build: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OpamToken {
+    String(String),
+    Ident(String),
+    Symbol(char),
+}
+
+#[derive(Debug, PartialEq)]
+enum OpamValue {
+    String(String),
+    Ident(String),
+    Operator(char),
+    List(Vec<OpamValue>),
+    /// Parenthesised formula, e.g. `("lwt" | "async")`
+    Group(Vec<OpamValue>),
+    /// A value with a filter, e.g. `"alcotest" {with-test}`
+    Filtered(Box<OpamValue>, Vec<OpamValue>),
+    Section(Vec<(String, OpamValue)>),
+}
+
+fn tokenize_opam(contents: &str) -> Vec<OpamToken> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' if chars.peek() == Some(&'*') => {
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == ')' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                let triple = contents_starts_with(&chars, "\"\"");
+                if triple {
+                    chars.next();
+                    chars.next();
+                }
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next()),
+                        '"' if !triple => break,
+                        '"' if contents_starts_with(&chars, "\"\"") => {
+                            chars.next();
+                            chars.next();
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                tokens.push(OpamToken::String(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || "_-+.".contains(c)) {
+                        break;
+                    }
+                    ident.push(c);
+                    chars.next();
+                }
+                tokens.push(OpamToken::Ident(ident));
+            }
+            c => tokens.push(OpamToken::Symbol(c)),
+        }
+    }
+    tokens
+}
+
+fn contents_starts_with(chars: &std::iter::Peekable<std::str::Chars>, prefix: &str) -> bool {
+    chars.clone().take(prefix.len()).eq(prefix.chars())
+}
+
+/// Parse one value, with its filter if any. Returns None at a closing
+/// symbol or the end of input.
+fn parse_opam_value(tokens: &[OpamToken], i: &mut usize) -> Option<OpamValue> {
+    let value = match tokens.get(*i)? {
+        OpamToken::Symbol(']' | ')' | '}') => return None,
+        OpamToken::Symbol('[') => {
+            *i += 1;
+            OpamValue::List(parse_opam_values(tokens, i))
+        }
+        OpamToken::Symbol('(') => {
+            *i += 1;
+            OpamValue::Group(parse_opam_values(tokens, i))
+        }
+        OpamToken::Symbol(c) => {
+            *i += 1;
+            OpamValue::Operator(*c)
+        }
+        OpamToken::String(s) => {
+            *i += 1;
+            OpamValue::String(s.clone())
+        }
+        OpamToken::Ident(s) => {
+            *i += 1;
+            OpamValue::Ident(s.clone())
+        }
+    };
+    if tokens.get(*i) == Some(&OpamToken::Symbol('{')) {
+        *i += 1;
+        let filter = parse_opam_values(tokens, i);
+        return Some(OpamValue::Filtered(Box::new(value), filter));
+    }
+    Some(value)
+}
+
+/// Parse values up to and including the next closing symbol.
+fn parse_opam_values(tokens: &[OpamToken], i: &mut usize) -> Vec<OpamValue> {
+    let mut values = Vec::new();
+    while let Some(value) = parse_opam_value(tokens, i) {
+        values.push(value);
+    }
+    *i += 1;
+    values
+}
+
+/// Parse `field: value` pairs and `section { ... }` blocks up to the
+/// closing `}` of the enclosing section (or the end of input).
+fn parse_opam_fields(tokens: &[OpamToken], i: &mut usize) -> Vec<(String, OpamValue)> {
+    let mut fields = Vec::new();
+    while let Some(token) = tokens.get(*i) {
+        *i += 1;
+        let name = match token {
+            OpamToken::Ident(name) => name.clone(),
+            OpamToken::Symbol('}') => break,
+            _ => continue,
+        };
+        match (tokens.get(*i), tokens.get(*i + 1)) {
+            (Some(OpamToken::Symbol(':')), _) => {
+                *i += 1;
+                if let Some(value) = parse_opam_value(tokens, i) {
+                    fields.push((name, value));
+                }
+            }
+            (Some(OpamToken::Symbol('{')), _) => {
+                *i += 1;
+                fields.push((name, OpamValue::Section(parse_opam_fields(tokens, i))));
+            }
+            (Some(OpamToken::String(_)), Some(OpamToken::Symbol('{'))) => {
+                *i += 2;
+                fields.push((name, OpamValue::Section(parse_opam_fields(tokens, i))));
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn opam_strings(value: &OpamValue) -> Vec<String> {
+    match value {
+        OpamValue::String(s) => vec![s.clone()],
+        OpamValue::List(values) => values.iter().flat_map(opam_strings).collect(),
+        OpamValue::Filtered(value, _) => opam_strings(value),
+        _ => Vec::new(),
+    }
+}
+
+fn filter_mentions(filter: &[OpamValue], word: &str) -> bool {
+    filter.iter().any(|value| match value {
+        OpamValue::Ident(s) | OpamValue::String(s) => s == word,
+        OpamValue::Group(values) => filter_mentions(values, word),
+        _ => false,
+    })
+}
+
+/// Collect the packages of a `depends` formula. Test, documentation and
+/// development dependencies are skipped, and only the first alternative of
+/// `|` is kept.
+fn collect_opam_depends(values: &[OpamValue], build: bool, depends: &mut Vec<OpamDependency>) {
+    for value in values {
+        match value {
+            OpamValue::String(name) => depends.push(OpamDependency {
+                name: name.clone(),
+                build,
+            }),
+            OpamValue::Filtered(value, filter) => {
+                let optional = ["with-test", "with-doc", "with-dev-setup", "dev"]
+                    .iter()
+                    .any(|word| filter_mentions(filter, word));
+                if !optional {
+                    let build = build || filter_mentions(filter, "build");
+                    collect_opam_depends(std::slice::from_ref(value), build, depends);
+                }
+            }
+            OpamValue::Group(values) => {
+                let first_alternative = values
+                    .split(|value| *value == OpamValue::Operator('|'))
+                    .next()
+                    .unwrap_or(&[]);
+                collect_opam_depends(first_alternative, build, depends);
+            }
+            OpamValue::List(values) => collect_opam_depends(values, build, depends),
+            _ => {}
+        }
+    }
+}
+
+/// Read an opam file (version 2.0 format).
+///
+/// ```text
+/// synopsis: "OCaml Format pretty-printer combinators"
+/// depends: [ "ocaml" {>= "4.08"} "dune" {>= "3.0"} "alcotest" {with-test} ]
+/// depexts: [ ["libgmp-dev"] {os-family = "debian"} ]
+/// url {
+///   src: "https://example.org/fmt-0.9.0.tbz"
+///   checksum: [ "sha256=..." "sha512=..." ]
+/// }
+/// ```
+pub fn parse_opam_file(opam_path: &Path) -> Option<OpamFile> {
+    let contents = match std::fs::read_to_string(opam_path) {
+        Ok(c) => c,
+        Err(e) => {
+            debug!(target: LOG_TARGET, "failed to read opam file: {}", e);
+            return None;
+        }
+    };
+
+    let tokens = tokenize_opam(&contents);
+    let mut opam = OpamFile::default();
+    for (name, value) in parse_opam_fields(&tokens, &mut 0) {
+        match (name.as_str(), value) {
+            ("synopsis", OpamValue::String(s)) => opam.synopsis = Some(s),
+            ("homepage", OpamValue::String(s)) => opam.homepage = Some(s),
+            ("license", value) => opam.licenses = opam_strings(&value),
+            ("url", OpamValue::Section(fields)) => {
+                for (name, value) in fields {
+                    match (name.as_str(), value) {
+                        ("src", OpamValue::String(s)) => opam.src = Some(s),
+                        ("checksum", value) => opam.checksums = opam_strings(&value),
+                        _ => {}
+                    }
+                }
+            }
+            ("depends", OpamValue::List(values)) => {
+                collect_opam_depends(&values, false, &mut opam.depends)
+            }
+            ("depexts", OpamValue::List(values)) => {
+                for value in values {
+                    if let OpamValue::Filtered(packages, filter) = value {
+                        if filter_mentions(&filter, "nixos") {
+                            opam.nixos_depexts.extend(opam_strings(&packages));
+                        } else if filter_mentions(&filter, "debian") {
+                            opam.debian_depexts.extend(opam_strings(&packages));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    debug!(target: LOG_TARGET, "parsed opam file: {:?}", opam);
+    Some(opam)
+}
+
+/// Map a system package, as named by Debian or by an opam `conf-*`
+/// package, to nixpkgs.
+///
+/// Returns the attribute and whether it is a build tool.
+fn lookup_system_package(name: &str) -> Option<(&'static str, bool)> {
+    match name {
+        "libgmp-dev" | "gmp" => Some(("gmp", false)),
+        "libmpfr-dev" | "mpfr" => Some(("mpfr", false)),
+        "libssl-dev" | "libssl" | "openssl" => Some(("openssl", false)),
+        "libffi-dev" | "libffi" => Some(("libffi", false)),
+        "zlib1g-dev" | "zlib" => Some(("zlib", false)),
+        "libev-dev" | "libev" => Some(("libev", false)),
+        "libuv1-dev" | "libuv" => Some(("libuv", false)),
+        "libsqlite3-dev" | "sqlite3" => Some(("sqlite", false)),
+        "libcurl4-gnutls-dev" | "libcurl4-openssl-dev" | "libcurl" => Some(("curl", false)),
+        "libpcre3-dev" | "libpcre" => Some(("pcre", false)),
+        "libpcre2-dev" | "libpcre2" => Some(("pcre2", false)),
+        "libzstd-dev" | "zstd" => Some(("zstd", false)),
+        "liblz4-dev" | "liblz4" => Some(("lz4", false)),
+        "libsodium-dev" | "libsodium" => Some(("libsodium", false)),
+        "libyaml-dev" | "libyaml" => Some(("libyaml", false)),
+        "libxml2-dev" | "libxml2" => Some(("libxml2", false)),
+        "libexpat1-dev" | "expat" => Some(("expat", false)),
+        "libpq-dev" | "postgresql" => Some(("libpq", false)),
+        "libcairo2-dev" | "cairo2" => Some(("cairo", false)),
+        "libgtk-3-dev" | "gtk3" => Some(("gtk3", false)),
+        "libsdl2-dev" | "sdl2" => Some(("SDL2", false)),
+        "pkg-config" | "pkgconf" => Some(("pkg-config", true)),
+        "cmake" => Some(("cmake", true)),
+        "autoconf" => Some(("autoconf", true)),
+        "m4" => Some(("m4", true)),
+        "perl" => Some(("perl", true)),
+        "python3" | "python-3" => Some(("python3", true)),
+        "which" => Some(("which", true)),
+        _ => None,
+    }
+}
+
+/// Map the dependencies of an opam file to nixpkgs.
+///
+/// `depends` become `ocamlPackages` attributes, which mostly share their
+/// opam name, and `conf-*` packages and `depexts` become system
+/// libraries. The compiler, dune and `base-*` packages are provided by
+/// `buildDunePackage`.
+///
+/// Returns a tuple of (propagatedBuildInputs, buildInputs, nativeBuildInputs).
+pub fn map_opam_dependencies_to_nix(opam: &OpamFile) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut propagated_build_inputs = BTreeSet::new();
+    let mut build_inputs = BTreeSet::new();
+    let mut native_build_inputs = BTreeSet::new();
+
+    let mut system_packages = Vec::new();
+
+    for dependency in &opam.depends {
+        let name = dependency.name.as_str();
+        if name == "ocaml" || name == "dune" || name.starts_with("base-") {
+            continue;
+        }
+        if let Some(conf) = name.strip_prefix("conf-") {
+            system_packages.push(conf);
+            continue;
+        }
+        let attr = match name {
+            "ocamlfind" => "findlib",
+            name => name,
+        };
+        if dependency.build {
+            native_build_inputs.insert(attr.to_string());
+        } else {
+            propagated_build_inputs.insert(attr.to_string());
+        }
+    }
+
+    if opam.nixos_depexts.is_empty() {
+        system_packages.extend(opam.debian_depexts.iter().map(String::as_str));
+    } else {
+        build_inputs.extend(opam.nixos_depexts.iter().cloned());
+    }
+
+    for name in system_packages {
+        match lookup_system_package(name) {
+            Some((attr, true)) => native_build_inputs.insert(attr.to_string()),
+            Some((attr, false)) => build_inputs.insert(attr.to_string()),
+            None => {
+                debug!(target: LOG_TARGET, "no nixpkgs mapping for system package {}", name);
+                continue;
+            }
+        };
+    }
+
+    (
+        propagated_build_inputs.into_iter().collect(),
+        build_inputs.into_iter().collect(),
+        native_build_inputs.into_iter().collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +577,106 @@ mod tests {
         assert!(version.is_some());
         // The exact parsing depends on the implementation
     }
+
+    #[test]
+    fn test_parse_opam_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let opam_file = temp_dir.path().join("zarith.opam");
+        fs::write(
+            &opam_file,
+            r#"
+opam-version: "2.0"
+synopsis: "Arbitrary precision integers"
+description: """
+Implements arithmetic and logical operations over
+arbitrary-precision integers."""
+license: ["LGPL-2.0-only" "OCaml-LGPL-linking-exception"]
+homepage: "https://github.com/ocaml/Zarith"
+# comments are ignored
+depends: [
+  "ocaml" {>= "4.07.0"}
+  "ocamlfind" {build}
+  "conf-gmp"
+  "conf-pkg-config" {build}
+  "stdlib-shims"
+  ("lwt" | "async")
+  "alcotest" {with-test & >= "1.0"}
+  "odoc" {with-doc}
+]
+depexts: [
+  ["libgmp-dev"] {os-family = "debian"}
+  ["gmp"] {os = "macos" & os-distribution = "homebrew"}
+]
+url {
+  src: "https://github.com/ocaml/Zarith/archive/release-1.13.tar.gz"
+  checksum: [
+    "md5=0f2e4e8c1ee9d1e9e1b0e7fdbd6b0a2c"
+    "sha256=a5826d33fea0103ad6e66f92583d8e075fb77976de893ffdd73ada0409b3f83b"
+  ]
+}
+"#,
+        )
+        .unwrap();
+
+        let opam = parse_opam_file(&opam_file).unwrap();
+        assert_eq!(
+            opam.synopsis.as_deref(),
+            Some("Arbitrary precision integers")
+        );
+        assert_eq!(
+            opam.homepage.as_deref(),
+            Some("https://github.com/ocaml/Zarith")
+        );
+        assert_eq!(
+            opam.licenses,
+            vec!["LGPL-2.0-only", "OCaml-LGPL-linking-exception"]
+        );
+        assert_eq!(
+            opam.src.as_deref(),
+            Some("https://github.com/ocaml/Zarith/archive/release-1.13.tar.gz")
+        );
+        assert_eq!(opam.checksums.len(), 2);
+        let names: Vec<&str> = opam.depends.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "ocaml",
+                "ocamlfind",
+                "conf-gmp",
+                "conf-pkg-config",
+                "stdlib-shims",
+                "lwt"
+            ]
+        );
+        assert_eq!(opam.debian_depexts, vec!["libgmp-dev"]);
+        assert!(opam.nixos_depexts.is_empty());
+
+        let (propagated, build, native) = map_opam_dependencies_to_nix(&opam);
+        assert_eq!(propagated, vec!["lwt", "stdlib-shims"]);
+        assert_eq!(build, vec!["gmp"]);
+        assert_eq!(native, vec!["findlib", "pkg-config"]);
+    }
+
+    #[test]
+    fn test_nixos_depexts_are_preferred() {
+        let temp_dir = TempDir::new().unwrap();
+        let opam_file = temp_dir.path().join("opam");
+        fs::write(
+            &opam_file,
+            r#"
+depends: ["dune" {>= "3.0"} "ctypes"]
+depexts: [
+  ["libffi-dev"] {os-family = "debian"}
+  ["libffi"] {os-distribution = "nixos"}
+]
+"#,
+        )
+        .unwrap();
+
+        let opam = parse_opam_file(&opam_file).unwrap();
+        let (propagated, build, native) = map_opam_dependencies_to_nix(&opam);
+        assert_eq!(propagated, vec!["ctypes"]);
+        assert_eq!(build, vec!["libffi"]);
+        assert!(native.is_empty());
+    }
 }
@@ -59,6 +59,14 @@ pub fn parse_meta_json(meta_json_path: &Path) -> Option<Vec<String>> {
         }
     };
 
+    meta_json_requires(&parsed)
+}
+
+/// Collect the runtime dependencies of already parsed META.json metadata,
+/// such as the `metadata` MetaCPAN returns for a release.
+///
+/// Returns None if the metadata lists no runtime dependencies.
+pub fn meta_json_requires(parsed: &serde_json::Value) -> Option<Vec<String>> {
     // Extract runtime dependencies from prereqs.runtime.requires
     let mut deps = Vec::new();
     if let Some(prereqs) = parsed.get("prereqs") {
@@ -154,6 +162,119 @@ pub fn parse_meta_yml(meta_yml_path: &Path) -> Option<Vec<String>> {
     }
 }
 
+/// Modules shipped with perl itself, which need no `perlPackages` entry.
+///
+/// Not exhaustive (see `corelist`), but covers what CPAN distributions
+/// commonly require.
+const CORE_MODULES: &[&str] = &[
+    "Archive::Tar",
+    "B",
+    "Carp",
+    "Compress::Zlib",
+    "Config",
+    "CPAN::Meta",
+    "Cwd",
+    "Data::Dumper",
+    "Digest::MD5",
+    "Digest::SHA",
+    "DynaLoader",
+    "Encode",
+    "English",
+    "Errno",
+    "Exporter",
+    "ExtUtils::MakeMaker",
+    "Fcntl",
+    "File::Basename",
+    "File::Copy",
+    "File::Find",
+    "File::Path",
+    "File::Spec",
+    "File::Spec::Functions",
+    "File::Temp",
+    "File::stat",
+    "FindBin",
+    "Getopt::Long",
+    "HTTP::Tiny",
+    "Hash::Util",
+    "I18N::LangTags",
+    "IO::File",
+    "IO::Handle",
+    "IO::Select",
+    "IO::Socket",
+    "IO::Socket::IP",
+    "IPC::Cmd",
+    "IPC::Open2",
+    "IPC::Open3",
+    "JSON::PP",
+    "List::Util",
+    "Locale::Maketext",
+    "MIME::Base64",
+    "Math::BigInt",
+    "Module::Load",
+    "Module::Metadata",
+    "POSIX",
+    "Pod::Usage",
+    "Scalar::Util",
+    "Socket",
+    "Storable",
+    "Sub::Util",
+    "Symbol",
+    "Sys::Hostname",
+    "Term::ANSIColor",
+    "Test::Builder",
+    "Test::Harness",
+    "Test::More",
+    "Test::Simple",
+    "Text::Abbrev",
+    "Text::Balanced",
+    "Text::ParseWords",
+    "Text::Wrap",
+    "Tie::Hash",
+    "Time::HiRes",
+    "Time::Local",
+    "Time::Piece",
+    "Unicode::Normalize",
+    "XSLoader",
+    "base",
+    "bytes",
+    "constant",
+    "feature",
+    "if",
+    "integer",
+    "lib",
+    "mro",
+    "overload",
+    "parent",
+    "perl",
+    "strict",
+    "threads",
+    "utf8",
+    "vars",
+    "version",
+    "warnings",
+];
+
+/// Whether `module` ships with perl itself.
+pub fn is_core_module(module: &str) -> bool {
+    CORE_MODULES.contains(&module)
+}
+
+/// nixpkgs `perlPackages` attribute for a module, e.g. "Foo::Bar" → "FooBar".
+pub fn module_to_attr(module: &str) -> String {
+    module.replace("::", "").replace('-', "")
+}
+
+/// Map module requirements to deduplicated `perlPackages` attributes,
+/// skipping core modules.
+pub fn modules_to_perl_packages(modules: &[String]) -> Vec<String> {
+    let attrs: std::collections::BTreeSet<String> = modules
+        .iter()
+        .filter(|module| !is_core_module(module))
+        .map(|module| module_to_attr(module))
+        .collect();
+    attrs.into_iter().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,4 +365,22 @@ version: 1.0.0
         let deps = parse_meta_yml(&meta_yml);
         assert_eq!(deps, None);
     }
+
+    #[test]
+    fn test_modules_to_perl_packages() {
+        assert_eq!(module_to_attr("Foo::Bar"), "FooBar");
+        assert_eq!(module_to_attr("Moose"), "Moose");
+
+        let modules = vec![
+            "Try::Tiny".to_string(),
+            "Scalar::Util".to_string(),
+            "perl".to_string(),
+            "Class::Load".to_string(),
+            "strict".to_string(),
+        ];
+        assert_eq!(
+            modules_to_perl_packages(&modules),
+            vec!["ClassLoad", "TryTiny"]
+        );
+    }
 }
@@ -17,6 +17,7 @@
 //! Multi-line values are indented with spaces.
 
 use log::debug;
+use std::collections::BTreeSet;
 use std::path::Path;
 
 const LOG_TARGET: &str = "nix-template::r_deps";
@@ -120,7 +121,23 @@ pub fn parse_r_dependencies(description_path: &Path) -> Vec<String> {
     }
 
     // Filter out "R" itself and common base packages
-    let base_packages = ["R", "methods", "stats", "utils", "graphics", "grDevices", "datasets", "base"];
+    let base_packages = [
+        "R",
+        "methods",
+        "stats",
+        "utils",
+        "graphics",
+        "grDevices",
+        "datasets",
+        "base",
+        "grid",
+        "parallel",
+        "splines",
+        "stats4",
+        "tools",
+        "tcltk",
+        "compiler",
+    ];
     deps.retain(|pkg| !base_packages.contains(&pkg.as_str()));
 
     debug!(target: LOG_TARGET, "parsed {} R package dependencies", deps.len());
@@ -130,7 +147,7 @@ pub fn parse_r_dependencies(description_path: &Path) -> Vec<String> {
 /// Parse a single field from DCF format.
 ///
 /// Handles multi-line fields where continuation lines start with whitespace.
-fn parse_dcf_field(contents: &str, field_name: &str) -> Option<String> {
+pub fn parse_dcf_field(contents: &str, field_name: &str) -> Option<String> {
     let mut lines = contents.lines();
     let mut field_value = String::new();
     let mut in_field = false;
@@ -185,6 +202,92 @@ fn extract_package_names(dep_string: &str) -> Vec<String> {
         .collect()
 }
 
+/// nixpkgs `rPackages` attribute for an R package, e.g. "data.table" →
+/// "data_table".
+pub fn r_package_attr(name: &str) -> String {
+    name.replace('.', "_")
+}
+
+/// Static mapping from a `SystemRequirements` token to its nixpkgs
+/// (`buildInputs`, `nativeBuildInputs`) requirements.
+fn lookup_system_requirement(
+    token: &str,
+) -> Option<(&'static [&'static str], &'static [&'static str])> {
+    match token {
+        "libxml2" | "libxml-2.0" => Some((&["libxml2"], &["pkg-config"])),
+        "gdal" => Some((&["gdal"], &[])),
+        "geos" => Some((&["geos"], &[])),
+        "proj" => Some((&["proj"], &[])),
+        "udunits" | "udunits-2" | "udunits2" => Some((&["udunits"], &[])),
+        "libcurl" => Some((&["curl"], &[])),
+        "openssl" => Some((&["openssl"], &[])),
+        "zlib" => Some((&["zlib"], &[])),
+        "bzip2" | "libbz2" => Some((&["bzip2"], &[])),
+        "liblzma" | "xz" => Some((&["xz"], &[])),
+        "zstd" => Some((&["zstd"], &[])),
+        "gsl" => Some((&["gsl"], &[])),
+        "fftw" | "fftw3" => Some((&["fftw"], &[])),
+        "gmp" => Some((&["gmp"], &[])),
+        "mpfr" => Some((&["mpfr"], &[])),
+        "glpk" => Some((&["glpk"], &[])),
+        "hdf5" => Some((&["hdf5"], &[])),
+        "netcdf" => Some((&["netcdf"], &[])),
+        "sqlite3" | "sqlite" => Some((&["sqlite"], &[])),
+        "libpq" | "postgresql" => Some((&["libpq"], &[])),
+        "mariadb" | "mysql" => Some((&["libmysqlclient"], &[])),
+        "unixodbc" => Some((&["unixODBC"], &[])),
+        "imagemagick" | "magick++" => Some((&["imagemagick"], &["pkg-config"])),
+        "poppler" => Some((&["poppler"], &["pkg-config"])),
+        "cairo" => Some((&["cairo"], &["pkg-config"])),
+        "freetype2" | "freetype" => Some((&["freetype"], &["pkg-config"])),
+        "fontconfig" => Some((&["fontconfig"], &[])),
+        "harfbuzz" => Some((&["harfbuzz"], &["pkg-config"])),
+        "fribidi" => Some((&["fribidi"], &["pkg-config"])),
+        "libpng" => Some((&["libpng"], &[])),
+        "libjpeg" => Some((&["libjpeg"], &[])),
+        "libtiff" => Some((&["libtiff"], &[])),
+        "libwebp" => Some((&["libwebp"], &[])),
+        "libsodium" => Some((&["libsodium"], &[])),
+        "libssh2" => Some((&["libssh2"], &[])),
+        "libgit2" => Some((&["libgit2"], &[])),
+        "protobuf" => Some((&["protobuf"], &[])),
+        "jags" => Some((&["jags"], &[])),
+        "java" => Some((&[], &["jdk"])),
+        "pandoc" => Some((&[], &["pandoc"])),
+        "cmake" => Some((&[], &["cmake"])),
+        "pkg-config" => Some((&[], &["pkg-config"])),
+        _ => None,
+    }
+}
+
+/// Translate a `SystemRequirements` field into deduplicated
+/// (build_inputs, native_build_inputs).
+///
+/// The field is free-form text, e.g.
+/// `GDAL (>= 2.0.1), GEOS (>= 3.4.0), PROJ (>= 4.8.0), sqlite3`, so each word
+/// is looked up on its own and anything unknown is ignored.
+pub fn map_system_requirements(system_requirements: &str) -> (Vec<String>, Vec<String>) {
+    let mut build_inputs: BTreeSet<String> = BTreeSet::new();
+    let mut native_build_inputs: BTreeSet<String> = BTreeSet::new();
+
+    let lowercase = system_requirements.to_lowercase();
+    let tokens = lowercase
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'))
+        .map(|token| token.trim_matches(|c| c == '.' || c == '-'));
+    for token in tokens {
+        if let Some((bi, nbi)) = lookup_system_requirement(token) {
+            debug!(target: LOG_TARGET, "Mapped SystemRequirements '{}' to buildInputs={:?}, nativeBuildInputs={:?}", token, bi, nbi);
+            build_inputs.extend(bi.iter().map(|s| s.to_string()));
+            native_build_inputs.extend(nbi.iter().map(|s| s.to_string()));
+        }
+    }
+
+    (
+        build_inputs.into_iter().collect(),
+        native_build_inputs.into_iter().collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,4 +451,18 @@ Author: John Doe
         let missing = parse_dcf_field(contents, "License");
         assert_eq!(missing, None);
     }
+
+    #[test]
+    fn test_map_system_requirements() {
+        let (build, native) =
+            map_system_requirements("GDAL (>= 2.0.1), GEOS (>= 3.4.0), PROJ (>= 4.8.0), GNU make");
+        assert_eq!(build, vec!["gdal", "geos", "proj"]);
+        assert!(native.is_empty());
+
+        let (build, native) = map_system_requirements("libxml2 (>= 2.6.3)");
+        assert_eq!(build, vec!["libxml2"]);
+        assert_eq!(native, vec!["pkg-config"]);
+
+        assert_eq!(r_package_attr("data.table"), "data_table");
+    }
 }
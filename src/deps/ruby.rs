@@ -147,10 +147,16 @@ fn infer_from_source_path(source_path: &Path) -> Option<(Vec<String>, Vec<String
         gems.len()
     );
 
+    Some(map_gems_to_nix(&gems))
+}
+
+/// Map gem names (from a lockfile, or a gem's runtime dependencies) to
+/// deduplicated (build_inputs, native_build_inputs).
+pub fn map_gems_to_nix(gems: &[String]) -> (Vec<String>, Vec<String>) {
     let mut build_inputs: BTreeSet<String> = BTreeSet::new();
     let mut native_build_inputs: BTreeSet<String> = BTreeSet::new();
 
-    for gem in &gems {
+    for gem in gems {
         if let Some((bi, nbi)) = lookup_gem(gem) {
             debug!(target: LOG_TARGET, "Mapped gem '{}' to buildInputs={:?}, nativeBuildInputs={:?}", gem, bi, nbi);
             build_inputs.extend(bi.iter().map(|s| s.to_string()));
@@ -158,10 +164,48 @@ fn infer_from_source_path(source_path: &Path) -> Option<(Vec<String>, Vec<String
         }
     }
 
-    Some((
+    (
         build_inputs.into_iter().collect(),
         native_build_inputs.into_iter().collect(),
-    ))
+    )
+}
+
+/// Extract the `executables` list from a gem's YAML specification (the
+/// `metadata.gz` member of a .gem file).
+///
+/// ```text
+/// executables:
+/// - rails
+/// extensions: []
+/// ```
+pub fn parse_gemspec_executables(gemspec: &str) -> Vec<String> {
+    let unquote = |name: &str| {
+        name.trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_owned()
+    };
+    let mut lines = gemspec
+        .lines()
+        .skip_while(|line| !line.starts_with("executables:"));
+
+    // Inline lists, e.g. "executables: []"
+    let inline = match lines.next() {
+        Some(line) => line.trim_start_matches("executables:").trim(),
+        None => return Vec::new(),
+    };
+    if inline.starts_with('[') {
+        return inline
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(',')
+            .map(unquote)
+            .filter(|name| !name.is_empty())
+            .collect();
+    }
+
+    lines
+        .map_while(|line| line.strip_prefix("- "))
+        .map(unquote)
+        .collect()
 }
 
 /// Infer Ruby gem dependencies from a local source path.
@@ -240,6 +284,38 @@ DEPENDENCIES
         assert_eq!(result, Some((&["postgresql"] as &[&str], &[] as &[&str])));
     }
 
+    #[test]
+    fn map_runtime_dependencies() {
+        let gems = vec!["nokogiri".to_owned(), "racc".to_owned(), "pg".to_owned()];
+        let (build_inputs, native_build_inputs) = map_gems_to_nix(&gems);
+        assert_eq!(build_inputs, vec!["libxml2", "libxslt", "postgresql"]);
+        assert_eq!(native_build_inputs, vec!["pkg-config"]);
+    }
+
+    #[test]
+    fn parse_gemspec_executables_lists() {
+        let gemspec = r#"--- !ruby/object:Gem::Specification
+name: rubocop
+bindir: exe
+executables:
+- rubocop
+- "rubocop-lsp"
+extensions: []
+"#;
+        assert_eq!(
+            parse_gemspec_executables(gemspec),
+            vec!["rubocop", "rubocop-lsp"]
+        );
+        assert_eq!(
+            parse_gemspec_executables("name: rack\nexecutables: []\nextensions: []\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            parse_gemspec_executables("name: rack\n"),
+            Vec::<String>::new()
+        );
+    }
+
     #[test]
     fn lookup_unknown_gem() {
         assert_eq!(lookup_gem("unknown-gem-12345"), None);
@@ -277,6 +353,12 @@ DEPENDENCIES
             include_documentation_links: false,
             include_meta: true,
             tag_prefix: "".to_owned(),
+            tag_version_separator: "".to_owned(),
+            src_rev: "".to_owned(),
+            fetch_submodules: false,
+            fetch_lfs: false,
+            main_program: String::new(),
+            executables: Vec::new(),
             owner: "test".to_owned(),
             src_sha: "sha256-test".to_owned(),
             description: "test".to_owned(),
@@ -288,11 +370,13 @@ DEPENDENCIES
             pnpm_deps_hash: "".to_owned(),
             project_file: "".to_owned(),
             domain: "".to_owned(),
+            src_url: "".to_owned(),
             build_inputs: Vec::new(),
             native_build_inputs: Vec::new(),
             use_cargo_lock_file: false,
             cargo_lock_git_deps: Vec::new(),
             go_module_path: String::new(),
+            go_mod_root: String::new(),
             python_format: "setuptools".to_owned(),
             mvn_hash: FAKE_SRI_HASH.to_owned(),
             mix_fod_hash: FAKE_SRI_HASH.to_owned(),
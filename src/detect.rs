@@ -177,6 +177,7 @@ pub fn detect_template_candidates_from_path(source_path: &Path) -> Vec<Candidate
                 candidate.template = Template::Dart(crate::templates::types::DartConfig {
                     executables,
                     dart_version: None,
+                    flutter: false,
                 });
             }
             break;
@@ -504,9 +505,10 @@ fn find_rproj_file(source_path: &Path) -> Option<std::path::PathBuf> {
 
 /// Detect template candidates by materialising a remote source tree.
 ///
-/// If the fetcher is PyPI, short-circuits without materialising (we already
-/// know it's Python). Otherwise fetches the source into the Nix store and
-/// delegates to `detect_template_candidates_from_path`.
+/// If the fetcher is PyPI, crates.io or RubyGems, short-circuits without
+/// materialising (we already know it's Python, Rust or Ruby). Otherwise
+/// fetches the source into the Nix store and delegates to
+/// `detect_template_candidates_from_path`.
 pub fn detect_template_candidates(info: &ExpressionInfo) -> Vec<Candidate> {
     // PyPI short-circuit: we know it's Python, just classify package vs application.
     if info.fetcher == Fetcher::pypi {
@@ -516,6 +518,20 @@ pub fn detect_template_candidates(info: &ExpressionInfo) -> Vec<Candidate> {
         }];
     }
 
+    if info.fetcher == Fetcher::crates {
+        return vec![Candidate {
+            template: Template::rust(),
+            reason: "crates.io source",
+        }];
+    }
+
+    if info.fetcher == Fetcher::rubygems {
+        return vec![Candidate {
+            template: Template::Ruby,
+            reason: "RubyGems source",
+        }];
+    }
+
     eprintln!("Materialising source to detect project type...");
     let source_path = match source::materialise_source(info) {
         Some(p) => p,
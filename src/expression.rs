@@ -66,8 +66,18 @@ fn derivation_helper(info: &ExpressionInfo) -> (String, String) {
             crate::types::ElixirVariant::Library => {
                 ("beamPackages", "beamPackages.buildMix", Some("buildMix"))
             }
+            crate::types::ElixirVariant::Rebar3 => (
+                "beamPackages",
+                "beamPackages.buildRebar3",
+                Some("buildRebar3"),
+            ),
         },
         Template::Gradle(_) => ("gradle", "stdenv.mkDerivation", Some("stdenvMkDerivation")),
+        Template::Dart(config) if config.flutter => (
+            "flutter",
+            "flutter.buildFlutterApplication",
+            Some("buildFlutterApplication"),
+        ),
         Template::Dart(_) => (
             "buildDartApplication",
             "buildDartApplication",
@@ -128,6 +138,16 @@ fn derivation_helper(info: &ExpressionInfo) -> (String, String) {
             "rPackages.buildRPackage",
             Some("buildRPackage"),
         ),
+        Template::Binary(_) => (
+            "stdenvNoCC",
+            "stdenvNoCC.mkDerivation",
+            Some("stdenvNoCCMkDerivation"),
+        ),
+        Template::AppImage(_) => (
+            "appimageTools",
+            "appimageTools.wrapType2",
+            Some("appimageTools"),
+        ),
         Template::Test => ("", "", None), // Tests aren't a normal expression
         Template::Module => ("", "", None), // Modules aren't a normal expression
     };
@@ -146,16 +166,16 @@ fn fetch_block(fetcher: &Fetcher) -> (&'static str, &'static str) {
     owner = \"@owner@\";
     repo = \"@pname@\";
     rev = @rev@;
-    sha256 = \"@src_sha@\";
+    sha256 = \"@src_sha@\";@fetch_options@
   };",
         ),
         Fetcher::gitlab => (
             "fetchFromGitLab",
-            "  @doc:fetcher@src = fetchFromGitLab {
+            "  @doc:fetcher@src = fetchFromGitLab {@forge_domain@
     owner = \"@owner@\";
     repo = \"@pname@\";
     rev = @rev@;
-    sha256 = \"@src_sha@\";
+    sha256 = \"@src_sha@\";@fetch_options@
   };",
         ),
         Fetcher::gitea => (
@@ -165,21 +185,47 @@ fn fetch_block(fetcher: &Fetcher) -> (&'static str, &'static str) {
     owner = \"@owner@\";
     repo = \"@pname@\";
     rev = @rev@;
-    sha256 = \"@src_sha@\";
+    sha256 = \"@src_sha@\";@fetch_options@
+  };",
+        ),
+        Fetcher::sourcehut => (
+            "fetchFromSourcehut",
+            "  @doc:fetcher@src = fetchFromSourcehut {@forge_domain@
+    owner = \"@owner@\";
+    repo = \"@pname@\";
+    rev = @rev@;
+    hash = \"@src_sha@\";@fetch_options@
+  };",
+        ),
+        Fetcher::bitbucket => (
+            "fetchFromBitbucket",
+            "  @doc:fetcher@src = fetchFromBitbucket {
+    owner = \"@owner@\";
+    repo = \"@pname@\";
+    rev = @rev@;
+    hash = \"@src_sha@\";
+  };",
+        ),
+        Fetcher::git => (
+            "fetchgit",
+            "  @doc:fetcher@src = fetchgit {
+    url = \"@src_url@\";
+    rev = @rev@;
+    hash = \"@src_sha@\";@fetch_options@
   };",
         ),
         Fetcher::url => (
             "fetchurl",
             "  @doc:fetcher@src = fetchurl {
-    url = \"CHANGE\";
-    sha256 = \"0000000000000000000000000000000000000000000000000000\";
+    url = \"@src_url@\";
+    sha256 = \"@src_sha@\";
   };",
         ),
         Fetcher::zip => (
             "fetchzip",
             "  @doc:fetcher@src = fetchzip {
-    url = \"CHANGE\";
-    sha256 = \"0000000000000000000000000000000000000000000000000000\";
+    url = \"@src_url@\";
+    sha256 = \"@src_sha@\";
   };",
         ),
         Fetcher::pypi => (
@@ -188,6 +234,64 @@ fn fetch_block(fetcher: &Fetcher) -> (&'static str, &'static str) {
     pname = \"@pname@\";
     inherit (finalAttrs) version;
     sha256 = \"@src_sha@\";
+  };",
+        ),
+        Fetcher::crates => (
+            "fetchCrate",
+            "  @doc:fetcher@src = fetchCrate {
+    inherit (finalAttrs) pname version;
+    hash = \"@src_sha@\";
+  };",
+        ),
+        // Only reached when a Hackage release is packaged with a non-Haskell
+        // template; the Haskell template calls callHackageDirect instead,
+        // which fetches the same unpacked sdist and takes the same hash
+        Fetcher::hackage => (
+            "fetchzip",
+            "  @doc:fetcher@src = fetchzip {
+    url = \"mirror://hackage/@pname@-${finalAttrs.version}/@pname@-${finalAttrs.version}.tar.gz\";
+    hash = \"@src_sha@\";
+  };",
+        ),
+        // pub.dev archives have no top-level directory
+        Fetcher::pubdev => (
+            "fetchzip",
+            "  @doc:fetcher@src = fetchzip {
+    url = \"https://pub.dev/api/archives/@pname@-${finalAttrs.version}.tar.gz\";
+    stripRoot = false;
+    hash = \"@src_sha@\";
+  };",
+        ),
+        Fetcher::rubygems => (
+            "fetchurl",
+            "  @doc:fetcher@src = fetchurl {
+    url = \"https://rubygems.org/gems/@pname@-${finalAttrs.version}.gem\";
+    hash = \"@src_sha@\";
+  };",
+        ),
+        Fetcher::cpan => (
+            "fetchurl",
+            "  @doc:fetcher@src = fetchurl {
+    url = \"@src_url@\";
+    hash = \"@src_sha@\";
+  };",
+        ),
+        Fetcher::cran => (
+            "fetchurl",
+            "  @doc:fetcher@src = fetchurl {
+    urls = [
+      \"mirror://cran/src/contrib/@pname@_${finalAttrs.version}.tar.gz\"
+      \"mirror://cran/src/contrib/Archive/@pname@/@pname@_${finalAttrs.version}.tar.gz\"
+    ];
+    hash = \"@src_sha@\";
+  };",
+        ),
+        Fetcher::hex => (
+            "beamPackages",
+            "  @doc:fetcher@src = beamPackages.fetchHex {
+    pkg = \"@pname@\";
+    inherit (finalAttrs) version;
+    sha256 = \"@src_sha@\";
   };",
         ),
         Fetcher::local => ("", "  @doc:fetcher@src = ./..;"),
@@ -307,7 +411,13 @@ fn build_inputs(info: &ExpressionInfo) -> String {
             } else {
                 "  @doc:vendorHash@vendorHash = \"@vendor_hash@\";".to_owned()
             };
-            // Suggest ldflags when the Go module path is known (local mode).
+            // Modules nested in their repository build from their own directory
+            let mod_root = if info.go_mod_root.is_empty() {
+                String::new()
+            } else {
+                format!("  modRoot = \"{}\";\n", info.go_mod_root)
+            };
+            // Suggest ldflags when the Go module path is known.
             let ldflags = if info.go_module_path.is_empty() {
                 String::new()
             } else {
@@ -318,9 +428,10 @@ fn build_inputs(info: &ExpressionInfo) -> String {
             };
             format!(
                 "  @doc:buildDependencies@
-  {vendor_line}{native}{build}
+{mod_root}  {vendor_line}{native}{build}
 
   @doc:goSubPackages@subPackages = [ \".\" ];{ldflags}",
+                mod_root = mod_root,
                 vendor_line = vendor_line,
                 native = native,
                 build = build,
@@ -401,17 +512,23 @@ fn build_inputs(info: &ExpressionInfo) -> String {
                 "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
 
-            // mixFodDeps pattern for both Release and Library variants
-            let base = "  mixFodDeps = beamPackages.fetchMixDeps {\n    pname = \"${finalAttrs.pname}-deps\";\n    inherit (finalAttrs) version src;\n    @doc:mixFodHash@hash = \"@mix_fod_hash@\";\n  };";
+            // mixFodDeps pattern for applications and libraries built from
+            // their repository
+            let mix_fod_deps = "  mixFodDeps = beamPackages.fetchMixDeps {\n    pname = \"${finalAttrs.pname}-deps\";\n    inherit (finalAttrs) version src;\n    @doc:mixFodHash@hash = \"@mix_fod_hash@\";\n  };";
+            // Hex tarballs carry no mix.lock; their dependencies are other
+            // beamPackages
+            let beam_deps = if info.propagated_build_inputs.is_empty() {
+                "  beamDeps = [ ];"
+            } else {
+                "  beamDeps = with beamPackages; [@propagated_build_inputs@ ];"
+            };
 
-            match config.variant {
-                crate::types::ElixirVariant::Release => {
-                    format!("{base}{native}{build}", base = base, native = native, build = build)
-                }
-                crate::types::ElixirVariant::Library => {
-                    format!("{base}{native}{build}", base = base, native = native, build = build)
-                }
-            }
+            let base = match config.variant {
+                crate::types::ElixirVariant::Library if info.fetcher == Fetcher::hex => beam_deps,
+                crate::types::ElixirVariant::Rebar3 => beam_deps,
+                _ => mix_fod_deps,
+            };
+            format!("{base}{native}{build}", base = base, native = native, build = build)
         }
         Template::Gradle(config) => {
             // Conditionally render buildInputs only when inferred
@@ -450,7 +567,10 @@ fn build_inputs(info: &ExpressionInfo) -> String {
                 "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
 
-            let base = "  # Convert pubspec.lock to JSON format:\n  #   dart pub get\n  #   yq . pubspec.lock > pubspec.lock.json\n  # See: https://nixos.org/manual/nixpkgs/stable/#ssec-dart-applications\n  pubspecLock = lib.importJSON ./pubspec.lock.json;";
+            let base = match info.template.dart_config() {
+                Some(config) if config.flutter => "  # Convert pubspec.lock to JSON format:\n  #   flutter pub get\n  #   yq . pubspec.lock > pubspec.lock.json\n  # See: https://nixos.org/manual/nixpkgs/stable/#ssec-dart-flutter\n  pubspecLock = lib.importJSON ./pubspec.lock.json;",
+                _ => "  # Convert pubspec.lock to JSON format:\n  #   dart pub get\n  #   yq . pubspec.lock > pubspec.lock.json\n  # See: https://nixos.org/manual/nixpkgs/stable/#ssec-dart-applications\n  pubspecLock = lib.importJSON ./pubspec.lock.json;",
+            };
             format!("{base}{native}{build}", base = base, native = native, build = build)
         }
         Template::Haskell(_) => {
@@ -481,9 +601,21 @@ fn build_inputs(info: &ExpressionInfo) -> String {
             } else {
                 "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
+            // ocamlPackages dependencies, e.g. from the `depends` of an opam file
+            let propagated = if info.propagated_build_inputs.is_empty() {
+                String::new()
+            } else {
+                "\n\n  propagatedBuildInputs = [@propagated_build_inputs@ ];".to_owned()
+            };
 
             let base = "  # buildDunePackage reads dependencies from dune-project\n  # See: https://nixos.org/manual/nixpkgs/stable/#sec-language-ocaml\n  # For complex dependency management, consider opam-nix: https://github.com/tweag/opam-nix";
-            format!("{base}{native}{build}", base = base, native = native, build = build)
+            format!(
+                "{base}{native}{build}{propagated}",
+                base = base,
+                native = native,
+                build = build,
+                propagated = propagated
+            )
         }
         Template::Scala(_) => {
             // Scala/SBT template: use sbt-derivation pattern with Fixed Output Derivation
@@ -529,9 +661,21 @@ fn build_inputs(info: &ExpressionInfo) -> String {
             } else {
                 "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
+            // Runtime requirements from CPAN metadata, as perlPackages attributes
+            let propagated = if info.propagated_build_inputs.is_empty() {
+                String::new()
+            } else {
+                "\n\n  propagatedBuildInputs = [@propagated_build_inputs@ ];".to_owned()
+            };
 
             let base = "  # Perl dependencies are typically handled automatically by buildPerlPackage/buildPerlModule\n  # Additional CPAN modules can be added to propagatedBuildInputs\n  # See: https://nixos.org/manual/nixpkgs/stable/#sec-language-perl";
-            format!("{base}{native}{build}", base = base, native = native, build = build)
+            format!(
+                "{base}{native}{build}{propagated}",
+                base = base,
+                native = native,
+                build = build,
+                propagated = propagated,
+            )
         }
         Template::Lua(_) => {
             // Lua template: buildLuaPackage/buildLuaApplication handle LuaRocks dependencies
@@ -546,8 +690,20 @@ fn build_inputs(info: &ExpressionInfo) -> String {
                 "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
 
+            let propagated = if info.propagated_build_inputs.is_empty() {
+                String::new()
+            } else {
+                "\n\n  propagatedBuildInputs = with luaPackages; [@propagated_build_inputs@ ];".to_owned()
+            };
+
             let base = "  # Lua dependencies from .rockspec are handled automatically by buildLuaPackage/buildLuaApplication\n  # Additional Lua rocks can be added to propagatedBuildInputs\n  # See: https://nixos.org/manual/nixpkgs/stable/#sec-language-lua";
-            format!("{base}{native}{build}", base = base, native = native, build = build)
+            format!(
+                "{base}{native}{build}{propagated}",
+                base = base,
+                native = native,
+                build = build,
+                propagated = propagated,
+            )
         }
         Template::Ruby => {
             // Conditionally render build inputs only when inferred
@@ -561,8 +717,25 @@ fn build_inputs(info: &ExpressionInfo) -> String {
             } else {
                 "\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
+            let exes = if info.executables.is_empty() {
+                "\"@pname@\"".to_owned()
+            } else {
+                info.executables
+                    .iter()
+                    .map(|exe| format!("\"{}\"", exe))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            // A gem from RubyGems has no Gemfile of its own to lock
+            let gemfile = if info.fetcher == Fetcher::rubygems {
+                "\n  # Gemfile: source \"https://rubygems.org\"; gem \"@pname@\", \"@version@\"\n  # Then run `bundle lock && bundix` to generate Gemfile.lock and gemset.nix"
+            } else {
+                ""
+            };
             format!(
-                "  gemdir = ./.;\n  exes = [ \"@pname@\" ];  # To build this package, you need Gemfile, Gemfile.lock, and gemset.nix in this directory{native}{build}\n",
+                "  gemdir = ./.;\n  exes = [ {exes} ];  # To build this package, you need Gemfile, Gemfile.lock, and gemset.nix in this directory{gemfile}{native}{build}\n",
+                exes = exes,
+                gemfile = gemfile,
                 native = native,
                 build = build,
             )
@@ -580,8 +753,20 @@ fn build_inputs(info: &ExpressionInfo) -> String {
                 "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
             };
 
+            let propagated = if info.propagated_build_inputs.is_empty() {
+                String::new()
+            } else {
+                "\n\n  propagatedBuildInputs = with rPackages; [@propagated_build_inputs@ ];".to_owned()
+            };
+
             let base = "  # R dependencies from DESCRIPTION are handled automatically by buildRPackage\n  # Additional R packages can be added to propagatedBuildInputs\n  # See: https://nixos.org/manual/nixpkgs/stable/#sec-language-r";
-            format!("{base}{native}{build}", base = base, native = native, build = build)
+            format!(
+                "{base}{native}{build}{propagated}",
+                base = base,
+                native = native,
+                build = build,
+                propagated = propagated,
+            )
         }
         // stdenv / stdenvNoCC: render `nativeBuildInputs` only when
         // populated (via --native-build-inputs); always render
@@ -604,7 +789,7 @@ fn meta() -> &'static str {
     description = \"@description@\";
     homepage = \"@homepage@\";
     license = licenses.@license@;
-    maintainers = with maintainers; [ @maintainer@ ];
+    maintainers = with maintainers; [ @maintainer@ ];@main_program@
   };"
 }
 
@@ -694,8 +879,8 @@ mkShell rec {
 
             let mut inputs = vec![String::from("lib"), String::from("haskellPackages")];
 
-            // Add fetcher input if not local
-            if !f_input.is_empty() {
+            // Add fetcher input if not local; callHackageDirect fetches itself
+            if !f_input.is_empty() && info.fetcher != Fetcher::hackage {
                 inputs.push(f_input.to_string());
             }
 
@@ -745,21 +930,42 @@ mkShell rec {
                 " { }".to_string()
             };
 
-            // callCabal2nix takes: name, src, and optional buildInputs override
+            // callCabal2nix takes: name, src, and optional buildInputs override.
+            // Hackage releases are fetched by callHackageDirect from the
+            // package name, version and sdist hash instead.
+            let call = if info.fetcher == Fetcher::hackage {
+                format!(
+                    "haskellPackages.callHackageDirect {{
+  pkg = \"{pname}\";
+  ver = \"{version}\";
+  sha256 = \"{sha}\";
+}}{overrides}",
+                    pname = &info.pname,
+                    version = &info.version,
+                    sha = &info.src_sha,
+                    overrides = overrides,
+                )
+            } else {
+                format!(
+                    "haskellPackages.callCabal2nix \"{pname}\" (
+  {src_expr}
+){overrides}",
+                    pname = &info.pname,
+                    src_expr = src_expr,
+                    overrides = overrides,
+                )
+            };
+
             let expression = if meta_content.is_empty() {
                 format!(
                     "{header}
 
 {build_inputs_comment}
 
-haskellPackages.callCabal2nix \"{pname}\" (
-  {src_expr}
-){overrides}
+{call}
 ",
                     header = header,
-                    pname = &info.pname,
-                    src_expr = src_expr,
-                    overrides = overrides,
+                    call = call,
                     build_inputs_comment = build_inputs_comment.trim(),
                 )
             } else {
@@ -768,15 +974,11 @@ haskellPackages.callCabal2nix \"{pname}\" (
 
 {build_inputs_comment}
 
-(haskellPackages.callCabal2nix \"{pname}\" (
-  {src_expr}
-){overrides}).overrideAttrs (finalAttrs: {{{meta}
+({call}).overrideAttrs (finalAttrs: {{{meta}
 }})
 ",
                     header = header,
-                    pname = &info.pname,
-                    src_expr = src_expr,
-                    overrides = overrides,
+                    call = call,
                     build_inputs_comment = build_inputs_comment.trim(),
                     meta = meta_content,
                 )
@@ -784,6 +986,47 @@ haskellPackages.callCabal2nix \"{pname}\" (
 
             info.format(&expression)
         }
+        // Gems without executables are libraries, built straight from the .gem
+        Template::Ruby if info.fetcher == Fetcher::rubygems && info.executables.is_empty() => {
+            let meta_content = if info.include_meta { meta() } else { "" };
+
+            let mut inputs = vec![
+                String::from("lib"),
+                String::from("buildRubyGem"),
+                String::from("ruby"),
+            ];
+            inputs.extend(info.native_build_inputs.iter().map(|s| s.to_owned()));
+            inputs.extend(info.build_inputs.iter().map(|s| s.to_owned()));
+
+            let mut build = String::new();
+            if !info.native_build_inputs.is_empty() {
+                build.push_str("  nativeBuildInputs = [@native_build_inputs@ ];\n");
+            }
+            if !info.build_inputs.is_empty() {
+                build.push_str("  @doc:buildDependencies@buildInputs = [@build_inputs@ ];\n");
+            }
+
+            let header = format!("{{ {input_list}\n}}:", input_list = inputs.join("\n, "));
+
+            info.format(&format!(
+                "{header}
+
+buildRubyGem {{
+  inherit ruby;
+  gemName = \"{pname}\";
+  version = \"{version}\";
+  source.sha256 = \"@src_sha@\";
+
+{build}{meta}
+}}
+",
+                header = header,
+                pname = &info.pname,
+                version = &info.version,
+                build = build,
+                meta = meta_content,
+            ))
+        }
         Template::Ruby => {
             let (_, dh_block) = derivation_helper(info);
             let meta_content = if info.include_meta { meta() } else { "" };
@@ -812,6 +1055,210 @@ haskellPackages.callCabal2nix \"{pname}\" (
                 meta = meta_content,
             ))
         }
+        // Prebuilt binaries: one release asset per system, selected by
+        // `stdenvNoCC.hostPlatform.system`
+        Template::Binary(config) => {
+            let (_, dh_block) = derivation_helper(info);
+            let needs_unzip = config.sources.iter().any(|s| s.url.ends_with(".zip"));
+
+            let mut inputs = vec![
+                String::from("lib"),
+                String::from("stdenvNoCC"),
+                String::from("fetchurl"),
+                String::from("autoPatchelfHook"),
+            ];
+            if needs_unzip {
+                inputs.push(String::from("unzip"));
+            }
+            inputs.extend(info.native_build_inputs.iter().map(|s| s.to_owned()));
+            inputs.extend(info.build_inputs.iter().map(|s| s.to_owned()));
+            let mut seen = std::collections::HashSet::new();
+            inputs.retain(|s| seen.insert(s.clone()));
+            let header = format!("{{ {input_list}\n}}:", input_list = inputs.join("\n, "));
+
+            let sources = if config.sources.is_empty() {
+                format!(
+                    "    x86_64-linux = fetchurl {{\n      url = \"CHANGE\";\n      hash = \"{}\";\n    }};",
+                    crate::types::FAKE_SRI_HASH
+                )
+            } else {
+                config
+                    .sources
+                    .iter()
+                    .map(|source| {
+                        format!(
+                            "    {system} = fetchurl {{\n      url = \"{url}\";\n      hash = \"{hash}\";\n    }};",
+                            system = source.system,
+                            url = source.url,
+                            hash = source.hash,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            // Archives are unpacked into the build directory; bare
+            // executables are installed straight from the store
+            let (unpack, install) = if config.archives {
+                (
+                    "  sourceRoot = \".\";",
+                    "    find . -type f -executable -exec install -Dm755 -t $out/bin {} +",
+                )
+            } else {
+                (
+                    "  dontUnpack = true;",
+                    "    install -Dm755 $src $out/bin/@pname@",
+                )
+            };
+            let mut native_inputs: Vec<&str> = Vec::new();
+            if needs_unzip {
+                native_inputs.push("unzip");
+            }
+            native_inputs.extend(info.native_build_inputs.iter().map(|s| s.as_str()));
+            let native = if native_inputs.is_empty() {
+                String::new()
+            } else {
+                format!("[\n    {}\n  ]\n  ++ ", native_inputs.join("\n    "))
+            };
+            let build = if info.build_inputs.is_empty() {
+                String::new()
+            } else {
+                "\n\n  buildInputs = [@build_inputs@ ];".to_owned()
+            };
+            let meta_content = if info.include_meta {
+                meta().replace(
+                    "@main_program@\n  };",
+                    "@main_program@\n    sourceProvenance = with sourceTypes; [ binaryNativeCode ];\n    platforms = builtins.attrNames finalAttrs.passthru.sources;\n  };",
+                )
+            } else {
+                String::new()
+            };
+
+            info.format(&format!(
+                "{header}
+
+{dh_helper} (finalAttrs: {{
+  pname = \"{pname}\";
+  version = \"{version}\";
+
+  src =
+    finalAttrs.passthru.sources.${{stdenvNoCC.hostPlatform.system}}
+      or (throw \"Unsupported system: ${{stdenvNoCC.hostPlatform.system}}\");
+
+{unpack}
+
+  @doc:autoPatchelfHook@nativeBuildInputs = {native}lib.optionals stdenvNoCC.hostPlatform.isLinux [ autoPatchelfHook ];{build}
+
+  installPhase = ''
+    runHook preInstall
+
+{install}
+
+    runHook postInstall
+  '';
+
+  passthru.sources = {{
+{sources}
+  }};
+{meta}
+}})
+",
+                header = header,
+                dh_helper = dh_block,
+                pname = &info.pname,
+                version = &info.version,
+                unpack = unpack,
+                native = native,
+                build = build,
+                install = install,
+                sources = sources,
+                meta = meta_content,
+            ))
+        }
+        // AppImages are wrapped as they are; the extracted contents only
+        // provide the desktop entry and icons
+        Template::AppImage(config) => {
+            let (dh_input, dh_block) = derivation_helper(info);
+            let header = format!("{{ lib\n, {}\n, fetchurl\n}}:", dh_input);
+
+            let desktop_file = config
+                .desktop_file
+                .clone()
+                .unwrap_or_else(|| format!("{}.desktop", &info.pname));
+            let mut install = vec![format!(
+                "    install -Dm444 ${{appimageContents}}/{} -t $out/share/applications",
+                desktop_file
+            )];
+            // The desktop entry launches the program inside the AppImage,
+            // which wrapType2 installs as $out/bin/${pname}. The program is
+            // matched as written in the entry, quotes included
+            let exec = config.exec.as_deref().unwrap_or("AppRun");
+            if exec != info.pname {
+                install.push(format!(
+                    "    substituteInPlace $out/share/applications/{} \\\n      --replace-fail 'Exec={}' 'Exec=${{pname}}'",
+                    desktop_file, exec
+                ));
+            }
+            match config.icon.as_deref().unwrap_or("usr/share/icons") {
+                "usr/share/icons" => {
+                    install.push("    cp -r ${appimageContents}/usr/share/icons $out/share".to_owned())
+                }
+                icon => install.push(format!(
+                    "    install -Dm444 ${{appimageContents}}/{} -t $out/share/pixmaps",
+                    icon
+                )),
+            }
+
+            let url = info.src_url.to_lowercase();
+            let platform = if url.contains("aarch64") || url.contains("arm64") {
+                "aarch64-linux"
+            } else {
+                "x86_64-linux"
+            };
+            let meta_content = if info.include_meta {
+                meta().replace(
+                    "@main_program@\n  };",
+                    &format!(
+                        "@main_program@\n    sourceProvenance = with sourceTypes; [ binaryNativeCode ];\n    platforms = [ \"{}\" ];\n  }};",
+                        platform
+                    ),
+                )
+            } else {
+                String::new()
+            };
+
+            info.format(&format!(
+                "{header}
+
+let
+  pname = \"{pname}\";
+  version = \"{version}\";
+
+  @doc:fetcher@src = fetchurl {{
+    url = \"{url}\";
+    hash = \"@src_sha@\";
+  }};
+
+  appimageContents = appimageTools.extract {{ inherit pname version src; }};
+in
+{dh_helper} {{
+  inherit pname version src;
+
+  extraInstallCommands = ''
+{install}
+  '';
+{meta}
+}}
+",
+                header = header,
+                dh_helper = dh_block,
+                pname = &info.pname,
+                version = &info.version,
+                url = info.src_url.replace("${finalAttrs.version}", "${version}"),
+                install = install.join("\n"),
+                meta = meta_content,
+            ))
+        }
         Template::Php(config) if !config.extensions.is_empty() => {
             // PHP with extensions needs a let block to build custom PHP with extensions
             let (dh_input, _) = derivation_helper(info);
@@ -875,7 +1322,17 @@ php.buildComposerProject2 (finalAttrs: {{
             if !f_input.is_empty() {
                 inputs.push(f_input.to_string());
             }
-            inputs.extend(info.propagated_build_inputs.iter().map(|s| s.to_owned()));
+            // R, BEAM and Lua packages are referenced through `with rPackages;`,
+            // `with beamPackages;` and `with luaPackages;` instead
+            match info.template {
+                Template::R | Template::Elixir(_) => (),
+                Template::Lua(_) => {
+                    if !info.propagated_build_inputs.is_empty() {
+                        inputs.push("luaPackages".to_string());
+                    }
+                }
+                _ => inputs.extend(info.propagated_build_inputs.iter().map(|s| s.to_owned())),
+            }
 
             // pnpm template needs special inputs for fetchPnpmDeps and pnpm setup
             if info.template == Template::pnpm() {
@@ -947,6 +1404,12 @@ mod tests {
             include_documentation_links: false,
             include_meta: true,
             tag_prefix: "".to_owned(),
+            tag_version_separator: "".to_owned(),
+            src_rev: "".to_owned(),
+            fetch_submodules: false,
+            fetch_lfs: false,
+            main_program: String::new(),
+            executables: Vec::new(),
             owner: "demo".to_owned(),
             src_sha: "sha256-demo".to_owned(),
             description: "demo".to_owned(),
@@ -958,11 +1421,13 @@ mod tests {
             pnpm_deps_hash: "sha256-pnpm".to_owned(),
             project_file: "Project.csproj".to_owned(),
             domain: "".to_owned(),
+            src_url: "".to_owned(),
             build_inputs: Vec::new(),
             native_build_inputs: Vec::new(),
             use_cargo_lock_file: false,
             cargo_lock_git_deps: Vec::new(),
             go_module_path: String::new(),
+            go_mod_root: String::new(),
             python_format: "setuptools".to_owned(),
             mvn_hash: "sha256-mvn".to_owned(),
             mix_fod_hash: "sha256-mix".to_owned(),
@@ -996,6 +1461,12 @@ mod tests {
             include_documentation_links: false,
             include_meta: true,
             tag_prefix: "".to_owned(),
+            tag_version_separator: "".to_owned(),
+            src_rev: "".to_owned(),
+            fetch_submodules: false,
+            fetch_lfs: false,
+            main_program: String::new(),
+            executables: Vec::new(),
             owner: "myfont".to_owned(),
             src_sha: "sha256-demo".to_owned(),
             description: "demo font".to_owned(),
@@ -1007,11 +1478,13 @@ mod tests {
             pnpm_deps_hash: "".to_owned(),
             project_file: "".to_owned(),
             domain: "".to_owned(),
+            src_url: "".to_owned(),
             build_inputs: Vec::new(),
             native_build_inputs: Vec::new(),
             use_cargo_lock_file: false,
             cargo_lock_git_deps: Vec::new(),
             go_module_path: String::new(),
+            go_mod_root: String::new(),
             python_format: "setuptools".to_owned(),
             mvn_hash: "sha256-mvn".to_owned(),
             mix_fod_hash: "sha256-mix".to_owned(),
@@ -1240,6 +1713,171 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hex_library_renders_fetch_hex_and_beam_deps() {
+        let mut info = rust_info();
+        info.template = Template::elixir_library();
+        info.fetcher = Fetcher::hex;
+        info.propagated_build_inputs = vec!["elixir_make".to_owned(), "comeonin".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains("beamPackages.buildMix (finalAttrs: {"));
+        assert!(out.contains(
+            "  src = beamPackages.fetchHex {\n    pkg = \"demo\";\n    inherit (finalAttrs) version;\n    sha256 = \"sha256-demo\";\n  };"
+        ));
+        assert!(
+            out.contains("beamDeps = with beamPackages; [\n    comeonin\n    elixir_make\n  ];")
+        );
+        assert!(!out.contains("mixFodDeps"));
+        // Dependencies come from beamPackages, not the function arguments
+        assert!(out.starts_with("{ lib\n, beamPackages\n}:"));
+
+        info.template.elixir_config_mut().unwrap().variant = crate::types::ElixirVariant::Rebar3;
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains("beamPackages.buildRebar3 (finalAttrs: {"));
+    }
+
+    #[test]
+    fn ocaml_renders_opam_dependencies() {
+        let mut info = rust_info();
+        info.template = Template::ocaml();
+        info.fetcher = Fetcher::url;
+        info.src_url = "https://example.org/demo-${finalAttrs.version}.tbz".to_owned();
+        info.propagated_build_inputs = vec!["stdlib-shims".to_owned()];
+        info.build_inputs = vec!["gmp".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains("buildDunePackage (finalAttrs: {"));
+        assert!(out.contains("  buildInputs = [\n    gmp\n  ];"));
+        assert!(out.contains("  propagatedBuildInputs = [\n    stdlib-shims\n  ];"));
+        // ocamlPackages dependencies are passed in by callPackage
+        assert!(out.contains("\n, stdlib-shims\n"));
+    }
+
+    #[test]
+    fn lua_renders_rockspec_dependencies() {
+        let mut info = rust_info();
+        info.template = Template::lua();
+        info.propagated_build_inputs = vec!["luasocket".to_owned()];
+        info.build_inputs = vec!["openssl".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains("  buildInputs = [\n    openssl\n  ];"));
+        assert!(out.contains("  propagatedBuildInputs = with luaPackages; [\n    luasocket\n  ];"));
+        // Rocks come from luaPackages, not the function arguments
+        assert!(out.contains("\n, luaPackages\n"));
+        assert!(!out.contains("\n, luasocket\n"));
+    }
+
+    #[test]
+    fn binary_renders_per_system_sources() {
+        let mut info = rust_info();
+        info.template = Template::Binary(crate::types::BinaryConfig {
+            sources: vec![
+                crate::types::BinarySource {
+                    system: "x86_64-linux".to_owned(),
+                    url: "https://github.com/demo/demo/releases/download/v${finalAttrs.version}/demo-x86_64-unknown-linux-gnu.tar.gz".to_owned(),
+                    hash: "sha256-linux".to_owned(),
+                },
+                crate::types::BinarySource {
+                    system: "aarch64-darwin".to_owned(),
+                    url: "https://github.com/demo/demo/releases/download/v${finalAttrs.version}/demo-aarch64-apple-darwin.zip".to_owned(),
+                    hash: "sha256-darwin".to_owned(),
+                },
+            ],
+            archives: true,
+        });
+        let out = info.format(&generate_expression(&info));
+        assert!(out.starts_with("{ lib\n, stdenvNoCC\n, fetchurl\n, autoPatchelfHook\n, unzip\n}:"));
+        assert!(out.contains("stdenvNoCC.mkDerivation (finalAttrs: {"));
+        assert!(out.contains("    x86_64-linux = fetchurl {\n      url = \"https://github.com/demo/demo/releases/download/v${finalAttrs.version}/demo-x86_64-unknown-linux-gnu.tar.gz\";\n      hash = \"sha256-linux\";\n    };"));
+        assert!(out.contains("    aarch64-darwin = fetchurl {"));
+        assert!(out.contains(
+            "  nativeBuildInputs = [\n    unzip\n  ]\n  ++ lib.optionals stdenvNoCC.hostPlatform.isLinux [ autoPatchelfHook ];"
+        ));
+        assert!(out.contains("find . -type f -executable -exec install -Dm755 -t $out/bin {} +"));
+        assert!(out.contains("platforms = builtins.attrNames finalAttrs.passthru.sources;"));
+
+        info.template.binary_config_mut().unwrap().sources.pop();
+        info.template.binary_config_mut().unwrap().archives = false;
+        let out = info.format(&generate_expression(&info));
+        assert!(!out.contains("unzip"));
+        assert!(out.contains(
+            "  nativeBuildInputs = lib.optionals stdenvNoCC.hostPlatform.isLinux [ autoPatchelfHook ];"
+        ));
+        assert!(out.contains("  dontUnpack = true;"));
+        assert!(out.contains("    install -Dm755 $src $out/bin/demo"));
+    }
+
+    #[test]
+    fn appimage_renders_wrap_type2_with_desktop_integration() {
+        let mut info = rust_info();
+        info.pname = "obsidian".to_owned();
+        info.version = "1.5.3".to_owned();
+        info.main_program = "obsidian".to_owned();
+        info.src_url = "https://github.com/obsidianmd/obsidian-releases/releases/download/v${finalAttrs.version}/Obsidian-${finalAttrs.version}.AppImage".to_owned();
+        info.template = Template::AppImage(crate::types::AppImageConfig {
+            desktop_file: Some("obsidian.desktop".to_owned()),
+            exec: Some("AppRun".to_owned()),
+            icon: Some("usr/share/icons".to_owned()),
+        });
+        let out = info.format(&generate_expression(&info));
+        assert!(out.starts_with("{ lib\n, appimageTools\n, fetchurl\n}:\n\nlet\n  pname = \"obsidian\";\n  version = \"1.5.3\";"));
+        assert!(out.contains("    url = \"https://github.com/obsidianmd/obsidian-releases/releases/download/v${version}/Obsidian-${version}.AppImage\";"));
+        assert!(out.contains("  appimageContents = appimageTools.extract { inherit pname version src; };\nin\nappimageTools.wrapType2 {\n  inherit pname version src;"));
+        assert!(out.contains("    install -Dm444 ${appimageContents}/obsidian.desktop -t $out/share/applications\n    substituteInPlace $out/share/applications/obsidian.desktop \\\n      --replace-fail 'Exec=AppRun' 'Exec=${pname}'\n    cp -r ${appimageContents}/usr/share/icons $out/share\n"));
+        assert!(out.contains("    mainProgram = \"obsidian\";"));
+        assert!(out.contains("platforms = [ \"x86_64-linux\" ];"));
+
+        let config = info.template.appimage_config_mut().unwrap();
+        config.exec = Some("obsidian".to_owned());
+        config.icon = Some("obsidian.png".to_owned());
+        let out = info.format(&generate_expression(&info));
+        assert!(!out.contains("substituteInPlace"));
+        assert!(out.contains("    install -Dm444 ${appimageContents}/obsidian.png -t $out/share/pixmaps"));
+
+        info.template.appimage_config_mut().unwrap().exec =
+            Some("\"/usr/bin/Obsidian App\"".to_owned());
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains(
+            "      --replace-fail 'Exec=\"/usr/bin/Obsidian App\"' 'Exec=${pname}'\n"
+        ));
+    }
+
+    #[test]
+    fn flutter_renders_build_flutter_application() {
+        let mut info = rust_info();
+        info.template = Template::dart();
+        info.fetcher = Fetcher::pubdev;
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains("buildDartApplication (finalAttrs: {"));
+        assert!(out.contains(
+            "  src = fetchzip {\n    url = \"https://pub.dev/api/archives/demo-${finalAttrs.version}.tar.gz\";\n    stripRoot = false;"
+        ));
+
+        info.template.dart_config_mut().unwrap().flutter = true;
+        let out = info.format(&generate_expression(&info));
+        assert!(out.contains("flutter.buildFlutterApplication (finalAttrs: {"));
+        assert!(out.starts_with("{ lib\n, flutter\n, fetchzip\n}:"));
+        assert!(out.contains("#ssec-dart-flutter"));
+    }
+
+    #[test]
+    fn go_nested_module_renders_mod_root() {
+        let mut info = rust_info();
+        info.template = Template::go();
+        info.go_module_path = "golang.org/x/tools/gopls".to_owned();
+        info.go_mod_root = "gopls".to_owned();
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("  modRoot = \"gopls\";\n"),
+            "expected modRoot in:\n{}",
+            out
+        );
+        assert!(out.contains("-X golang.org/x/tools/gopls/main.version="));
+
+        info.go_mod_root = String::new();
+        let out = info.format(&generate_expression(&info));
+        assert!(!out.contains("modRoot"));
+    }
+
     #[test]
     fn go_vendor_null_renders_without_quotes() {
         let mut info = rust_info();
@@ -1259,6 +1897,364 @@ mod tests {
         );
     }
 
+    #[test]
+    fn gitlab_fetcher_renders_domain() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::gitlab;
+        info.domain = "git.example.org".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains(
+                "src = fetchFromGitLab {\n    domain = \"git.example.org\";\n    owner = "
+            ),
+            "fetchFromGitLab block is missing domain:\n{}",
+            out
+        );
+
+        // fetchFromGitLab defaults to gitlab.com
+        for domain in ["gitlab.com", "CHANGE"] {
+            info.domain = domain.to_owned();
+            let out = info.format(&generate_expression(&info));
+            assert!(
+                out.contains("src = fetchFromGitLab {\n    owner = "),
+                "{}",
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn sourcehut_fetcher_renders_domain() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::sourcehut;
+        info.domain = "sr.ht".to_owned();
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("src = fetchFromSourcehut {\n    owner = "),
+            "{}",
+            out
+        );
+
+        info.domain = "example.org".to_owned();
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("src = fetchFromSourcehut {\n    domain = \"example.org\";\n    owner = "),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn url_fetcher_renders_archive_url() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::url;
+        info.src_url = "https://example.org/demo-${finalAttrs.version}.tar.gz".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains("src = fetchurl {\n    url = \"https://example.org/demo-${finalAttrs.version}.tar.gz\";\n    sha256 = \"sha256-demo\";\n  };"),
+            "unexpected fetchurl block in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn transformed_tag_rev_reconstructs_tag() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::github;
+        info.version = "4.3.1".to_owned();
+        info.tag_prefix = "R_".to_owned();
+        info.tag_version_separator = "_".to_owned();
+        assert_eq!(info.upstream_tag(), "R_4_3_1");
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains(
+                "rev = \"R_${lib.replaceStrings [ \".\" ] [ \"_\" ] finalAttrs.version}\";"
+            ),
+            "rev should rebuild the tag in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn unstable_version_renders_commit_rev() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::github;
+        info.version = "0-unstable-2024-05-01".to_owned();
+        info.tag_prefix = "v".to_owned();
+        info.src_rev = "0123456789abcdef0123456789abcdef01234567".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains("version = \"0-unstable-2024-05-01\";"),
+            "unexpected version in:\n{}",
+            out
+        );
+        assert!(
+            out.contains("rev = \"0123456789abcdef0123456789abcdef01234567\";"),
+            "rev should pin the commit in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn submodules_and_lfs_render_fetch_options() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::github;
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            !out.contains("fetchSubmodules") && !out.contains("fetchLFS"),
+            "no fetch options expected in:\n{}",
+            out
+        );
+
+        info.fetch_submodules = true;
+        info.fetch_lfs = true;
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("\";\n    fetchSubmodules = true;\n    fetchLFS = true;\n  };"),
+            "fetch options should follow the hash in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn git_fetcher_renders_fetchgit() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::git;
+        info.src_url = "https://git.example.org/demo.git".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains(", fetchgit"),
+            "header missing fetchgit:\n{}",
+            out
+        );
+        assert!(
+            out.contains("src = fetchgit {\n    url = \"https://git.example.org/demo.git\";\n    rev = finalAttrs.version;\n    hash = \"sha256-demo\";\n  };"),
+            "unexpected fetchgit block in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn crates_fetcher_renders_fetch_crate() {
+        let mut info = rust_info();
+        info.fetcher = Fetcher::crates;
+        info.license = "mit asl20".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains(", fetchCrate"),
+            "header missing fetchCrate:\n{}",
+            out
+        );
+        assert!(
+            out.contains("src = fetchCrate {\n    inherit (finalAttrs) pname version;\n    hash = \"sha256-demo\";\n  };"),
+            "unexpected fetchCrate block in:\n{}",
+            out
+        );
+        assert!(
+            out.contains("license = [ licenses.mit licenses.asl20 ];"),
+            "dual license should render as a list in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn main_program_renders_in_meta() {
+        let mut info = rust_info();
+        info.main_program = "demo-cli".to_owned();
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains(
+                "maintainers = with maintainers; [ me ];\n    mainProgram = \"demo-cli\";\n  };"
+            ),
+            "mainProgram should close the meta block in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn hackage_fetcher_renders_call_hackage_direct() {
+        let mut info = rust_info();
+        info.template = Template::haskell();
+        info.fetcher = Fetcher::hackage;
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains(
+                "(haskellPackages.callHackageDirect {\n  pkg = \"demo\";\n  ver = \"1.0.0\";\n  sha256 = \"sha256-demo\";\n} { }).overrideAttrs"
+            ),
+            "unexpected callHackageDirect call in:\n{}",
+            out
+        );
+        assert!(
+            !out.contains("fetchzip"),
+            "callHackageDirect needs no fetcher input:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn hackage_fetcher_with_stdenv_renders_fetchzip() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::hackage;
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("url = \"mirror://hackage/demo-${finalAttrs.version}/demo-${finalAttrs.version}.tar.gz\";\n    hash = \"sha256-demo\";"),
+            "unexpected fetchzip block in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn rubygems_library_renders_build_ruby_gem() {
+        let mut info = rust_info();
+        info.template = Template::Ruby;
+        info.fetcher = Fetcher::rubygems;
+        info.build_inputs = vec!["postgresql".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("{ lib\n, buildRubyGem\n, ruby\n, postgresql\n}:"),
+            "unexpected header in:\n{}",
+            out
+        );
+        assert!(
+            out.contains(
+                "buildRubyGem {\n  inherit ruby;\n  gemName = \"demo\";\n  version = \"1.0.0\";\n  source.sha256 = \"sha256-demo\";\n\n  buildInputs = [\n    postgresql\n  ];"
+            ),
+            "unexpected buildRubyGem call in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn rubygems_executables_render_bundler_app() {
+        let mut info = rust_info();
+        info.template = Template::Ruby;
+        info.fetcher = Fetcher::rubygems;
+        info.executables = vec!["demo".to_owned(), "demo-lsp".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("bundlerApp {\n  pname = \"demo\";\n  gemdir = ./.;\n  exes = [ \"demo\" \"demo-lsp\" ];"),
+            "unexpected bundlerApp call in:\n{}",
+            out
+        );
+        assert!(
+            out.contains("gem \"demo\", \"1.0.0\""),
+            "missing Gemfile hint in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn cpan_fetcher_renders_mirror_url_and_perl_packages() {
+        let mut info = rust_info();
+        info.template = Template::perl();
+        info.fetcher = Fetcher::cpan;
+        info.src_url =
+            "mirror://cpan/authors/id/M/ME/ME/demo-${finalAttrs.version}.tar.gz".to_owned();
+        info.propagated_build_inputs = vec!["TryTiny".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("{ lib\n, buildPerlPackage\n, fetchurl\n, TryTiny\n}:"),
+            "unexpected header in:\n{}",
+            out
+        );
+        assert!(
+            out.contains(
+                "src = fetchurl {\n    url = \"mirror://cpan/authors/id/M/ME/ME/demo-${finalAttrs.version}.tar.gz\";\n    hash = \"sha256-demo\";\n  };"
+            ),
+            "unexpected fetchurl block in:\n{}",
+            out
+        );
+        assert!(
+            out.contains("propagatedBuildInputs = [\n    TryTiny\n  ];"),
+            "missing propagatedBuildInputs in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn cran_fetcher_renders_r_packages() {
+        let mut info = rust_info();
+        info.template = Template::R;
+        info.fetcher = Fetcher::cran;
+        info.propagated_build_inputs = vec!["Rcpp".to_owned(), "data_table".to_owned()];
+        info.build_inputs = vec!["gdal".to_owned()];
+        let out = info.format(&generate_expression(&info));
+        assert!(
+            out.contains("{ lib\n, rPackages\n, fetchurl\n, gdal\n}:"),
+            "unexpected header in:\n{}",
+            out
+        );
+        assert!(
+            out.contains(
+                "\"mirror://cran/src/contrib/Archive/demo/demo_${finalAttrs.version}.tar.gz\""
+            ),
+            "missing CRAN archive url in:\n{}",
+            out
+        );
+        assert!(
+            out.contains(
+                "propagatedBuildInputs = with rPackages; [\n    Rcpp\n    data_table\n  ];"
+            ),
+            "unexpected propagatedBuildInputs in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn sourcehut_fetcher_renders_fetch_from_sourcehut() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::sourcehut;
+        info.owner = "~sircmpwn".to_owned();
+        info.tag_prefix = "v".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains(", fetchFromSourcehut"),
+            "header missing fetchFromSourcehut:\n{}",
+            out
+        );
+        assert!(
+            out.contains("src = fetchFromSourcehut {\n    owner = \"~sircmpwn\";\n    repo = \"demo\";\n    rev = \"v${finalAttrs.version}\";\n    hash = \"sha256-demo\";\n  };"),
+            "unexpected fetchFromSourcehut block in:\n{}",
+            out
+        );
+    }
+
+    #[test]
+    fn bitbucket_fetcher_renders_fetch_from_bitbucket() {
+        let mut info = rust_info();
+        info.template = Template::stdenv();
+        info.fetcher = Fetcher::bitbucket;
+        info.owner = "multicoreware".to_owned();
+        let expr = generate_expression(&info);
+        let out = info.format(&expr);
+        assert!(
+            out.contains(", fetchFromBitbucket"),
+            "header missing fetchFromBitbucket:\n{}",
+            out
+        );
+        assert!(
+            out.contains("src = fetchFromBitbucket {\n    owner = \"multicoreware\";\n    repo = \"demo\";\n    rev = finalAttrs.version;\n    hash = \"sha256-demo\";\n  };"),
+            "unexpected fetchFromBitbucket block in:\n{}",
+            out
+        );
+    }
+
     #[test]
     fn python_format_renders_detected_format() {
         let mut info = rust_info();
@@ -1,5 +1,14 @@
-use crate::types::{Fetcher, GiteaRepo, GithubRepo, PypiRepo, Repo, Template, UserConfig};
-use crate::url::{fetch_github_release_info, fetch_github_repo_info, fetch_pypi_project_info};
+use crate::types::{
+    BitbucketRepo, CpanRepo, CranRepo, CratesRepo, Fetcher, ForgeConfig, GiteaRepo, GithubRepo,
+    GoModuleRepo, HackageRepo, HexRepo, LuaRocksRepo, MavenRepo, NpmLicense, NpmRepo, OpamRepo,
+    PackagistRepo, PubRepo, PypiRepo, Repo, RubygemsRepo, SourcehutRepo, Template, UserConfig,
+};
+use crate::url::{
+    configured_forge_repo, fetch_crate_info, fetch_github_release_info, fetch_github_repo_info,
+    fetch_npm_manifest, fetch_pypi_project_info, parse_archive_url, parse_generic_git_url,
+    parse_local_opam_url, parse_maven_coordinates, spdx_to_nixpkgs_license, TagScheme,
+    CRATES_IO_API_BASE, NPM_REGISTRY_BASE,
+};
 use anyhow::{anyhow, Result};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
@@ -111,14 +120,59 @@ lazy_static! {
     static ref PYPI_URL_REGEX: Regex = {
         Regex::new("pypi.org/project/([^/]*)/?").unwrap()
     };
+    static ref CRATES_URL_REGEX: Regex = {
+        Regex::new(r"^crates\.io/crates/([A-Za-z0-9_-]+)/?").unwrap()
+    };
+    static ref NPM_URL_REGEX: Regex = {
+        Regex::new(r"^(?:www\.)?npmjs\.com/package/((?:@[^/]+/)?[^/?#]+)/?").unwrap()
+    };
+    static ref HACKAGE_URL_REGEX: Regex = {
+        Regex::new(r"^hackage\.haskell\.org/package/([A-Za-z0-9-]+?)(?:-([0-9][0-9.]*))?/?$").unwrap()
+    };
+    static ref RUBYGEMS_URL_REGEX: Regex = {
+        Regex::new(r"^rubygems\.org/gems/([A-Za-z0-9_.-]+)").unwrap()
+    };
+    static ref METACPAN_URL_REGEX: Regex = {
+        Regex::new(r"^metacpan\.org/(dist|release|pod)/([A-Za-z0-9_:.-]+?)/?$").unwrap()
+    };
+    static ref CRAN_URL_REGEX: Regex = {
+        Regex::new(r"^(?:cran|cloud)\.r-project\.org/(?:package=|web/packages/)([A-Za-z0-9.]+?)(?:/index\.html)?/?$").unwrap()
+    };
+    static ref BIOCONDUCTOR_URL_REGEX: Regex = {
+        Regex::new(r"^(?:www\.)?bioconductor\.org/packages/(?:(?:release|devel|[0-9.]+)/bioc/html/)?([A-Za-z0-9.]+?)(?:\.html)?/?$").unwrap()
+    };
+    static ref HEX_URL_REGEX: Regex = {
+        Regex::new(r"^hex\.pm/packages/([A-Za-z0-9_]+)(?:/([0-9][A-Za-z0-9.+-]*))?/?$").unwrap()
+    };
+    static ref PACKAGIST_URL_REGEX: Regex = {
+        Regex::new(r"^packagist\.org/packages/([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+)/?$").unwrap()
+    };
+    static ref MAVEN_CENTRAL_URL_REGEX: Regex = {
+        Regex::new(r"^central\.sonatype\.com/artifact/([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+)(?:/([A-Za-z0-9_.+-]+))?/?$").unwrap()
+    };
+    static ref LUAROCKS_URL_REGEX: Regex = {
+        Regex::new(r"^luarocks\.org/modules/([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+)(?:/([A-Za-z0-9_.+-]+))?/?$").unwrap()
+    };
+    static ref PUB_URL_REGEX: Regex = {
+        Regex::new(r"^pub\.dev/packages/([a-z0-9_]+)(?:/versions/([0-9][A-Za-z0-9.+-]*))?(?:/[a-z]+)?/?$").unwrap()
+    };
+    static ref OPAM_URL_REGEX: Regex = {
+        Regex::new(r"^opam\.ocaml\.org/packages/([A-Za-z0-9_+-]+)(?:/([A-Za-z0-9_+-]+)\.([^/]+))?/?$").unwrap()
+    };
+    static ref GO_MODULE_URL_REGEX: Regex = {
+        Regex::new(r"^pkg\.go\.dev/([A-Za-z0-9._~/-]+?)(?:@([A-Za-z0-9._+-]+))?/?$").unwrap()
+    };
+    static ref SOURCEHUT_URL_REGEX: Regex = {
+        Regex::new(r"git\.sr\.ht/(~[^/]+)/([^/]+?)(?:\.git)?/?$").unwrap()
+    };
+    static ref BITBUCKET_URL_REGEX: Regex = {
+        Regex::new(r"bitbucket\.org/([^/]+)/([^/]+?)(?:\.git)?/?$").unwrap()
+    };
     /// Hosts recognised as Gitea instances for interactive metadata extraction.
     static ref GITEA_HOSTS: Vec<&'static str> = vec!["codeberg.org", "gitea.com"];
     static ref GITEA_URL_REGEX: Regex = {
         Regex::new(r"(?P<domain>[^/]+)/(?P<owner>[^/]+)/(?P<repo>[^/]+)/?").unwrap()
     };
-    static ref VERSION_REGEX: Regex = {
-        Regex::new("^([^0-9]*)(.+)").unwrap()
-    };
     static ref STABLE_RELEASE_REGEX: Regex = {
         Regex::new(r"^([0-9.]*)+$").unwrap()
     };
@@ -449,9 +503,13 @@ pub fn prompt_template_from_candidates(
     Ok(candidates[selected_idx].template.clone())
 }
 
-/// Extract metadata from a URL
-pub fn extract_metadata_from_url(url: &str) -> Result<UrlMetadata> {
-    let repo = parse_url(url)?;
+/// Extract metadata from a URL. Domains in `forges` are parsed as the
+/// configured forge kind.
+pub fn extract_metadata_from_url(
+    url: &str,
+    forges: &HashMap<String, ForgeConfig>,
+) -> Result<UrlMetadata> {
+    let repo = parse_url(url, forges)?;
 
     match repo {
         Repo::Github(gh_repo) => {
@@ -499,6 +557,261 @@ pub fn extract_metadata_from_url(url: &str) -> Result<UrlMetadata> {
                 fetcher: Fetcher::pypi,
             })
         }
+        Repo::Crates(crates_repo) => {
+            eprintln!(
+                "Fetching metadata from crates.io for {}...",
+                crates_repo.name
+            );
+
+            let response = fetch_crate_info(CRATES_IO_API_BASE, &crates_repo)?;
+            let license = response
+                .versions
+                .iter()
+                .find(|v| !v.yanked)
+                .and_then(|v| v.license.as_deref())
+                .map(spdx_to_nixpkgs_license)
+                .unwrap_or_else(|| "CHANGE".to_string());
+            let homepage = response
+                .krate
+                .homepage
+                .or(response.krate.repository)
+                .unwrap_or_else(|| format!("https://crates.io/crates/{}", crates_repo.name));
+
+            Ok(UrlMetadata {
+                pname: crates_repo.name.clone(),
+                license,
+                description: response
+                    .krate
+                    .description
+                    .map(|d| d.trim().trim_end_matches('.').to_string())
+                    .unwrap_or("CHANGE".to_string()),
+                homepage,
+                fetcher: Fetcher::crates,
+            })
+        }
+        Repo::Npm(npm_repo) => {
+            eprintln!("Fetching metadata from npm for {}...", npm_repo.name);
+
+            let manifest = fetch_npm_manifest(NPM_REGISTRY_BASE, &npm_repo, "latest")?;
+            let license = match &manifest.license {
+                Some(NpmLicense::Spdx(license)) => spdx_to_nixpkgs_license(license),
+                Some(NpmLicense::Object { kind }) => spdx_to_nixpkgs_license(kind),
+                None => "CHANGE".to_string(),
+            };
+            let pname = match npm_repo.name.rsplit_once('/') {
+                Some((_, name)) => name.to_owned(),
+                None => npm_repo.name.clone(),
+            };
+
+            Ok(UrlMetadata {
+                pname,
+                license,
+                description: manifest
+                    .description
+                    .map(|d| d.trim().trim_end_matches('.').to_string())
+                    .unwrap_or("CHANGE".to_string()),
+                homepage: manifest
+                    .homepage
+                    .unwrap_or_else(|| format!("https://www.npmjs.com/package/{}", npm_repo.name)),
+                fetcher: Fetcher::zip,
+            })
+        }
+        Repo::Hackage(hackage_repo) => {
+            // Synopsis and license live in the .cabal file, which
+            // `read_meta_from_url` downloads later.
+            eprintln!(
+                "Detected Hackage URL ({}), full metadata will be fetched later.",
+                hackage_repo.package
+            );
+            Ok(UrlMetadata {
+                pname: hackage_repo.package.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!(
+                    "https://hackage.haskell.org/package/{}",
+                    hackage_repo.package
+                ),
+                fetcher: Fetcher::hackage,
+            })
+        }
+        Repo::Rubygems(rubygems_repo) => {
+            // Summary, license and executables are filled later by
+            // `read_meta_from_url`.
+            eprintln!(
+                "Detected RubyGems URL ({}), full metadata will be fetched later.",
+                rubygems_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: rubygems_repo.name.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!("https://rubygems.org/gems/{}", rubygems_repo.name),
+                fetcher: Fetcher::rubygems,
+            })
+        }
+        Repo::Cpan(cpan_repo) => {
+            // The distribution of a module, its abstract and license are
+            // filled later by `read_meta_from_url`.
+            eprintln!(
+                "Detected MetaCPAN URL ({}), full metadata will be fetched later.",
+                cpan_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: cpan_repo.name.replace("::", "-"),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!(
+                    "https://metacpan.org/dist/{}",
+                    cpan_repo.name.replace("::", "-")
+                ),
+                fetcher: Fetcher::cpan,
+            })
+        }
+        Repo::Cran(cran_repo) => {
+            // Title, license and dependencies live in the DESCRIPTION
+            // file, which `read_meta_from_url` downloads later.
+            eprintln!(
+                "Detected CRAN/Bioconductor URL ({}), full metadata will be fetched later.",
+                cran_repo.package
+            );
+            Ok(UrlMetadata {
+                pname: cran_repo.package.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: if cran_repo.bioconductor {
+                    format!("https://bioconductor.org/packages/{}", cran_repo.package)
+                } else {
+                    format!("https://cran.r-project.org/package={}", cran_repo.package)
+                },
+                fetcher: if cran_repo.bioconductor {
+                    Fetcher::url
+                } else {
+                    Fetcher::cran
+                },
+            })
+        }
+        Repo::Hex(hex_repo) => {
+            // Description, licenses and dependencies are filled later by
+            // `read_meta_from_url`.
+            eprintln!(
+                "Detected Hex URL ({}), full metadata will be fetched later.",
+                hex_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: hex_repo.name.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!("https://hex.pm/packages/{}", hex_repo.name),
+                fetcher: Fetcher::hex,
+            })
+        }
+        Repo::Packagist(packagist_repo) => {
+            // The release and its source repository are resolved later by
+            // `read_meta_from_url`.
+            eprintln!(
+                "Detected Packagist URL ({}/{}), full metadata will be fetched later.",
+                packagist_repo.vendor, packagist_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: packagist_repo.name.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!(
+                    "https://packagist.org/packages/{}/{}",
+                    packagist_repo.vendor, packagist_repo.name
+                ),
+                fetcher: Fetcher::github,
+            })
+        }
+        Repo::Maven(maven_repo) => {
+            // The version, POM and source repository are resolved later by
+            // `read_meta_from_url`.
+            eprintln!(
+                "Detected Maven artifact ({}:{}), full metadata will be fetched later.",
+                maven_repo.group_id, maven_repo.artifact_id
+            );
+            Ok(UrlMetadata {
+                pname: maven_repo.artifact_id.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!(
+                    "https://central.sonatype.com/artifact/{}/{}",
+                    maven_repo.group_id, maven_repo.artifact_id
+                ),
+                fetcher: Fetcher::github,
+            })
+        }
+        Repo::LuaRocks(luarocks_repo) => {
+            // The rockspec and its source are resolved later by
+            // `read_meta_from_url`.
+            eprintln!(
+                "Detected LuaRocks URL ({}/{}), full metadata will be fetched later.",
+                luarocks_repo.user, luarocks_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: luarocks_repo.name.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!(
+                    "https://luarocks.org/modules/{}/{}",
+                    luarocks_repo.user, luarocks_repo.name
+                ),
+                fetcher: Fetcher::github,
+            })
+        }
+        Repo::Pub(pub_repo) => {
+            // Description, homepage and the Flutter check are filled later
+            // by `read_meta_from_url`.
+            eprintln!(
+                "Detected pub.dev URL ({}), full metadata will be fetched later.",
+                pub_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: pub_repo.name.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!("https://pub.dev/packages/{}", pub_repo.name),
+                fetcher: Fetcher::pubdev,
+            })
+        }
+        Repo::Opam(opam_repo) => {
+            // The opam file is read later by `read_meta_from_url`.
+            eprintln!(
+                "Detected opam URL ({}), full metadata will be fetched later.",
+                opam_repo.name
+            );
+            Ok(UrlMetadata {
+                pname: opam_repo.name.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!("https://opam.ocaml.org/packages/{}", opam_repo.name),
+                fetcher: Fetcher::url,
+            })
+        }
+        Repo::GoModule(go_module_repo) => {
+            // The module root, its repository and release are resolved
+            // later by `read_meta_from_url`.
+            eprintln!(
+                "Detected Go module URL ({}), full metadata will be fetched later.",
+                go_module_repo.path
+            );
+            Ok(UrlMetadata {
+                pname: go_module_repo
+                    .path
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(&go_module_repo.path)
+                    .to_string(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage: format!("https://pkg.go.dev/{}", go_module_repo.path),
+                fetcher: if go_module_repo.path.starts_with("github.com/") {
+                    Fetcher::github
+                } else {
+                    Fetcher::git
+                },
+            })
+        }
         Repo::Gitea(gitea_repo) => {
             // For interactive metadata extraction we don't perform the
             // network call here; full metadata is filled later via
@@ -541,11 +854,78 @@ pub fn extract_metadata_from_url(url: &str) -> Result<UrlMetadata> {
                 fetcher: Fetcher::gitlab,
             })
         }
+        Repo::Sourcehut(sourcehut_repo) => {
+            // For interactive metadata extraction we don't perform the
+            // network call here; full metadata is filled later via
+            // `read_meta_from_url`. We surface only what we need to
+            // pre-populate the prompts.
+            eprintln!(
+                "Detected Sourcehut URL ({}/{}), full metadata will be fetched later.",
+                sourcehut_repo.owner, sourcehut_repo.repo
+            );
+            let homepage = format!(
+                "https://{}/{}/{}",
+                sourcehut_repo.domain, sourcehut_repo.owner, sourcehut_repo.repo
+            );
+            Ok(UrlMetadata {
+                pname: sourcehut_repo.repo.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage,
+                fetcher: Fetcher::sourcehut,
+            })
+        }
+        Repo::Bitbucket(bitbucket_repo) => {
+            // Same as Sourcehut: metadata is filled later via
+            // `read_meta_from_url`.
+            eprintln!(
+                "Detected Bitbucket URL ({}/{}), full metadata will be fetched later.",
+                bitbucket_repo.workspace, bitbucket_repo.repo
+            );
+            let homepage = format!(
+                "https://bitbucket.org/{}/{}",
+                bitbucket_repo.workspace, bitbucket_repo.repo
+            );
+            Ok(UrlMetadata {
+                pname: bitbucket_repo.repo.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage,
+                fetcher: Fetcher::bitbucket,
+            })
+        }
+        Repo::Git(git_repo) => {
+            // Plain git hosting has no metadata API; only the name can be
+            // derived from the URL.
+            let homepage = if git_repo.url.starts_with("http") {
+                git_repo.url.trim_end_matches(".git").to_owned()
+            } else {
+                "CHANGE".to_string()
+            };
+            Ok(UrlMetadata {
+                pname: git_repo.repo.clone(),
+                license: "CHANGE".to_string(),
+                description: "CHANGE".to_string(),
+                homepage,
+                fetcher: Fetcher::git,
+            })
+        }
+        Repo::Archive(archive) => Ok(UrlMetadata {
+            pname: archive.pname.unwrap_or_else(|| "CHANGE".to_string()),
+            license: "CHANGE".to_string(),
+            description: "CHANGE".to_string(),
+            homepage: "CHANGE".to_string(),
+            fetcher: if archive.is_zip {
+                Fetcher::zip
+            } else {
+                Fetcher::url
+            },
+        }),
     }
 }
 
 /// Prompt for URL (GitHub or PyPI) and return URL with extracted metadata
-pub fn prompt_url() -> Result<Option<(String, UrlMetadata)>> {
+pub fn prompt_url(forges: &HashMap<String, ForgeConfig>) -> Result<Option<(String, UrlMetadata)>> {
     let should_provide =
         Confirm::new("Do you want to fetch metadata from a URL (GitHub/PyPI/Gitea)?")
             .with_default(false)
@@ -565,6 +945,8 @@ pub fn prompt_url() -> Result<Option<(String, UrlMetadata)>> {
             "https://codeberg.org/",
             "https://gitea.com/",
             "https://gitlab.com/",
+            "https://git.sr.ht/",
+            "https://bitbucket.org/",
         ]))
         .prompt()?;
 
@@ -573,7 +955,7 @@ pub fn prompt_url() -> Result<Option<(String, UrlMetadata)>> {
     }
 
     // Immediately fetch metadata from the URL
-    match extract_metadata_from_url(&url) {
+    match extract_metadata_from_url(&url, forges) {
         Ok(metadata) => {
             eprintln!("✓ Successfully fetched metadata!");
             Ok(Some((url, metadata)))
@@ -588,13 +970,50 @@ pub fn prompt_url() -> Result<Option<(String, UrlMetadata)>> {
 }
 
 /// Parse URL into a Repo type
-fn parse_url(url: &str) -> Result<Repo> {
+fn parse_url(url: &str, forges: &HashMap<String, ForgeConfig>) -> Result<Repo> {
     let normalized_url = url
         .trim()
         .trim_start_matches("http://")
         .trim_start_matches("https://")
         .trim_end_matches('/');
 
+    if let Some(archive) = parse_archive_url(url) {
+        return Ok(Repo::Archive(archive));
+    }
+
+    if let Some(opam_repo) = parse_local_opam_url(url) {
+        return Ok(Repo::Opam(opam_repo));
+    }
+
+    if let Some(maven_repo) = parse_maven_coordinates(url) {
+        return Ok(Repo::Maven(maven_repo));
+    }
+
+    if !url.trim().starts_with("http") {
+        if let Some(git_repo) = parse_generic_git_url(url) {
+            return Ok(Repo::Git(git_repo));
+        }
+    }
+
+    // Domains declared in the user's [forges] table take precedence over
+    // the built-in hosts
+    if let Some((domain, forge)) = normalized_url
+        .split('/')
+        .next()
+        .and_then(|domain| forges.get_key_value(domain))
+    {
+        return configured_forge_repo(domain, &normalized_url[domain.len()..], forge);
+    }
+
+    // Checked first: module paths often contain "github.com/<owner>/<repo>"
+    if GO_MODULE_URL_REGEX.is_match(normalized_url) {
+        let captures = GO_MODULE_URL_REGEX.captures(normalized_url).unwrap();
+        return Ok(Repo::GoModule(GoModuleRepo {
+            path: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        }));
+    }
+
     if GITHUB_URL_REGEX.is_match(normalized_url) {
         let captures = GITHUB_URL_REGEX.captures(normalized_url).unwrap();
         Ok(Repo::Github(GithubRepo {
@@ -606,56 +1025,171 @@ fn parse_url(url: &str) -> Result<Repo> {
         Ok(Repo::Pypi(PypiRepo {
             project: captures.get(1).unwrap().as_str().to_owned(),
         }))
+    } else if CRATES_URL_REGEX.is_match(normalized_url) {
+        let captures = CRATES_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Crates(CratesRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+        }))
+    } else if NPM_URL_REGEX.is_match(normalized_url) {
+        let captures = NPM_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Npm(NpmRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+        }))
+    } else if HACKAGE_URL_REGEX.is_match(normalized_url) {
+        let captures = HACKAGE_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Hackage(HackageRepo {
+            package: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        }))
+    } else if CRAN_URL_REGEX.is_match(normalized_url) {
+        let captures = CRAN_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Cran(CranRepo {
+            package: captures.get(1).unwrap().as_str().to_owned(),
+            bioconductor: false,
+        }))
+    } else if BIOCONDUCTOR_URL_REGEX.is_match(normalized_url) {
+        let captures = BIOCONDUCTOR_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Cran(CranRepo {
+            package: captures.get(1).unwrap().as_str().to_owned(),
+            bioconductor: true,
+        }))
+    } else if HEX_URL_REGEX.is_match(normalized_url) {
+        let captures = HEX_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Hex(HexRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        }))
+    } else if PACKAGIST_URL_REGEX.is_match(normalized_url) {
+        let captures = PACKAGIST_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Packagist(PackagistRepo {
+            vendor: captures.get(1).unwrap().as_str().to_owned(),
+            name: captures.get(2).unwrap().as_str().to_owned(),
+        }))
+    } else if MAVEN_CENTRAL_URL_REGEX.is_match(normalized_url) {
+        let captures = MAVEN_CENTRAL_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Maven(MavenRepo {
+            group_id: captures.get(1).unwrap().as_str().to_owned(),
+            artifact_id: captures.get(2).unwrap().as_str().to_owned(),
+            version: captures.get(3).map(|m| m.as_str().to_owned()),
+        }))
+    } else if LUAROCKS_URL_REGEX.is_match(normalized_url) {
+        let captures = LUAROCKS_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::LuaRocks(LuaRocksRepo {
+            user: captures.get(1).unwrap().as_str().to_owned(),
+            name: captures.get(2).unwrap().as_str().to_owned(),
+            version: captures.get(3).map(|m| m.as_str().to_owned()),
+        }))
+    } else if PUB_URL_REGEX.is_match(normalized_url) {
+        let captures = PUB_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Pub(PubRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        }))
+    } else if OPAM_URL_REGEX.is_match(normalized_url) {
+        let captures = OPAM_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Opam(OpamRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(3).map(|m| m.as_str().to_owned()),
+            repository: None,
+        }))
+    } else if METACPAN_URL_REGEX.is_match(normalized_url) {
+        let captures = METACPAN_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Cpan(CpanRepo {
+            name: captures.get(2).unwrap().as_str().to_owned(),
+            module: captures.get(1).unwrap().as_str() == "pod",
+        }))
+    } else if RUBYGEMS_URL_REGEX.is_match(normalized_url) {
+        let captures = RUBYGEMS_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Rubygems(RubygemsRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+        }))
+    } else if SOURCEHUT_URL_REGEX.is_match(normalized_url) {
+        let captures = SOURCEHUT_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Sourcehut(SourcehutRepo::new(
+            "git.sr.ht",
+            captures.get(1).unwrap().as_str(),
+            captures.get(2).unwrap().as_str(),
+        )))
+    } else if BITBUCKET_URL_REGEX.is_match(normalized_url) {
+        let captures = BITBUCKET_URL_REGEX.captures(normalized_url).unwrap();
+        Ok(Repo::Bitbucket(BitbucketRepo {
+            workspace: captures.get(1).unwrap().as_str().to_owned(),
+            repo: captures.get(2).unwrap().as_str().to_owned(),
+        }))
     } else if GITEA_HOSTS.iter().any(|h| normalized_url.starts_with(h)) {
         let captures = GITEA_URL_REGEX
             .captures(normalized_url)
             .ok_or_else(|| anyhow!("Invalid Gitea URL"))?;
-        Ok(Repo::Gitea(GiteaRepo {
-            domain: captures.name("domain").unwrap().as_str().to_owned(),
-            owner: captures.name("owner").unwrap().as_str().to_owned(),
-            repo: captures.name("repo").unwrap().as_str().to_owned(),
-        }))
+        Ok(Repo::Gitea(GiteaRepo::new(
+            captures.name("domain").unwrap().as_str(),
+            captures.name("owner").unwrap().as_str(),
+            captures.name("repo").unwrap().as_str(),
+        )))
+    } else if let Some(git_repo) = parse_generic_git_url(url) {
+        Ok(Repo::Git(git_repo))
     } else {
         Err(anyhow!(
-            "Invalid URL. Only github.com, pypi.org, and known Gitea hosts ({}) are supported.",
+            "Invalid URL. Only github.com, pypi.org, git.sr.ht, bitbucket.org, known Gitea hosts ({}), forges declared in the [forges] table of config.toml, and git URLs are supported.",
             GITEA_HOSTS.join(", "),
         ))
     }
 }
 
-/// Fetch versions from GitHub
-fn fetch_github_versions(repo: &GithubRepo) -> Result<Vec<(String, String)>> {
+/// Fetch versions from GitHub, split into prefix and version by `scheme`
+fn fetch_github_versions(repo: &GithubRepo, scheme: &TagScheme) -> Result<Vec<(String, String)>> {
     eprintln!("Fetching releases from GitHub...");
-    let mut releases = fetch_github_release_info(repo);
+    let releases = fetch_github_release_info(repo);
 
     if releases.is_empty() {
         return Err(anyhow!("No releases found for this repository"));
     }
 
-    // Filter out prereleases and sort
-    releases = releases.into_iter().filter(|r| !r.prerelease).collect();
-    releases.sort_by(|a, b| {
-        VersionCompare::compare(&b.tag_name, &a.tag_name)
-            .unwrap()
-            .ord()
-            .unwrap()
+    // Filter out prereleases and tags outside the scheme, then sort
+    let mut tags: Vec<(String, _)> = releases
+        .into_iter()
+        .filter(|r| !r.prerelease)
+        .filter_map(|r| scheme.split(&r.tag_name).map(|split| (r.tag_name, split)))
+        .collect();
+    tags.sort_by(|(_, a), (_, b)| {
+        VersionCompare::compare(&b.version, &a.version)
+            .ok()
+            .and_then(|c| c.ord())
+            .unwrap_or(std::cmp::Ordering::Equal)
     });
 
     // Return (display_name, actual_version) tuples
-    let versions: Vec<(String, String)> = releases
-        .iter()
-        .map(|r| {
-            let parsed = VERSION_REGEX.captures(&r.tag_name).unwrap();
-            let version = parsed.get(2).unwrap().as_str();
-            let prefix = parsed.get(1).unwrap().as_str();
-            let display = format!("{} (tag: {})", version, r.tag_name);
-            (display, format!("{}|{}", prefix, version))
+    let versions: Vec<(String, String)> = tags
+        .into_iter()
+        .map(|(tag, split)| {
+            let display = format!("{} (tag: {})", split.version, tag);
+            (display, format!("{}|{}", split.prefix, split.version))
         })
         .collect();
 
     Ok(versions)
 }
 
+/// Fetch non-yanked versions from crates.io
+fn fetch_crate_versions(repo: &CratesRepo) -> Result<Vec<(String, String)>> {
+    eprintln!("Fetching releases from crates.io...");
+    let response = fetch_crate_info(CRATES_IO_API_BASE, repo)?;
+
+    let mut versions: Vec<String> = response
+        .versions
+        .into_iter()
+        .filter(|v| !v.yanked && STABLE_RELEASE_REGEX.is_match(&v.num))
+        .map(|v| v.num)
+        .collect();
+
+    if versions.is_empty() {
+        return Err(anyhow!("No stable versions found on crates.io"));
+    }
+
+    versions.sort_by(|a, b| VersionCompare::compare(b, a).unwrap().ord().unwrap());
+
+    Ok(versions.into_iter().map(|v| (v.clone(), v)).collect())
+}
+
 /// Fetch versions from PyPI
 fn fetch_pypi_versions(repo: &PypiRepo) -> Result<Vec<(String, String)>> {
     eprintln!("Fetching releases from PyPI...");
@@ -678,15 +1212,21 @@ fn fetch_pypi_versions(repo: &PypiRepo) -> Result<Vec<(String, String)>> {
     Ok(versions.into_iter().map(|v| (v.clone(), v)).collect())
 }
 
-/// Prompt for version with auto-fetch from URL if available
-pub fn prompt_version(url: Option<&str>, default: &str) -> Result<String> {
+/// Prompt for version with auto-fetch from URL if available. Tags are
+/// split into prefix and version by `tag_scheme`.
+pub fn prompt_version(
+    url: Option<&str>,
+    default: &str,
+    tag_scheme: &TagScheme,
+    forges: &HashMap<String, ForgeConfig>,
+) -> Result<String> {
     let mut fetched_versions: Vec<(String, String)> = Vec::new();
 
     if let Some(url_str) = url {
-        if let Ok(repo) = parse_url(url_str) {
+        if let Ok(repo) = parse_url(url_str, forges) {
             match repo {
                 Repo::Github(gh_repo) => {
-                    if let Ok(versions) = fetch_github_versions(&gh_repo) {
+                    if let Ok(versions) = fetch_github_versions(&gh_repo, tag_scheme) {
                         fetched_versions = versions;
                     }
                 }
@@ -695,6 +1235,60 @@ pub fn prompt_version(url: Option<&str>, default: &str) -> Result<String> {
                         fetched_versions = versions;
                     }
                 }
+                Repo::Crates(crates_repo) => {
+                    if let Ok(versions) = fetch_crate_versions(&crates_repo) {
+                        fetched_versions = versions;
+                    }
+                }
+                Repo::Npm(_) => {
+                    // Version fetching for npm is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Hackage(_) => {
+                    // Version fetching for Hackage is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Rubygems(_) => {
+                    // Version fetching for RubyGems is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Cpan(_) => {
+                    // Version fetching for MetaCPAN is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Cran(_) => {
+                    // Version fetching for CRAN and Bioconductor is handled
+                    // later by `read_meta_from_url`; skip prompt-time
+                    // enumeration.
+                }
+                Repo::Hex(_) => {
+                    // Version fetching for Hex is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Packagist(_) => {
+                    // Version fetching for Packagist is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Maven(_) => {
+                    // Version fetching for Maven Central is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::LuaRocks(_) => {
+                    // Version fetching for LuaRocks is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Pub(_) => {
+                    // Version fetching for pub.dev is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Opam(_) => {
+                    // Version fetching for opam is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::GoModule(_) => {
+                    // Version fetching for Go modules is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
                 Repo::Gitea(_) => {
                     // Version fetching for Gitea is handled later by
                     // `read_meta_from_url`; skip prompt-time enumeration.
@@ -703,6 +1297,24 @@ pub fn prompt_version(url: Option<&str>, default: &str) -> Result<String> {
                     // Version fetching for GitLab is handled later by
                     // `read_meta_from_url`; skip prompt-time enumeration.
                 }
+                Repo::Sourcehut(_) => {
+                    // Version fetching for Sourcehut is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Bitbucket(_) => {
+                    // Version fetching for Bitbucket is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Git(_) => {
+                    // Version fetching for git URLs is handled later by
+                    // `read_meta_from_url`; skip prompt-time enumeration.
+                }
+                Repo::Archive(archive) => {
+                    // The archive filename is the only source of versions
+                    if let Some(version) = archive.version {
+                        fetched_versions = vec![(version.clone(), version)];
+                    }
+                }
             }
         }
     }
@@ -840,7 +1452,15 @@ pub fn prompt_fetcher(default: Fetcher, _template: &Template) -> Result<Fetcher>
     let options = vec![
         ("github", "fetchFromGitHub"),
         ("gitlab", "fetchFromGitLab"),
+        ("sourcehut", "fetchFromSourcehut"),
+        ("bitbucket", "fetchFromBitbucket"),
+        ("git", "fetchgit"),
         ("pypi", "fetchPypi"),
+        ("crates", "fetchCrate"),
+        ("hackage", "callHackageDirect"),
+        ("rubygems", "fetchurl (.gem)"),
+        ("hex", "fetchHex"),
+        ("pubdev", "fetchzip (pub.dev)"),
         ("url", "fetchurl"),
         ("zip", "fetchzip"),
     ];
@@ -868,7 +1488,13 @@ pub fn prompt_fetcher(default: Fetcher, _template: &Template) -> Result<Fetcher>
     Ok(match fetcher_name {
         "github" => Fetcher::github,
         "gitlab" => Fetcher::gitlab,
+        "sourcehut" => Fetcher::sourcehut,
+        "bitbucket" => Fetcher::bitbucket,
+        "git" => Fetcher::git,
         "pypi" => Fetcher::pypi,
+        "crates" => Fetcher::crates,
+        "hackage" => Fetcher::hackage,
+        "rubygems" => Fetcher::rubygems,
         "url" => Fetcher::url,
         "zip" => Fetcher::zip,
         _ => Fetcher::github,
@@ -921,6 +1547,7 @@ pub fn prompt_homepage(default: &str) -> Result<String> {
 pub fn run_interactive_mode(
     initial_template: Option<Template>,
     user_config: Option<&UserConfig>,
+    tag_scheme: TagScheme,
 ) -> Result<InteractiveData> {
     run_interactive_mode_with_defaults(
         initial_template,
@@ -929,6 +1556,7 @@ pub fn run_interactive_mode(
         None,       // default_pname
         None,       // inferred_deps
         false,      // is_local_init
+        tag_scheme,
     )
 }
 
@@ -939,6 +1567,7 @@ pub fn run_interactive_mode_with_defaults(
     default_pname: Option<String>,
     inferred_deps: Option<(Vec<String>, Vec<String>)>,
     is_local_init: bool,
+    tag_scheme: TagScheme,
 ) -> Result<InteractiveData> {
     println!("\n=== Interactive nix-template ===\n");
 
@@ -955,8 +1584,15 @@ pub fn run_interactive_mode_with_defaults(
         prompt_template_type(initial_template)?
     };
 
+    // Self-hosted forges declared in the user config
+    let forges = user_config.map(|c| c.forges.clone()).unwrap_or_default();
+
     // 2. URL (optional) - skip if we're in local init mode
-    let url_with_metadata = if is_local_init { None } else { prompt_url()? };
+    let url_with_metadata = if is_local_init {
+        None
+    } else {
+        prompt_url(&forges)?
+    };
 
     // Extract metadata for defaults
     let metadata = url_with_metadata
@@ -970,7 +1606,7 @@ pub fn run_interactive_mode_with_defaults(
 
     // 4. Version (with auto-fetch if URL provided)
     let url_str = url_with_metadata.as_ref().map(|(url, _)| url.as_str());
-    let version = prompt_version(url_str, "0.0.1")?;
+    let version = prompt_version(url_str, "0.0.1", &tag_scheme, &forges)?;
 
     // 5. License (pre-filled from URL if available)
     let license = prompt_license(&metadata.license)?;
@@ -1087,6 +1723,7 @@ pub fn run_interactive_mode_with_defaults(
         infer_deps,
         include_prereleases,
         preinferred_deps: inferred_deps,
+        tag_scheme,
     })
 }
 
@@ -1116,4 +1753,6 @@ pub struct InteractiveData {
     pub include_prereleases: bool,
     /// Pre-inferred dependencies (from init mode). (buildInputs, nativeBuildInputs)
     pub preinferred_deps: Option<(Vec<String>, Vec<String>)>,
+    /// How upstream tags split into prefix and version (`--tag-regex`).
+    pub tag_scheme: TagScheme,
 }
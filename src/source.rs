@@ -3,11 +3,12 @@
 //! This module provides a single `materialise_source` function that fetches
 //! and unpacks a source tree into the Nix store, returning the store path.
 //! Used by template detection, Rust dependency inference, and Go dependency
-//! inference.
+//! inference. `extract_appimage` does the same for the contents of an
+//! AppImage.
 
 use crate::types::{ExpressionInfo, Fetcher};
 use log::debug;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 const LOG_TARGET: &str = "nix-template::source";
@@ -15,7 +16,10 @@ const LOG_TARGET: &str = "nix-template::source";
 /// Materialise the source tree referenced by `info` into the Nix store
 /// and return the resulting `/nix/store/...-source` path.
 ///
-/// Supports `fetchFromGitHub`, `fetchFromGitea`, and `fetchFromGitLab`.
+/// Supports `fetchFromGitHub`, `fetchFromGitea`, `fetchFromGitLab`,
+/// `fetchFromSourcehut`, `fetchFromBitbucket`, `fetchgit`, `fetchCrate`,
+/// Hackage sdists, `fetchzip`, and `fetchurl` including CPAN and CRAN
+/// tarballs (tarballs are unpacked with `srcOnly`).
 /// Returns `None` for fetchers we can't cleanly drive headlessly or when
 /// the source hash is not yet known.
 pub fn materialise_source(info: &ExpressionInfo) -> Option<PathBuf> {
@@ -28,35 +32,115 @@ pub fn materialise_source(info: &ExpressionInfo) -> Option<PathBuf> {
         return None;
     }
 
-    let rev = if info.tag_prefix.is_empty() {
-        info.version.clone()
+    let rev = if !info.src_rev.is_empty() {
+        info.src_rev.clone()
     } else {
-        format!("{}{}", info.tag_prefix, info.version)
+        info.upstream_tag()
     };
 
+    let mut git_options = String::new();
+    if info.fetch_submodules {
+        git_options.push_str(" fetchSubmodules = true;");
+    }
+    if info.fetch_lfs {
+        git_options.push_str(" fetchLFS = true;");
+    }
+
+    // Archive URLs are stored with the version templated out
+    let src_url = info.src_url.replace("${finalAttrs.version}", &info.version);
+
     let expr = match info.fetcher {
         Fetcher::github => format!(
-            "(import <nixpkgs> {{}}).fetchFromGitHub {{ owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; sha256 = \"{sha}\"; }}",
+            "(import <nixpkgs> {{}}).fetchFromGitHub {{ owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; sha256 = \"{sha}\";{opts} }}",
             owner = info.owner,
             repo = info.pname,
             rev = rev,
             sha = info.src_sha,
+            opts = git_options,
         ),
         Fetcher::gitea => format!(
-            "(import <nixpkgs> {{}}).fetchFromGitea {{ domain = \"{domain}\"; owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; sha256 = \"{sha}\"; }}",
+            "(import <nixpkgs> {{}}).fetchFromGitea {{ domain = \"{domain}\"; owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; sha256 = \"{sha}\";{opts} }}",
             domain = info.domain,
             owner = info.owner,
             repo = info.pname,
             rev = rev,
             sha = info.src_sha,
+            opts = git_options,
         ),
         Fetcher::gitlab => format!(
-            "(import <nixpkgs> {{}}).fetchFromGitLab {{ owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; sha256 = \"{sha}\"; }}",
+            "(import <nixpkgs> {{}}).fetchFromGitLab {{ domain = \"{domain}\"; owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; sha256 = \"{sha}\";{opts} }}",
+            domain = info.domain,
+            owner = info.owner,
+            repo = info.pname,
+            rev = rev,
+            sha = info.src_sha,
+            opts = git_options,
+        ),
+        Fetcher::sourcehut => format!(
+            "(import <nixpkgs> {{}}).fetchFromSourcehut {{ domain = \"{domain}\"; owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; hash = \"{sha}\";{opts} }}",
+            domain = info.domain,
+            owner = info.owner,
+            repo = info.pname,
+            rev = rev,
+            sha = info.src_sha,
+            opts = git_options,
+        ),
+        Fetcher::bitbucket => format!(
+            "(import <nixpkgs> {{}}).fetchFromBitbucket {{ owner = \"{owner}\"; repo = \"{repo}\"; rev = \"{rev}\"; hash = \"{sha}\"; }}",
             owner = info.owner,
             repo = info.pname,
             rev = rev,
             sha = info.src_sha,
         ),
+        Fetcher::git => format!(
+            "(import <nixpkgs> {{}}).fetchgit {{ url = \"{url}\"; rev = \"{rev}\"; hash = \"{sha}\";{opts} }}",
+            url = src_url,
+            rev = rev,
+            sha = info.src_sha,
+            opts = git_options,
+        ),
+        Fetcher::crates => format!(
+            "(import <nixpkgs> {{}}).fetchCrate {{ pname = \"{pname}\"; version = \"{version}\"; hash = \"{sha}\"; }}",
+            pname = info.pname,
+            version = info.version,
+            sha = info.src_sha,
+        ),
+        Fetcher::hackage => format!(
+            "(import <nixpkgs> {{}}).fetchzip {{ url = \"mirror://hackage/{pname}-{version}/{pname}-{version}.tar.gz\"; hash = \"{sha}\"; }}",
+            pname = info.pname,
+            version = info.version,
+            sha = info.src_sha,
+        ),
+        Fetcher::hex => format!(
+            "(import <nixpkgs> {{}}).beamPackages.fetchHex {{ pkg = \"{pname}\"; version = \"{version}\"; sha256 = \"{sha}\"; }}",
+            pname = info.pname,
+            version = info.version,
+            sha = info.src_sha,
+        ),
+        Fetcher::pubdev => format!(
+            "(import <nixpkgs> {{}}).fetchzip {{ url = \"https://pub.dev/api/archives/{pname}-{version}.tar.gz\"; stripRoot = false; hash = \"{sha}\"; }}",
+            pname = info.pname,
+            version = info.version,
+            sha = info.src_sha,
+        ),
+        Fetcher::zip => format!(
+            "(import <nixpkgs> {{}}).fetchzip {{ url = \"{url}\"; hash = \"{sha}\"; }}",
+            url = src_url,
+            sha = info.src_sha,
+        ),
+        // fetchurl's flat hash can't be reused for fetchzip, so unpack the
+        // tarball with the stdenv unpack phase instead.
+        Fetcher::cran => format!(
+            "let pkgs = import <nixpkgs> {{}}; in pkgs.srcOnly {{ name = \"source\"; src = pkgs.fetchurl {{ urls = [ \"mirror://cran/src/contrib/{pname}_{version}.tar.gz\" \"mirror://cran/src/contrib/Archive/{pname}/{pname}_{version}.tar.gz\" ]; hash = \"{sha}\"; }}; }}",
+            pname = info.pname,
+            version = info.version,
+            sha = info.src_sha,
+        ),
+        Fetcher::url | Fetcher::cpan => format!(
+            "let pkgs = import <nixpkgs> {{}}; in pkgs.srcOnly {{ name = \"source\"; nativeBuildInputs = [ pkgs.unzip ]; src = pkgs.fetchurl {{ url = \"{url}\"; hash = \"{sha}\"; }}; }}",
+            url = src_url,
+            sha = info.src_sha,
+        ),
         _ => {
             debug!(
                 target: LOG_TARGET,
@@ -67,9 +151,33 @@ pub fn materialise_source(info: &ExpressionInfo) -> Option<PathBuf> {
         }
     };
 
+    nix_build(&expr)
+}
+
+/// Extract the AppImage at `url` with `appimageTools.extract` and return
+/// the `/nix/store/...-extracted` path of its contents.
+///
+/// Returns `None` when the hash is not yet known or the build fails.
+pub fn extract_appimage(info: &ExpressionInfo, url: &str) -> Option<PathBuf> {
+    if info.src_sha.is_empty() || info.src_sha == crate::types::FAKE_SRI_HASH {
+        debug!(target: LOG_TARGET, "src_sha not yet known; skipping AppImage extraction");
+        return None;
+    }
+
+    nix_build(&format!(
+        "let pkgs = import <nixpkgs> {{}}; in pkgs.appimageTools.extract {{ pname = \"{pname}\"; version = \"{version}\"; src = pkgs.fetchurl {{ url = \"{url}\"; hash = \"{sha}\"; }}; }}",
+        pname = info.pname,
+        version = info.version,
+        url = url,
+        sha = info.src_sha,
+    ))
+}
+
+/// Build `expr` without a GC root and return its output path.
+fn nix_build(expr: &str) -> Option<PathBuf> {
     let output = Command::new("nix-build")
         .args(&["--no-out-link", "-E"])
-        .arg(&expr)
+        .arg(expr)
         .output();
 
     let output = match output {
@@ -111,3 +219,69 @@ pub fn materialise_source(info: &ExpressionInfo) -> Option<PathBuf> {
     }
     Some(PathBuf::from(path))
 }
+
+/// Git features a source tree relies on which forge archives leave out.
+#[derive(Debug, Default, PartialEq)]
+pub struct GitFeatures {
+    pub submodules: bool,
+    pub lfs: bool,
+}
+
+/// Inspect the root of a materialised source tree for a `.gitmodules` file
+/// and `.gitattributes` entries routed through the LFS filter.
+pub fn detect_git_features(path: &Path) -> GitFeatures {
+    let lfs = std::fs::read_to_string(path.join(".gitattributes"))
+        .map(|attributes| {
+            attributes.lines().any(|line| {
+                let line = line.trim_start();
+                !line.starts_with('#') && line.split_whitespace().any(|attr| attr == "filter=lfs")
+            })
+        })
+        .unwrap_or(false);
+
+    GitFeatures {
+        submodules: path.join(".gitmodules").is_file(),
+        lfs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn detects_submodules_and_lfs() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(detect_git_features(dir.path()), GitFeatures::default());
+
+        fs::write(
+            dir.path().join(".gitmodules"),
+            "[submodule \"vendor/lib\"]\n\tpath = vendor/lib\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".gitattributes"),
+            "# filter=lfs\n*.png filter=lfs diff=lfs merge=lfs -text\n",
+        )
+        .unwrap();
+        assert_eq!(
+            detect_git_features(dir.path()),
+            GitFeatures {
+                submodules: true,
+                lfs: true,
+            }
+        );
+    }
+
+    #[test]
+    fn commented_lfs_attribute_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(".gitattributes"),
+            "# *.bin filter=lfs\n*.sh text eol=lf\n",
+        )
+        .unwrap();
+        assert!(!detect_git_features(dir.path()).lfs);
+    }
+}
@@ -49,6 +49,10 @@ pub enum Template {
     Lua(LuaConfig),
     /// R package (rPackages.buildRPackage)
     R,
+    /// Prebuilt release binaries (stdenvNoCC.mkDerivation + autoPatchelfHook)
+    Binary(BinaryConfig),
+    /// AppImage (appimageTools.wrapType2)
+    AppImage(AppImageConfig),
     /// .NET package (buildDotnetModule)
     Dotnet,
     /// Ruby application (bundlerApp)
@@ -222,6 +226,8 @@ pub enum ElixirVariant {
     Release,
     /// buildMix (for hex.pm libraries)
     Library,
+    /// buildRebar3 (for Erlang libraries on hex.pm built with rebar3)
+    Rebar3,
 }
 
 /// Gradle template configuration: variant, DSL, and JDK version.
@@ -263,6 +269,9 @@ pub struct DartConfig {
     /// Dart SDK version constraint from pubspec.yaml
     /// Not currently used but reserved for future version pinning
     pub dart_version: Option<String>,
+    /// Flutter application, built with buildFlutterApplication
+    #[serde(default)]
+    pub flutter: bool,
 }
 
 /// Haskell template configuration: build system and GHC version.
@@ -342,6 +351,39 @@ pub enum PerlBuildSystem {
     Module,
 }
 
+/// Binary template configuration: one prebuilt release asset per system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinaryConfig {
+    /// Release assets keyed by Nix system, in rendering order
+    pub sources: Vec<BinarySource>,
+    /// Whether the assets are archives to unpack rather than bare executables
+    pub archives: bool,
+}
+
+/// Release asset of the binary template for one Nix system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinarySource {
+    /// Nix system, e.g. "x86_64-linux"
+    pub system: String,
+    /// Download URL with the version replaced by `${finalAttrs.version}`
+    pub url: String,
+    /// Flat SRI hash of the asset, as used by `fetchurl`
+    pub hash: String,
+}
+
+/// AppImage template configuration: desktop integration read from the
+/// extracted AppImage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppImageConfig {
+    /// Desktop entry at the root of the AppImage, e.g. "obsidian.desktop"
+    pub desktop_file: Option<String>,
+    /// Program of the desktop entry's `Exec` key as written, quotes
+    /// included, e.g. "AppRun"
+    pub exec: Option<String>,
+    /// Icons to install: the "usr/share/icons" theme or a single file
+    pub icon: Option<String>,
+}
+
 /// Lua template configuration: variant and version.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LuaConfig {
@@ -402,6 +444,8 @@ pub const CLI_TEMPLATES: &[&str] = &[
     "perl",
     "lua",
     "r",
+    "binary",
+    "appimage",
     "dotnet",
     "ruby",
     "mkshell",
@@ -532,6 +576,7 @@ impl Template {
         Template::Dart(DartConfig {
             executables: Vec::new(), // Will be parsed from pubspec.yaml
             dart_version: None,      // Reserved for future version pinning
+            flutter: false,          // Set when pubspec.yaml depends on Flutter
         })
     }
 
@@ -583,6 +628,23 @@ impl Template {
         })
     }
 
+    /// Create a binary template (release assets are filled in later).
+    pub fn binary() -> Self {
+        Template::Binary(BinaryConfig {
+            sources: Vec::new(),
+            archives: true,
+        })
+    }
+
+    /// Create an AppImage template (desktop integration is filled in later).
+    pub fn appimage() -> Self {
+        Template::AppImage(AppImageConfig {
+            desktop_file: None,
+            exec: None,
+            icon: None,
+        })
+    }
+
     /// Create a default stdenv template.
     pub fn stdenv() -> Self {
         Template::Stdenv(StdenvVariant::Default)
@@ -649,6 +711,7 @@ impl Template {
             "dart" => Ok(Template::Dart(DartConfig {
                 executables: Vec::new(),
                 dart_version: None,
+                flutter: false,
             })),
             "haskell" => Ok(Template::Haskell(HaskellConfig {
                 build_system: HaskellBuildSystem::Cabal,
@@ -675,6 +738,8 @@ impl Template {
                 version: LuaVersion::Lua54,
             })),
             "r" => Ok(Template::R),
+            "binary" => Ok(Template::binary()),
+            "appimage" => Ok(Template::appimage()),
             "dotnet" => Ok(Template::Dotnet),
             "ruby" => Ok(Template::Ruby),
             "mkshell" => Ok(Template::Mkshell),
@@ -719,6 +784,8 @@ impl Template {
             Template::Perl(_) => "perl",
             Template::Lua(_) => "lua",
             Template::R => "r",
+            Template::Binary(_) => "binary",
+            Template::AppImage(_) => "appimage",
             Template::Dotnet => "dotnet",
             Template::Ruby => "ruby",
             Template::Mkshell => "mkshell",
@@ -916,7 +983,6 @@ impl Template {
     }
 
     /// Get Dart config if this is a Dart template.
-    #[allow(dead_code)]
     pub fn dart_config(&self) -> Option<&DartConfig> {
         match self {
             Template::Dart(config) => Some(config),
@@ -925,7 +991,6 @@ impl Template {
     }
 
     /// Get mutable Dart config.
-    #[allow(dead_code)]
     pub fn dart_config_mut(&mut self) -> Option<&mut DartConfig> {
         match self {
             Template::Dart(config) => Some(config),
@@ -1070,6 +1135,40 @@ impl Template {
             _ => None,
         }
     }
+
+    /// Get binary config if this is a binary template.
+    #[cfg(test)]
+    pub fn binary_config(&self) -> Option<&BinaryConfig> {
+        match self {
+            Template::Binary(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Get mutable binary config.
+    pub fn binary_config_mut(&mut self) -> Option<&mut BinaryConfig> {
+        match self {
+            Template::Binary(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Get AppImage config if this is an AppImage template.
+    #[cfg(test)]
+    pub fn appimage_config(&self) -> Option<&AppImageConfig> {
+        match self {
+            Template::AppImage(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Get mutable AppImage config.
+    pub fn appimage_config_mut(&mut self) -> Option<&mut AppImageConfig> {
+        match self {
+            Template::AppImage(config) => Some(config),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for Template {
@@ -1243,6 +1342,33 @@ mod tests {
         assert_eq!(tmpl.maven_config().unwrap().jdk_version, None); // Default inferred from pom.xml
     }
 
+    #[test]
+    fn binary_template_parsing() {
+        let mut tmpl: Template = "binary".parse().unwrap();
+        assert_eq!(tmpl.to_cli_str(), "binary");
+        assert!(tmpl.binary_config().unwrap().sources.is_empty());
+
+        tmpl.binary_config_mut().unwrap().sources.push(BinarySource {
+            system: "x86_64-linux".to_string(),
+            url: "https://example.org/demo-linux-amd64".to_string(),
+            hash: "sha256-demo".to_string(),
+        });
+        assert_eq!(tmpl.binary_config().unwrap().sources.len(), 1);
+    }
+
+    #[test]
+    fn appimage_template_parsing() {
+        let mut tmpl: Template = "appimage".parse().unwrap();
+        assert_eq!(tmpl.to_cli_str(), "appimage");
+        assert_eq!(tmpl.appimage_config().unwrap().desktop_file, None);
+
+        tmpl.appimage_config_mut().unwrap().desktop_file = Some("demo.desktop".to_string());
+        assert_eq!(
+            tmpl.appimage_config().unwrap().desktop_file.as_deref(),
+            Some("demo.desktop")
+        );
+    }
+
     #[test]
     fn maven_config_access() {
         let mut tmpl: Template = "maven".parse().unwrap();
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Paginated response from Bitbucket Cloud's
+/// `/2.0/repositories/<workspace>/<repo>/refs/tags` endpoint. `next` is the
+/// absolute URL of the following page, or `None` on the last page.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitbucketRefsResponse {
+    #[serde(rename = "values")]
+    pub values: Vec<BitbucketRef>,
+
+    #[serde(rename = "next")]
+    pub next: Option<String>,
+
+    #[serde(rename = "page")]
+    pub page: Option<i64>,
+
+    #[serde(rename = "pagelen")]
+    pub pagelen: Option<i64>,
+}
+
+/// Single tag ref, e.g. "v1.0.0"
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitbucketRef {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+/// Bitbucket Cloud repository API response
+/// (`/2.0/repositories/<workspace>/<repo>`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitbucketRepoResponse {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "description")]
+    pub description: Option<String>,
+
+    #[serde(rename = "website")]
+    pub website: Option<String>,
+
+    #[serde(rename = "is_private")]
+    pub is_private: Option<bool>,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of crates.io's `/api/v1/crates/<name>` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CratesResponse {
+    #[serde(rename = "crate")]
+    pub krate: CrateInfo,
+
+    #[serde(rename = "versions")]
+    pub versions: Vec<CrateVersion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrateInfo {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "description")]
+    pub description: Option<String>,
+
+    #[serde(rename = "homepage")]
+    pub homepage: Option<String>,
+
+    #[serde(rename = "repository")]
+    pub repository: Option<String>,
+}
+
+/// Single published version, newest first in the API response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrateVersion {
+    #[serde(rename = "num")]
+    pub num: String,
+
+    #[serde(rename = "yanked")]
+    pub yanked: bool,
+
+    /// SPDX license expression, e.g. "MIT OR Apache-2.0"
+    #[serde(rename = "license")]
+    pub license: Option<String>,
+}
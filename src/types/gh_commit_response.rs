@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Commit as returned by GitHub's `/repos/<owner>/<repo>/commits/<ref>`.
+/// Gitea's `/repos/<owner>/<repo>/commits` list uses the same shape.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhCommitResponse {
+    #[serde(rename = "sha")]
+    pub sha: String,
+
+    #[serde(rename = "commit")]
+    pub commit: GhCommitDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhCommitDetails {
+    #[serde(rename = "committer")]
+    pub committer: GhCommitSignature,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhCommitSignature {
+    /// ISO 8601 timestamp, e.g. "2024-05-01T12:00:00Z"
+    #[serde(rename = "date")]
+    pub date: String,
+}
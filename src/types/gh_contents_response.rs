@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Directory listing of GitHub's `/repos/<owner>/<repo>/contents/<path>`
+/// endpoint
+pub type GhContentsResponse = Vec<GhContentsElement>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhContentsElement {
+    pub name: String,
+    /// "file", "dir", "symlink" or "submodule"
+    #[serde(rename = "type")]
+    pub kind: String,
+}
@@ -58,7 +58,7 @@ pub struct GhReleaseResponseElement {
     published_at: String,
 
     #[serde(rename = "assets")]
-    assets: Vec<Asset>,
+    pub assets: Vec<Asset>,
 
     #[serde(rename = "tarball_url")]
     tarball_url: String,
@@ -82,7 +82,7 @@ pub struct Asset {
     node_id: String,
 
     #[serde(rename = "name")]
-    name: String,
+    pub name: String,
 
     #[serde(rename = "label")]
     label: Option<serde_json::Value>,
@@ -109,7 +109,7 @@ pub struct Asset {
     updated_at: String,
 
     #[serde(rename = "browser_download_url")]
-    browser_download_url: String,
+    pub browser_download_url: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Page of GitHub's `/repos/<owner>/<repo>/tags` endpoint. Gitea's
+/// `/repos/<owner>/<repo>/tags` returns the same shape.
+pub type GhTagResponse = Vec<GhTagElement>;
+
+/// Single tag, e.g. "v1.0.0"
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhTagElement {
+    #[serde(rename = "name")]
+    pub name: String,
+}
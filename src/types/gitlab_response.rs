@@ -153,3 +153,14 @@ pub struct GitlabLicense {
     #[serde(rename = "nickname")]
     pub nickname: Option<String>,
 }
+
+/// GitLab commit (`/projects/<id>/repository/commits/<ref>`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitlabCommitResponse {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    /// ISO 8601 timestamp, e.g. "2024-05-01T12:00:00.000+02:00"
+    #[serde(rename = "committed_date")]
+    pub committed_date: String,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of a GOPROXY's `<module>/@latest` and
+/// `<module>/@v/<version>.info` endpoints
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoProxyInfo {
+    #[serde(rename = "Version")]
+    pub version: String,
+    /// Commit timestamp (RFC 3339)
+    #[serde(rename = "Time", default)]
+    pub time: Option<String>,
+    /// Where the module was fetched from; only reported by recent proxies
+    #[serde(rename = "Origin", default)]
+    pub origin: Option<GoProxyOrigin>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoProxyOrigin {
+    #[serde(rename = "VCS", default)]
+    pub vcs: Option<String>,
+    #[serde(rename = "URL", default)]
+    pub url: Option<String>,
+    /// Directory of the module inside the repository
+    #[serde(rename = "Subdir", default)]
+    pub subdir: Option<String>,
+    /// Full commit hash
+    #[serde(rename = "Hash", default)]
+    pub hash: Option<String>,
+}
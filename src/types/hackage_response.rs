@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of Hackage's `/package/<name>/preferred` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HackagePreferredResponse {
+    /// Versions that aren't deprecated, newest first
+    #[serde(rename = "normal-version", default)]
+    pub normal_version: Vec<String>,
+
+    #[serde(rename = "deprecated-version", default)]
+    pub deprecated_version: Vec<String>,
+}
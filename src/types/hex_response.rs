@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Response of Hex's `/api/packages/<name>` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HexPackage {
+    pub name: String,
+    #[serde(default)]
+    pub meta: HexPackageMeta,
+    /// Newest release that isn't a prerelease
+    pub latest_stable_version: Option<String>,
+    pub latest_version: Option<String>,
+    pub html_url: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HexPackageMeta {
+    pub description: Option<String>,
+    /// SPDX identifiers
+    #[serde(default)]
+    pub licenses: Vec<String>,
+    /// Named links, e.g. "GitHub" -> repository URL
+    #[serde(default)]
+    pub links: HashMap<String, String>,
+}
+
+/// Response of Hex's `/api/packages/<name>/releases/<version>` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HexRelease {
+    pub version: String,
+    /// Dependencies keyed by package name
+    #[serde(default)]
+    pub requirements: HashMap<String, HexRequirement>,
+    #[serde(default)]
+    pub meta: HexReleaseMeta,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HexRequirement {
+    #[serde(default)]
+    pub optional: bool,
+    pub requirement: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HexReleaseMeta {
+    /// e.g. ["mix"] or ["rebar3"]
+    #[serde(default)]
+    pub build_tools: Vec<String>,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of MetaCPAN's `/v1/release/<Dist>` and
+/// `/v1/release/<author>/<Dist>-<version>` endpoints
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CpanRelease {
+    pub distribution: String,
+    pub version: String,
+    /// PAUSE id of the uploader, e.g. "ETHER"
+    pub author: String,
+    /// e.g. "https://cpan.metacpan.org/authors/id/E/ET/ETHER/Moose-2.2207.tar.gz"
+    pub download_url: String,
+    pub checksum_sha256: Option<String>,
+    #[serde(rename = "abstract")]
+    pub abstract_: Option<String>,
+    /// CPAN::Meta license strings, e.g. "perl_5"
+    #[serde(default)]
+    pub license: Vec<String>,
+    #[serde(default)]
+    pub resources: CpanResources,
+    /// The release's META.json
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CpanResources {
+    pub homepage: Option<String>,
+    pub repository: Option<CpanRepository>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CpanRepository {
+    pub url: Option<String>,
+    pub web: Option<String>,
+}
+
+/// Response of MetaCPAN's `/v1/module/<Module>` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CpanModule {
+    /// Distribution shipping the module, e.g. "Moose" for "Moose::Role"
+    pub distribution: String,
+}
@@ -4,15 +4,43 @@ use std::collections::HashMap;
 use clap::arg_enum;
 use regex::{Captures, Regex};
 
+pub mod bitbucket_response;
+pub mod crates_response;
+pub mod gh_commit_response;
+pub mod gh_contents_response;
 pub mod gh_release_response;
 pub mod gh_repo_response;
+pub mod gh_tag_response;
 pub mod gitlab_response;
+pub mod goproxy_response;
+pub mod hackage_response;
+pub mod hex_response;
+pub mod metacpan_response;
+pub mod npm_response;
+pub mod packagist_response;
+pub mod pub_response;
 pub mod pypi;
+pub mod rubygems_response;
+pub mod sourcehut_response;
 
+pub use bitbucket_response::*;
+pub use crates_response::*;
+pub use gh_commit_response::*;
+pub use gh_contents_response::*;
 pub use gh_release_response::*;
 pub use gh_repo_response::*;
+pub use gh_tag_response::*;
 pub use gitlab_response::*;
+pub use goproxy_response::*;
+pub use hackage_response::*;
+pub use hex_response::*;
+pub use metacpan_response::*;
+pub use npm_response::*;
+pub use packagist_response::*;
+pub use pub_response::*;
 pub use pypi::*;
+pub use rubygems_response::*;
+pub use sourcehut_response::*;
 
 // Re-export template types from the templates module for backward compatibility
 pub use crate::templates::types::*;
@@ -45,6 +73,14 @@ lazy_static! {
             "https://nixos.org/nixpkgs/manual/#chap-pkgs-fetchers\n  # NOTE: fetchPypi is discouraged in nixpkgs; prefer fetching from the original source (GitHub, GitLab, etc.)\n  ",
         );
         m.insert("meta", "https://nixos.org/nixpkgs/manual/#chap-meta\n  ");
+        m.insert(
+            "autoPatchelfHook",
+            "https://nixos.org/manual/nixpkgs/stable/#setup-hook-autopatchelfhook\n  ",
+        );
+        m.insert(
+            "appimageTools",
+            "https://nixos.org/manual/nixpkgs/stable/#sec-pkgs-appimageTools\n  ",
+        );
         // Template-specific documentation
         m.insert(
             "pythonImportsCheck",
@@ -95,9 +131,19 @@ arg_enum! {
         github,
         gitlab,
         gitea,
+        sourcehut,
+        bitbucket,
+        git,
         url,
         zip,
         pypi,
+        crates,
+        hackage,
+        rubygems,
+        cpan,
+        cran,
+        hex,
+        pubdev,
         local,
     }
 }
@@ -105,9 +151,80 @@ arg_enum! {
 #[derive(Debug, PartialEq)]
 pub enum Repo {
     Pypi(PypiRepo),
+    Crates(CratesRepo),
+    Npm(NpmRepo),
+    Hackage(HackageRepo),
+    Rubygems(RubygemsRepo),
+    Cpan(CpanRepo),
+    Cran(CranRepo),
+    Hex(HexRepo),
+    Opam(OpamRepo),
+    Pub(PubRepo),
+    Packagist(PackagistRepo),
+    Maven(MavenRepo),
+    LuaRocks(LuaRocksRepo),
+    GoModule(GoModuleRepo),
     Github(GithubRepo),
     Gitlab(GitlabRepo),
     Gitea(GiteaRepo),
+    Sourcehut(SourcehutRepo),
+    Bitbucket(BitbucketRepo),
+    Git(GitRepo),
+    Archive(ArchiveRepo),
+}
+
+impl Repo {
+    /// Name of the host or registry, as shown in messages
+    pub fn host_name(&self) -> &'static str {
+        match self {
+            Repo::Pypi(_) => "PyPI",
+            Repo::Crates(_) => "crates.io",
+            Repo::Npm(_) => "npm",
+            Repo::Hackage(_) => "Hackage",
+            Repo::Rubygems(_) => "RubyGems",
+            Repo::Cpan(_) => "MetaCPAN",
+            Repo::Cran(repo) if repo.bioconductor => "Bioconductor",
+            Repo::Cran(_) => "CRAN",
+            Repo::Hex(_) => "Hex",
+            Repo::Opam(_) => "opam",
+            Repo::Pub(_) => "pub.dev",
+            Repo::Packagist(_) => "Packagist",
+            Repo::Maven(_) => "Maven",
+            Repo::LuaRocks(_) => "LuaRocks",
+            Repo::GoModule(_) => "Go module",
+            Repo::Github(_) => "GitHub",
+            Repo::Gitlab(_) => "GitLab",
+            Repo::Gitea(_) => "Gitea",
+            Repo::Sourcehut(_) => "Sourcehut",
+            Repo::Bitbucket(_) => "Bitbucket",
+            Repo::Git(_) => "git",
+            Repo::Archive(_) => "archive",
+        }
+    }
+
+    /// Whether an exact release can be selected with `--tag`
+    pub fn supports_tag(&self) -> bool {
+        match self {
+            Repo::Pypi(_)
+            | Repo::Crates(_)
+            | Repo::Npm(_)
+            | Repo::Hackage(_)
+            | Repo::Rubygems(_)
+            | Repo::Cpan(_)
+            | Repo::Cran(_)
+            | Repo::Hex(_)
+            | Repo::Opam(_)
+            | Repo::Pub(_)
+            | Repo::Packagist(_)
+            | Repo::Maven(_)
+            | Repo::LuaRocks(_)
+            | Repo::GoModule(_)
+            | Repo::Github(_)
+            | Repo::Gitlab(_)
+            | Repo::Gitea(_) => true,
+            Repo::Sourcehut(_) | Repo::Bitbucket(_) | Repo::Git(_) | Repo::Archive(_) => false,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -115,6 +232,112 @@ pub struct PypiRepo {
     pub project: String,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct NpmRepo {
+    /// Package name, including the scope if any (e.g., "@angular/cli")
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct HackageRepo {
+    /// Hackage package name, e.g. "pandoc"
+    pub package: String,
+    /// Release linked in the URL, e.g. "3.1.11" for ".../package/pandoc-3.1.11"
+    pub version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RubygemsRepo {
+    /// Gem name, e.g. "rails"
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CpanRepo {
+    /// Distribution ("Moose") or module ("Moose::Role") name
+    pub name: String,
+    /// Whether `name` is a module, as in metacpan.org/pod/<Module> URLs
+    pub module: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CranRepo {
+    /// R package name, e.g. "sf"
+    pub package: String,
+    /// Whether the package is hosted on Bioconductor rather than CRAN
+    pub bioconductor: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct HexRepo {
+    /// Hex package name, e.g. "jason"
+    pub name: String,
+    /// Version from a `hex.pm/packages/<name>/<version>` URL, if any
+    pub version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PackagistRepo {
+    /// Composer vendor, e.g. "phpstan"
+    pub vendor: String,
+    /// Composer package name, e.g. "phpstan"
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MavenRepo {
+    /// Maven groupId, e.g. "org.apache.commons"
+    pub group_id: String,
+    /// Maven artifactId, e.g. "commons-text"
+    pub artifact_id: String,
+    /// Version from `groupId:artifactId:version` coordinates or a
+    /// `central.sonatype.com/artifact/<group>/<artifact>/<version>` URL, if any
+    pub version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LuaRocksRepo {
+    /// LuaRocks account that uploaded the rock, e.g. "gvvaughan"
+    pub user: String,
+    /// Rock name, e.g. "luaposix"
+    pub name: String,
+    /// Version from a `luarocks.org/modules/<user>/<rock>/<version>` URL, if any
+    pub version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OpamRepo {
+    /// opam package name, e.g. "zarith"
+    pub name: String,
+    /// Version from an `opam.ocaml.org/packages/<name>/<name>.<version>` URL, if any
+    pub version: Option<String>,
+    /// Root of a local opam-repository checkout; opam.ocaml.org's
+    /// repository on GitHub is used otherwise
+    pub repository: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PubRepo {
+    /// pub.dev package name, e.g. "dart_style"
+    pub name: String,
+    /// Version from a `pub.dev/packages/<name>/versions/<version>` URL, if any
+    pub version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GoModuleRepo {
+    /// Module or package import path, e.g. "golang.org/x/tools/gopls"
+    pub path: String,
+    /// Version from a `pkg.go.dev/<path>@<version>` URL, if any
+    pub version: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CratesRepo {
+    /// Crate name as published, e.g. "ripgrep"
+    pub name: String,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct GithubRepo {
     pub owner: String,
@@ -130,6 +353,10 @@ pub struct GitlabRepo {
     pub owner: String,
     /// Repository name (last component of project_path, e.g., "design-system")
     pub repo: String,
+    /// Base URL of the REST API, e.g. "https://gitlab.com/api/v4"
+    pub api_base: String,
+    /// Environment variable holding the API token (sent as `PRIVATE-TOKEN`)
+    pub token_env: String,
 }
 
 #[derive(Debug, PartialEq)]
@@ -137,12 +364,114 @@ pub struct GiteaRepo {
     pub domain: String,
     pub owner: String,
     pub repo: String,
+    /// Base URL of the REST API, e.g. "https://codeberg.org/api/v1"
+    pub api_base: String,
+    /// Environment variable holding the API token
+    pub token_env: String,
+}
+
+impl GitlabRepo {
+    /// Build a repo from a project path (`group/subgroup/repo`) using the
+    /// default API location and `GITLAB_TOKEN`.
+    pub fn new(domain: &str, project_path: &str) -> Self {
+        let owner = project_path.split('/').next().unwrap_or("").to_owned();
+        let repo = project_path.rsplit('/').next().unwrap_or("").to_owned();
+        GitlabRepo {
+            domain: domain.to_owned(),
+            project_path: project_path.to_owned(),
+            owner,
+            repo,
+            api_base: format!("https://{}/api/v4", domain),
+            token_env: "GITLAB_TOKEN".to_owned(),
+        }
+    }
+}
+
+impl SourcehutRepo {
+    /// Build a repo using the default API location and `SRHT_TOKEN`.
+    pub fn new(domain: &str, owner: &str, repo: &str) -> Self {
+        SourcehutRepo {
+            domain: domain.to_owned(),
+            owner: owner.to_owned(),
+            repo: repo.to_owned(),
+            api_base: format!("https://{}/api", domain),
+            token_env: "SRHT_TOKEN".to_owned(),
+        }
+    }
+
+    /// Domain passed to `fetchFromSourcehut`, which prepends "git." itself
+    /// (e.g. "sr.ht" for "git.sr.ht").
+    pub fn fetcher_domain(&self) -> &str {
+        self.domain.strip_prefix("git.").unwrap_or(&self.domain)
+    }
+}
+
+impl GiteaRepo {
+    /// Build a repo using the default API location and `GITEA_TOKEN`.
+    pub fn new(domain: &str, owner: &str, repo: &str) -> Self {
+        GiteaRepo {
+            domain: domain.to_owned(),
+            owner: owner.to_owned(),
+            repo: repo.to_owned(),
+            api_base: format!("https://{}/api/v1", domain),
+            token_env: "GITEA_TOKEN".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SourcehutRepo {
+    /// Host of the git service, e.g. "git.sr.ht"
+    pub domain: String,
+    /// Owner including the leading tilde (e.g., "~sircmpwn"), as expected by
+    /// `fetchFromSourcehut`
+    pub owner: String,
+    pub repo: String,
+    /// Base URL of the REST API, e.g. "https://git.sr.ht/api"
+    pub api_base: String,
+    /// Environment variable holding the API token
+    pub token_env: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BitbucketRepo {
+    /// Bitbucket workspace (rendered as `owner` in `fetchFromBitbucket`)
+    pub workspace: String,
+    pub repo: String,
+}
+
+/// Plain git repository reachable via `git ls-remote`, e.g. cgit or
+/// kernel.org hosting.
+#[derive(Debug, PartialEq)]
+pub struct GitRepo {
+    /// Clone URL as given by the user (`git://`, `ssh://`, `file://`,
+    /// `user@host:path` or `https://...git`)
+    pub url: String,
+    /// Repository name (last path component without `.git`)
+    pub repo: String,
+}
+
+/// Release archive fetched directly, e.g.
+/// "https://example.org/releases/foo-1.2.3.tar.gz"
+#[derive(Debug, PartialEq)]
+pub struct ArchiveRepo {
+    pub url: String,
+    /// Name inferred from the archive filename, if any
+    pub pname: Option<String>,
+    /// Version inferred from the archive filename, if any
+    pub version: Option<String>,
+    /// True for `.zip` archives, which are fetched with `fetchzip`
+    pub is_zip: bool,
+    /// True for `.AppImage` files, which are packaged as they are
+    pub is_appimage: bool,
 }
 
 #[derive(Debug)]
 pub struct ExpressionInfo {
     pub pname: String,
     pub version: String,
+    /// nixpkgs license attribute; several space-separated attributes render
+    /// as a list
     pub license: String,
     pub maintainer: String,
     pub fetcher: Fetcher,
@@ -152,6 +481,21 @@ pub struct ExpressionInfo {
     pub include_documentation_links: bool,
     pub include_meta: bool,
     pub tag_prefix: String,
+    /// Separator the upstream tag uses between version components instead
+    /// of `.` (`--version-transform`), e.g. `_` for `R_4_3_1`. Empty when
+    /// the tag spells the version as-is.
+    pub tag_version_separator: String,
+    /// Commit pinned by unstable versions; rendered as `rev = "<sha>"`
+    /// instead of the tag. Empty when a release tag is used.
+    pub src_rev: String,
+    /// Render `fetchSubmodules = true;` (upstream has a `.gitmodules`)
+    pub fetch_submodules: bool,
+    /// Render `fetchLFS = true;` (upstream stores files in git LFS)
+    pub fetch_lfs: bool,
+    /// Executable rendered as `meta.mainProgram`; omitted when empty
+    pub main_program: String,
+    /// Executables shipped by a gem, rendered as bundlerApp's `exes`
+    pub executables: Vec<String>,
     pub owner: String,
     pub src_sha: String,
     pub description: String,
@@ -172,9 +516,16 @@ pub struct ExpressionInfo {
     /// Path to the .NET project file (used for `dotnet` template).
     /// Typically a .csproj, .fsproj, or .sln file relative to src root.
     pub project_file: String,
-    /// Domain of the Gitea instance (used by the `gitea` fetcher), e.g.
-    /// "codeberg.org" or "gitea.com". Empty for non-Gitea fetchers.
+    /// Domain of the forge instance, e.g. "codeberg.org", "gitlab.com" or
+    /// "sr.ht". Always rendered for `gitea`; rendered for `gitlab` and
+    /// `sourcehut` only when it isn't their public instance. Empty for other
+    /// fetchers.
     pub domain: String,
+    /// URL the source is fetched from (used by the `git`, `url` and `zip`
+    /// fetchers), e.g. "https://git.kernel.org/pub/scm/utils/dtc/dtc.git".
+    /// Archive URLs have the version replaced by `${finalAttrs.version}`.
+    /// Empty or "CHANGE" when unknown.
+    pub src_url: String,
     /// Inferred system libraries that need to be linked at build time
     /// (rendered into `buildInputs` for the `rust` template).
     pub build_inputs: Vec<String>,
@@ -191,6 +542,9 @@ pub struct ExpressionInfo {
     /// Go module path from `go.mod` (e.g. `github.com/user/repo`).
     /// Used to suggest `ldflags` for version embedding. Empty when unknown.
     pub go_module_path: String,
+    /// Directory of the Go module inside its repository (`modRoot`), for
+    /// modules that don't live at the repository root. Empty otherwise.
+    pub go_mod_root: String,
     /// Python build system format, detected from pyproject.toml or defaulted.
     /// One of: "setuptools", "pyproject", "flit", "poetry", "hatchling".
     pub python_format: String,
@@ -214,13 +568,73 @@ pub const FAKE_SRI_HASH: &str = "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
 pub const VENDOR_HASH_NULL: &str = "null";
 
 impl ExpressionInfo {
+    /// The upstream tag `rev` evaluates to, e.g. `v1.2.3` or `R_4_3_1`
+    pub fn upstream_tag(&self) -> String {
+        if self.tag_version_separator.is_empty() {
+            format!("{}{}", self.tag_prefix, self.version)
+        } else {
+            format!(
+                "{}{}",
+                self.tag_prefix,
+                self.version.replace('.', &self.tag_version_separator)
+            )
+        }
+    }
+
     pub fn format(&self, s: &str) -> String {
-        let rev: String = if self.tag_prefix.is_empty() {
+        let tag_version = if self.tag_version_separator.is_empty() {
             "finalAttrs.version".to_owned()
         } else {
-            format!(r#""{}${{finalAttrs.version}}""#, &self.tag_prefix)
+            format!(
+                r#"lib.replaceStrings [ "." ] [ "{}" ] finalAttrs.version"#,
+                &self.tag_version_separator
+            )
+        };
+        let rev: String = if !self.src_rev.is_empty() {
+            format!(r#""{}""#, &self.src_rev)
+        } else if self.tag_prefix.is_empty() && self.tag_version_separator.is_empty() {
+            tag_version
+        } else {
+            format!(r#""{}${{{}}}""#, &self.tag_prefix, tag_version)
+        };
+
+        let license = if self.license.contains(' ') {
+            let licenses: Vec<String> = self
+                .license
+                .split_whitespace()
+                .map(|l| format!("licenses.{}", l))
+                .collect();
+            format!("[ {} ]", licenses.join(" "))
+        } else {
+            format!("licenses.{}", self.license)
         };
 
+        let main_program = if self.main_program.is_empty() {
+            String::new()
+        } else {
+            format!("\n    mainProgram = \"{}\";", self.main_program)
+        };
+
+        // fetchFromGitLab and fetchFromSourcehut default to the public
+        // instance, so only other domains are rendered
+        let forge_domain = match (&self.fetcher, self.domain.as_str()) {
+            (_, "" | "CHANGE")
+            | (Fetcher::gitlab, "gitlab.com")
+            | (Fetcher::sourcehut, "sr.ht") => String::new(),
+            (Fetcher::gitlab | Fetcher::sourcehut, domain) => {
+                format!("\n    domain = \"{}\";", domain)
+            }
+            _ => String::new(),
+        };
+
+        let mut fetch_options = String::new();
+        if self.fetch_submodules {
+            fetch_options.push_str("\n    fetchSubmodules = true;");
+        }
+        if self.fetch_lfs {
+            fetch_options.push_str("\n    fetchLFS = true;");
+        }
+
         fn format_inputs(inputs: &Vec<String>) -> String {
             if inputs.is_empty() {
                 "".to_owned()
@@ -245,6 +659,8 @@ impl ExpressionInfo {
             .replace("@owner@", &self.owner)
             .replace("@rev@", &rev)
             .replace("@src_sha@", &self.src_sha)
+            .replace("@fetch_options@", &fetch_options)
+            .replace("@forge_domain@", &forge_domain)
             .replace("@cargo_hash@", &self.cargo_hash)
             .replace("@vendor_hash@", &self.vendor_hash)
             .replace("@npm_deps_hash@", &self.npm_deps_hash)
@@ -254,9 +670,12 @@ impl ExpressionInfo {
             .replace("@gradle_hash@", &self.gradle_hash)
             .replace("@project_file@", &self.project_file)
             .replace("@domain@", &self.domain)
+            .replace("@src_url@", &self.src_url)
             .replace("@description@", &self.description)
             .replace("@homepage@", &self.homepage)
+            .replace("licenses.@license@", &license)
             .replace("@license@", &self.license)
+            .replace("@main_program@", &main_program)
             .replace("@maintainer@", &self.maintainer)
             .replace(
                 "@propagated_build_inputs@",
@@ -293,4 +712,36 @@ impl ExpressionInfo {
 pub struct UserConfig {
     pub maintainer: Option<String>,
     pub nixpkgs_root: Option<String>,
+    /// Self-hosted forges keyed by domain, e.g.
+    ///
+    /// ```toml
+    /// [forges."git.example.org"]
+    /// kind = "forgejo"
+    /// api_base = "https://git.example.org/api/v1"
+    /// token_env = "EXAMPLE_TOKEN"
+    /// ```
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub forges: HashMap<String, ForgeConfig>,
+}
+
+/// API flavour spoken by a configured forge. Forgejo is a Gitea fork and
+/// shares its API and `fetchFromGitea`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Gitlab,
+    Gitea,
+    Forgejo,
+    Sourcehut,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ForgeConfig {
+    pub kind: ForgeKind,
+    /// Overrides the default `https://<domain>/api/v4` (GitLab),
+    /// `https://<domain>/api/v1` (Gitea/Forgejo) or `https://<domain>/api`
+    /// (Sourcehut)
+    pub api_base: Option<String>,
+    /// Overrides the default `GITLAB_TOKEN` / `GITEA_TOKEN` / `SRHT_TOKEN`
+    pub token_env: Option<String>,
 }
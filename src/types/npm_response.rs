@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version manifest from the npm registry's `/<name>/<version>` endpoint
+/// (`/<name>/latest` resolves the `latest` dist-tag)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NpmVersionResponse {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "version")]
+    pub version: String,
+
+    #[serde(rename = "description")]
+    pub description: Option<String>,
+
+    #[serde(rename = "homepage")]
+    pub homepage: Option<String>,
+
+    #[serde(rename = "license")]
+    pub license: Option<NpmLicense>,
+
+    #[serde(rename = "repository")]
+    pub repository: Option<NpmRepository>,
+
+    #[serde(rename = "bin")]
+    pub bin: Option<NpmBin>,
+
+    #[serde(rename = "dist")]
+    pub dist: NpmDist,
+}
+
+/// Either an SPDX expression or the deprecated `{ "type": "MIT" }` object
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NpmLicense {
+    Spdx(String),
+    Object {
+        #[serde(rename = "type")]
+        kind: String,
+    },
+}
+
+/// Either a shorthand such as "github:owner/repo" or `{ "type", "url" }`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NpmRepository {
+    Shorthand(String),
+    Object {
+        #[serde(rename = "url")]
+        url: String,
+    },
+}
+
+/// A single executable named after the package, or a name -> path map
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NpmBin {
+    Single(String),
+    Map(BTreeMap<String, String>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NpmDist {
+    #[serde(rename = "tarball")]
+    pub tarball: String,
+}
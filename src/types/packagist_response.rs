@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Response of Packagist's `/packages/<vendor>/<name>.json` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackagistResponse {
+    pub package: PackagistPackage,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackagistPackage {
+    /// "<vendor>/<name>"
+    pub name: String,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    /// Releases and branches keyed by version, e.g. "v1.2.3" or "dev-main"
+    #[serde(default)]
+    pub versions: HashMap<String, PackagistVersion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackagistVersion {
+    pub version: String,
+    /// SPDX identifiers
+    #[serde(default)]
+    pub license: Vec<String>,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub source: Option<PackagistSource>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackagistSource {
+    /// "git", "hg" or "svn"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: String,
+    /// Commit of the release
+    pub reference: Option<String>,
+}
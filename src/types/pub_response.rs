@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of pub.dev's `/api/packages/<name>` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PubPackage {
+    pub name: String,
+    /// Newest stable version
+    pub latest: PubVersion,
+    /// All versions, oldest first
+    #[serde(default)]
+    pub versions: Vec<PubVersion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PubVersion {
+    pub version: String,
+    #[serde(default)]
+    pub retracted: bool,
+    pub pubspec: PubPubspec,
+    /// e.g. "https://pub.dev/api/archives/<name>-<version>.tar.gz"
+    pub archive_url: String,
+}
+
+/// The fields of pubspec.yaml used for `meta`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PubPubspec {
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+}
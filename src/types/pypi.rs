@@ -30,6 +30,16 @@ pub struct PypiResponse {
     urls: Vec<Url>,
 }
 
+/// Response of the per-release `/pypi/<project>/<version>/json` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PypiReleaseResponse {
+    #[serde(rename = "info")]
+    pub info: Info,
+
+    #[serde(rename = "urls")]
+    pub urls: Vec<Url>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Info {
     #[serde(rename = "author")]
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of RubyGems' `/api/v1/gems/<name>.json` and
+/// `/api/v2/rubygems/<name>/versions/<version>.json` endpoints
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RubygemResponse {
+    pub name: String,
+    // The v2 endpoint reports the version as "number"
+    #[serde(alias = "number")]
+    pub version: String,
+    #[serde(default)]
+    pub licenses: Option<Vec<String>>,
+    /// Summary, or the description when the gem has no summary
+    pub info: Option<String>,
+    pub homepage_uri: Option<String>,
+    pub source_code_uri: Option<String>,
+    /// Hex sha256 of the .gem file
+    pub sha: Option<String>,
+    #[serde(default)]
+    pub dependencies: RubygemDependencies,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RubygemDependencies {
+    #[serde(default)]
+    pub runtime: Vec<RubygemDependency>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RubygemDependency {
+    pub name: String,
+    pub requirements: String,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Paginated response from git.sr.ht's `/api/~<owner>/repos/<repo>/refs`
+/// endpoint. `next` is the cursor to pass as `?start=` for the next page,
+/// or `None` on the last page.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourcehutRefsResponse {
+    #[serde(rename = "next")]
+    pub next: Option<serde_json::Value>,
+
+    #[serde(rename = "results")]
+    pub results: Vec<SourcehutRef>,
+
+    #[serde(rename = "total")]
+    pub total: Option<i64>,
+
+    #[serde(rename = "results_per_page")]
+    pub results_per_page: Option<i64>,
+}
+
+/// Single ref (branch or tag). `name` is fully qualified, e.g.
+/// "refs/tags/v1.0.0" or "refs/heads/master".
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourcehutRef {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "target")]
+    pub target: String,
+}
+
+/// git.sr.ht repository API response (`/api/~<owner>/repos/<repo>`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourcehutRepoResponse {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "description")]
+    pub description: Option<String>,
+
+    #[serde(rename = "visibility")]
+    pub visibility: Option<String>,
+}
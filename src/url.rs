@@ -1,5 +1,8 @@
 use crate::types;
-use crate::types::Repo::{Gitea, Github, Gitlab, Pypi};
+use crate::types::Repo::{
+    Archive, Bitbucket, Cpan, Cran, Crates, Git, Gitea, Github, Gitlab, GoModule, Hackage, Hex,
+    LuaRocks, Maven, Npm, Opam, Packagist, Pub, Pypi, Rubygems, Sourcehut,
+};
 use crate::types::{Template, FAKE_SRI_HASH};
 
 use anyhow::anyhow;
@@ -23,12 +26,139 @@ lazy_static! {
         Regex::new("pypi.org/project/([^/]*)/?").unwrap()
     };
 
+    static ref CRATES_URL_REGEX: Regex = {
+        // e.g. crates.io/crates/ripgrep
+        Regex::new(r"^crates\.io/crates/([A-Za-z0-9_-]+)/?").unwrap()
+    };
+
+    static ref NPM_URL_REGEX: Regex = {
+        // e.g. npmjs.com/package/prettier or npmjs.com/package/@angular/cli
+        Regex::new(r"^(?:www\.)?npmjs\.com/package/((?:@[^/]+/)?[^/?#]+)/?").unwrap()
+    };
+
+    static ref HACKAGE_URL_REGEX: Regex = {
+        // e.g. hackage.haskell.org/package/pandoc or
+        // hackage.haskell.org/package/pandoc-3.1.11
+        Regex::new(r"^hackage\.haskell\.org/package/([A-Za-z0-9-]+?)(?:-([0-9][0-9.]*))?/?$").unwrap()
+    };
+
+    static ref RUBYGEMS_URL_REGEX: Regex = {
+        // e.g. rubygems.org/gems/rails or rubygems.org/gems/rails/versions/7.1.3
+        Regex::new(r"^rubygems\.org/gems/([A-Za-z0-9_.-]+)").unwrap()
+    };
+
+    static ref METACPAN_URL_REGEX: Regex = {
+        // e.g. metacpan.org/dist/Moose or metacpan.org/pod/Moose::Role
+        Regex::new(r"^metacpan\.org/(dist|release|pod)/([A-Za-z0-9_:.-]+?)/?$").unwrap()
+    };
+
+    static ref CRAN_URL_REGEX: Regex = {
+        // e.g. cran.r-project.org/package=sf or cran.r-project.org/web/packages/sf/index.html
+        Regex::new(r"^(?:cran|cloud)\.r-project\.org/(?:package=|web/packages/)([A-Za-z0-9.]+?)(?:/index\.html)?/?$").unwrap()
+    };
+
+    static ref BIOCONDUCTOR_URL_REGEX: Regex = {
+        // e.g. bioconductor.org/packages/DESeq2 or
+        // bioconductor.org/packages/release/bioc/html/DESeq2.html
+        Regex::new(r"^(?:www\.)?bioconductor\.org/packages/(?:(?:release|devel|[0-9.]+)/bioc/html/)?([A-Za-z0-9.]+?)(?:\.html)?/?$").unwrap()
+    };
+
+    static ref HEX_URL_REGEX: Regex = {
+        // e.g. hex.pm/packages/jason or hex.pm/packages/jason/1.4.1
+        Regex::new(r"^hex\.pm/packages/([A-Za-z0-9_]+)(?:/([0-9][A-Za-z0-9.+-]*))?/?$").unwrap()
+    };
+
+    static ref PACKAGIST_URL_REGEX: Regex = {
+        // e.g. packagist.org/packages/phpstan/phpstan
+        Regex::new(r"^packagist\.org/packages/([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+)/?$").unwrap()
+    };
+
+    static ref MAVEN_CENTRAL_URL_REGEX: Regex = {
+        // e.g. central.sonatype.com/artifact/org.apache.commons/commons-text or
+        // central.sonatype.com/artifact/org.apache.commons/commons-text/1.12.0
+        Regex::new(r"^central\.sonatype\.com/artifact/([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+)(?:/([A-Za-z0-9_.+-]+))?/?$").unwrap()
+    };
+
+    static ref MAVEN_COORDINATES_REGEX: Regex = {
+        // e.g. org.apache.commons:commons-text or org.apache.commons:commons-text:1.12.0
+        Regex::new(r"^([A-Za-z0-9_.-]+):([A-Za-z0-9_.-]+)(?::([A-Za-z0-9_.+-]+))?$").unwrap()
+    };
+
+    static ref MAVEN_METADATA_VERSION_REGEX: Regex = {
+        Regex::new(r"<version>\s*([^<\s]+)\s*</version>").unwrap()
+    };
+
+    /// Qualifiers of Maven versions that aren't final releases, e.g.
+    /// "2.0.0-M1", "1.0-rc-2", "3.1.0-SNAPSHOT" or "5.0.0.Beta1"
+    static ref MAVEN_PRERELEASE_REGEX: Regex = {
+        Regex::new(r"(?i)(?:alpha|beta|snapshot|preview|[.-](?:rc|cr|m|ea)[.-]?[0-9]*$)").unwrap()
+    };
+
+    static ref LUAROCKS_URL_REGEX: Regex = {
+        // e.g. luarocks.org/modules/gvvaughan/luaposix or
+        // luarocks.org/modules/gvvaughan/luaposix/36.2.1-1
+        Regex::new(r"^luarocks\.org/modules/([A-Za-z0-9_.-]+)/([A-Za-z0-9_.-]+)(?:/([A-Za-z0-9_.+-]+))?/?$").unwrap()
+    };
+
+    static ref PUB_URL_REGEX: Regex = {
+        // e.g. pub.dev/packages/dart_style or pub.dev/packages/dart_style/versions/2.3.6
+        Regex::new(r"^pub\.dev/packages/([a-z0-9_]+)(?:/versions/([0-9][A-Za-z0-9.+-]*))?(?:/[a-z]+)?/?$").unwrap()
+    };
+
+    static ref OPAM_URL_REGEX: Regex = {
+        // e.g. opam.ocaml.org/packages/zarith or opam.ocaml.org/packages/zarith/zarith.1.13
+        Regex::new(r"^opam\.ocaml\.org/packages/([A-Za-z0-9_+-]+)(?:/([A-Za-z0-9_+-]+)\.([^/]+))?/?$").unwrap()
+    };
+
+    static ref GO_MODULE_URL_REGEX: Regex = {
+        // e.g. pkg.go.dev/golang.org/x/tools/gopls or pkg.go.dev/github.com/junegunn/fzf@v0.54.0
+        Regex::new(r"^pkg\.go\.dev/([A-Za-z0-9._~/-]+?)(?:@([A-Za-z0-9._+-]+))?/?$").unwrap()
+    };
+
+    /// Date and abbreviated commit of a Go pseudo-version, e.g.
+    /// "v0.0.0-20240501120000-0123456789ab" or "v1.2.4-0.20240501120000-0123456789ab"
+    static ref GO_PSEUDO_VERSION_REGEX: Regex = {
+        Regex::new(r"[.-](?:0\.)?([0-9]{4})([0-9]{2})([0-9]{2})[0-9]{6}-([0-9a-f]{12})$").unwrap()
+    };
+
+    /// `<meta name="go-import" content="<prefix> <vcs> <repo>">` tags served
+    /// for vanity import paths
+    static ref GO_IMPORT_META_REGEX: Regex = {
+        Regex::new(r#"<meta\s+name=["']go-import["']\s+content=["']([^"']+)["']"#).unwrap()
+    };
+
     static ref GITLAB_URL_REGEX: Regex = {
         // e.g. gitlab.com/gitlab-org/gitlab-foss or gitlab.com/org/subgroup/repo
         // Matches gitlab.com/ followed by any path (greedy, supports nested groups)
         Regex::new(r"gitlab\.com/(.+?)(?:\.git)?/?$").unwrap()
     };
 
+    static ref SOURCEHUT_URL_REGEX: Regex = {
+        // e.g. git.sr.ht/~sircmpwn/scdoc
+        Regex::new(r"git\.sr\.ht/(~[^/]+)/([^/]+?)(?:\.git)?/?$").unwrap()
+    };
+
+    static ref BITBUCKET_URL_REGEX: Regex = {
+        // e.g. bitbucket.org/multicoreware/x265_git
+        Regex::new(r"bitbucket\.org/([^/]+)/([^/]+?)(?:\.git)?/?$").unwrap()
+    };
+
+    static ref SCP_GIT_URL_REGEX: Regex = {
+        // e.g. git@git.example.org:project/repo.git
+        Regex::new(r"^[A-Za-z0-9._-]+@[A-Za-z0-9.-]+:[^/]").unwrap()
+    };
+
+    static ref ARCHIVE_URL_REGEX: Regex = {
+        // e.g. https://example.org/releases/foo-1.2.3.tar.gz
+        Regex::new(r"\.(?:tar\.(?:gz|xz|bz2|zst)|tgz|zip|AppImage)$").unwrap()
+    };
+
+    /// Splits an archive filename such as "foo-bar-1.2.3.tar.xz",
+    /// "foo_v2.0.zip" or "Foo-1.4.0-x86_64.AppImage" into name and version.
+    static ref ARCHIVE_FILENAME_REGEX: Regex = {
+        Regex::new(r"^(?:(?P<pname>.+?)[-_])?v?(?P<version>[0-9][^/]*?)(?:[-_.](?:x86_64|amd64|x64|aarch64|arm64))?(?:\.orig)?\.(?:tar\.(?:gz|xz|bz2|zst)|tgz|zip|AppImage)$").unwrap()
+    };
+
     /// Hosts that we recognise as Gitea instances. Their REST APIs are
     /// compatible with each other (and largely with GitHub's), so we use a
     /// shared code path for fetching metadata.
@@ -50,6 +180,11 @@ lazy_static! {
         Regex::new(r"^[0-9.]{1,50}$").unwrap()
     };
 
+    /// Date part of an unstable version, e.g. "2024-05-01"
+    static ref UNSTABLE_DATE_REGEX: Regex = {
+        Regex::new(r"^[0-9]{4}-[0-9]{2}-[0-9]{2}$").unwrap()
+    };
+
     /// Matches the "got:" line emitted by `nix-build` when a fixed-output
     /// derivation has a hash mismatch. Examples:
     ///     got:    sha256-abcdef...=
@@ -78,6 +213,104 @@ lazy_static! {
         m
     };
 
+    static ref SPDX_TO_NIXPKGS_LICENSE: HashMap<&'static str, &'static str> = {
+        let mut m = HashMap::new();
+        m.insert("0BSD", "bsd0");
+        m.insert("AGPL-3.0", "agpl3Only");
+        m.insert("AGPL-3.0-only", "agpl3Only");
+        m.insert("AGPL-3.0-or-later", "agpl3Plus");
+        m.insert("Apache-2.0", "asl20");
+        m.insert("BSD-2-Clause", "bsd2");
+        m.insert("BSD-3-Clause", "bsd3");
+        m.insert("BSL-1.0", "boost");
+        m.insert("CC0-1.0", "cc0");
+        m.insert("EPL-2.0", "epl20");
+        m.insert("GPL-2.0", "gpl2Only");
+        m.insert("GPL-2.0-only", "gpl2Only");
+        m.insert("GPL-2.0-or-later", "gpl2Plus");
+        m.insert("GPL-3.0", "gpl3Only");
+        m.insert("GPL-3.0-only", "gpl3Only");
+        m.insert("GPL-3.0-or-later", "gpl3Plus");
+        m.insert("ISC", "isc");
+        m.insert("LGPL-2.1", "lgpl21Only");
+        m.insert("LGPL-2.1-only", "lgpl21Only");
+        m.insert("LGPL-2.1-or-later", "lgpl21Plus");
+        m.insert("LGPL-3.0", "lgpl3Only");
+        m.insert("LGPL-3.0-only", "lgpl3Only");
+        m.insert("LGPL-3.0-or-later", "lgpl3Plus");
+        m.insert("MIT", "mit");
+        m.insert("MPL-2.0", "mpl20");
+        m.insert("Unicode-3.0", "unicode-30");
+        m.insert("Unicode-DFS-2016", "unicode-dfs-2016");
+        m.insert("Unlicense", "unlicense");
+        m.insert("Zlib", "zlib");
+        m
+    };
+
+    /// Cabal's legacy license names; SPDX expressions are handled by
+    /// `spdx_to_nixpkgs_license`
+    static ref CABAL_TO_NIXPKGS_LICENSE: HashMap<&'static str, &'static str> = {
+        let mut m = HashMap::new();
+        m.insert("AGPL-3", "agpl3Only");
+        m.insert("BSD2", "bsd2");
+        m.insert("BSD3", "bsd3");
+        m.insert("GPL-2", "gpl2Only");
+        m.insert("GPL-3", "gpl3Only");
+        m.insert("LGPL-2.1", "lgpl21Only");
+        m.insert("LGPL-3", "lgpl3Only");
+        m.insert("PublicDomain", "publicDomain");
+        m
+    };
+
+    static ref R_TO_NIXPKGS_LICENSE: HashMap<&'static str, &'static str> = {
+        // License specifications from "Writing R Extensions", minus any
+        // "+ file LICENSE"
+        let mut m = HashMap::new();
+        m.insert("AGPL-3", "agpl3Only");
+        m.insert("Apache License 2.0", "asl20");
+        m.insert("Apache License (== 2.0)", "asl20");
+        m.insert("Artistic-2.0", "artistic2");
+        m.insert("BSD_2_clause", "bsd2");
+        m.insert("BSD_3_clause", "bsd3");
+        m.insert("CC0", "cc0");
+        m.insert("GPL", "gpl2Plus");
+        m.insert("GPL-2", "gpl2Only");
+        m.insert("GPL-3", "gpl3Only");
+        m.insert("GPL (>= 2)", "gpl2Plus");
+        m.insert("GPL (>= 2.0)", "gpl2Plus");
+        m.insert("GPL (>= 3)", "gpl3Plus");
+        m.insert("LGPL-2.1", "lgpl21Only");
+        m.insert("LGPL-3", "lgpl3Only");
+        m.insert("LGPL (>= 2.1)", "lgpl21Plus");
+        m.insert("LGPL (>= 3)", "lgpl3Plus");
+        m.insert("MIT", "mit");
+        m.insert("MPL-2.0", "mpl20");
+        m
+    };
+
+    static ref CPAN_TO_NIXPKGS_LICENSE: HashMap<&'static str, &'static str> = {
+        // CPAN::Meta::Spec license strings
+        let mut m = HashMap::new();
+        m.insert("agpl_3", "agpl3Only");
+        m.insert("apache_2_0", "asl20");
+        m.insert("artistic_1", "artistic1");
+        m.insert("artistic_2", "artistic2");
+        m.insert("bsd", "bsd3");
+        m.insert("freebsd", "bsd2");
+        m.insert("gpl_1", "gpl1Only");
+        m.insert("gpl_2", "gpl2Only");
+        m.insert("gpl_3", "gpl3Only");
+        m.insert("lgpl_2_1", "lgpl21Only");
+        m.insert("lgpl_3_0", "lgpl3Only");
+        m.insert("mit", "mit");
+        m.insert("mozilla_1_1", "mpl11");
+        m.insert("mozilla_2_0", "mpl20");
+        // The nixpkgs spelling of "same terms as perl itself"
+        m.insert("perl_5", "artistic1 gpl1Plus");
+        m.insert("zlib", "zlib");
+        m
+    };
+
     static ref PYPI_TO_NIXPKGS_LICENSE: HashMap<&'static str, &'static str> = {
         let mut m = HashMap::new();
         // TODO: add more licenses
@@ -91,6 +324,25 @@ lazy_static! {
 }
 
 const LOG_TARGET: &str = "nix-template::url";
+const GITHUB_API_BASE: &str = "https://api.github.com";
+pub const CRATES_IO_API_BASE: &str = "https://crates.io/api/v1";
+pub const NPM_REGISTRY_BASE: &str = "https://registry.npmjs.org";
+const PYPI_API_BASE: &str = "https://pypi.io/pypi";
+const HACKAGE_BASE: &str = "https://hackage.haskell.org";
+const RUBYGEMS_BASE: &str = "https://rubygems.org";
+const METACPAN_API_BASE: &str = "https://fastapi.metacpan.org/v1";
+const CRAN_BASE: &str = "https://cran.r-project.org";
+const BIOCONDUCTOR_BASE: &str = "https://bioconductor.org";
+const HEX_API_BASE: &str = "https://hex.pm/api";
+const HEX_REPO_BASE: &str = "https://repo.hex.pm";
+const PACKAGIST_BASE: &str = "https://packagist.org";
+const MAVEN_CENTRAL_BASE: &str = "https://repo1.maven.org/maven2";
+const LUAROCKS_BASE: &str = "https://luarocks.org";
+const PUB_API_BASE: &str = "https://pub.dev/api";
+const OPAM_REPOSITORY: &str = "ocaml/opam-repository";
+const OPAM_REPOSITORY_RAW_BASE: &str =
+    "https://raw.githubusercontent.com/ocaml/opam-repository/master";
+const GO_PROXY_BASE: &str = "https://proxy.golang.org";
 
 /// Validates a URL component (owner, repo, version, etc.) to prevent injection attacks.
 /// Returns an error if the component contains dangerous characters.
@@ -149,12 +401,127 @@ fn validate_gitea_repo(repo: &types::GiteaRepo) -> Result<()> {
     Ok(())
 }
 
+/// Validates all components of a Sourcehut repo for use in commands/URLs.
+fn validate_sourcehut_repo(repo: &types::SourcehutRepo) -> Result<()> {
+    validate_url_component(&repo.domain, "Sourcehut domain")?;
+    validate_url_component(&repo.owner, "Sourcehut owner")?;
+    validate_url_component(&repo.repo, "Sourcehut repo")?;
+    Ok(())
+}
+
+/// Validates all components of a Bitbucket repo for use in commands/URLs.
+fn validate_bitbucket_repo(repo: &types::BitbucketRepo) -> Result<()> {
+    validate_url_component(&repo.workspace, "Bitbucket workspace")?;
+    validate_url_component(&repo.repo, "Bitbucket repo")?;
+    Ok(())
+}
+
+/// Validates a generic git repo for use in commands.
+fn validate_git_repo(repo: &types::GitRepo) -> Result<()> {
+    validate_url_component(&repo.url, "git URL")?;
+    validate_url_component(&repo.repo, "git repo name")?;
+    // The URL is passed to git as an argument; don't let it become an option
+    if repo.url.starts_with('-') {
+        return Err(anyhow!("git URL must not start with '-'"));
+    }
+    Ok(())
+}
+
 /// Validates all components of a PyPI repo for use in commands/URLs.
 fn validate_pypi_repo(repo: &types::PypiRepo) -> Result<()> {
     validate_url_component(&repo.project, "PyPI project")?;
     Ok(())
 }
 
+fn validate_npm_repo(repo: &types::NpmRepo) -> Result<()> {
+    validate_url_component(&repo.name, "npm package")?;
+    Ok(())
+}
+
+fn validate_hackage_repo(repo: &types::HackageRepo) -> Result<()> {
+    validate_url_component(&repo.package, "Hackage package")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "Hackage version")?;
+    }
+    Ok(())
+}
+
+fn validate_rubygems_repo(repo: &types::RubygemsRepo) -> Result<()> {
+    validate_url_component(&repo.name, "gem name")?;
+    Ok(())
+}
+
+fn validate_cpan_repo(repo: &types::CpanRepo) -> Result<()> {
+    validate_url_component(&repo.name, "CPAN distribution")?;
+    Ok(())
+}
+
+fn validate_cran_repo(repo: &types::CranRepo) -> Result<()> {
+    validate_url_component(&repo.package, "R package")?;
+    Ok(())
+}
+
+fn validate_hex_repo(repo: &types::HexRepo) -> Result<()> {
+    validate_url_component(&repo.name, "Hex package")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "Hex version")?;
+    }
+    Ok(())
+}
+
+fn validate_packagist_repo(repo: &types::PackagistRepo) -> Result<()> {
+    validate_url_component(&repo.vendor, "Composer vendor")?;
+    validate_url_component(&repo.name, "Composer package")?;
+    Ok(())
+}
+
+fn validate_maven_repo(repo: &types::MavenRepo) -> Result<()> {
+    validate_url_component(&repo.group_id, "Maven groupId")?;
+    validate_url_component(&repo.artifact_id, "Maven artifactId")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "Maven version")?;
+    }
+    Ok(())
+}
+
+fn validate_luarocks_repo(repo: &types::LuaRocksRepo) -> Result<()> {
+    validate_url_component(&repo.user, "LuaRocks user")?;
+    validate_url_component(&repo.name, "LuaRocks rock")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "LuaRocks version")?;
+    }
+    Ok(())
+}
+
+fn validate_pub_repo(repo: &types::PubRepo) -> Result<()> {
+    validate_url_component(&repo.name, "pub.dev package")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "pub.dev version")?;
+    }
+    Ok(())
+}
+
+fn validate_opam_repo(repo: &types::OpamRepo) -> Result<()> {
+    validate_url_component(&repo.name, "opam package")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "opam version")?;
+    }
+    Ok(())
+}
+
+fn validate_go_module_repo(repo: &types::GoModuleRepo) -> Result<()> {
+    validate_url_component(&repo.path, "Go module path")?;
+    if let Some(version) = &repo.version {
+        validate_url_component(version, "Go module version")?;
+    }
+    Ok(())
+}
+
+fn validate_crates_repo(repo: &types::CratesRepo) -> Result<()> {
+    validate_url_component(&repo.name, "crate name")?;
+    Ok(())
+}
+
 /// Validates version and tag_prefix components.
 fn validate_version_components(version: &str, tag_prefix: &str) -> Result<()> {
     validate_url_component(version, "version")?;
@@ -244,8 +611,242 @@ fn detect_forge_platform(domain: &str) -> Option<&'static str> {
     None
 }
 
+/// Recognise URLs that can only be served by plain git: `git://`,
+/// `ssh://`, `file://`, scp-style `user@host:path`, and http(s) URLs ending
+/// in `.git`. Callers must check known forges first, since
+/// `https://github.com/owner/repo.git` also matches.
+pub fn parse_generic_git_url(url: &str) -> Option<types::GitRepo> {
+    let url = url.trim().trim_end_matches('/');
+    let is_git_url = ["git://", "ssh://", "git+ssh://", "file://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+        || SCP_GIT_URL_REGEX.is_match(url)
+        || ((url.starts_with("https://") || url.starts_with("http://")) && url.ends_with(".git"));
+    if !is_git_url {
+        return None;
+    }
+
+    let repo = url
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or("")
+        .trim_end_matches(".git");
+    if repo.is_empty() {
+        return None;
+    }
+
+    Some(types::GitRepo {
+        url: url.to_owned(),
+        repo: repo.to_owned(),
+    })
+}
+
+/// Recognise a package directory of a local opam-repository checkout,
+/// `file://<root>/packages/<name>` or `file://<root>/packages/<name>/<name>.<version>`.
+pub fn parse_local_opam_url(url: &str) -> Option<types::OpamRepo> {
+    let path = std::path::Path::new(url.trim().strip_prefix("file://")?);
+    let mut components: Vec<&str> = path.iter().filter_map(|c| c.to_str()).collect();
+
+    let mut version = None;
+    let name = match components.as_slice() {
+        [.., "packages", name] => name.to_string(),
+        [.., "packages", name, release] => {
+            version = Some(release.strip_prefix(name)?.strip_prefix('.')?.to_owned());
+            name.to_string()
+        }
+        _ => return None,
+    };
+    let depth = if version.is_some() { 2 } else { 1 };
+    components.truncate(components.len() - depth - 1);
+    let root: std::path::PathBuf = components.iter().collect();
+    if !root.join("packages").join(&name).is_dir() {
+        return None;
+    }
+
+    Some(types::OpamRepo {
+        name,
+        version,
+        repository: Some(root.to_string_lossy().into_owned()),
+    })
+}
+
+/// Recognise Maven coordinates, `groupId:artifactId` or
+/// `groupId:artifactId:version`.
+pub fn parse_maven_coordinates(url: &str) -> Option<types::MavenRepo> {
+    let captures = MAVEN_COORDINATES_REGEX.captures(url.trim())?;
+    // Dots are what tell a groupId apart from an scp-style git host
+    if !captures[1].contains('.') {
+        return None;
+    }
+
+    Some(types::MavenRepo {
+        group_id: captures[1].to_owned(),
+        artifact_id: captures[2].to_owned(),
+        version: captures.get(3).map(|m| m.as_str().to_owned()),
+    })
+}
+
+/// Recognise direct links to release archives (`.tar.gz`, `.tar.xz`,
+/// `.tar.bz2`, `.tar.zst`, `.tgz`, `.zip`) and AppImages, and infer name and
+/// version from the filename.
+pub fn parse_archive_url(url: &str) -> Option<types::ArchiveRepo> {
+    let url = url.trim();
+    let has_scheme = ["https://", "http://", "ftp://", "file://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme));
+    if !has_scheme || !ARCHIVE_URL_REGEX.is_match(url) {
+        return None;
+    }
+
+    let filename = url.rsplit('/').next().unwrap_or("");
+    let captures = ARCHIVE_FILENAME_REGEX.captures(filename);
+    Some(types::ArchiveRepo {
+        url: url.to_owned(),
+        pname: captures
+            .as_ref()
+            .and_then(|c| c.name("pname"))
+            .map(|m| m.as_str().to_owned()),
+        version: captures
+            .as_ref()
+            .and_then(|c| c.name("version"))
+            .map(|m| m.as_str().to_owned()),
+        is_zip: url.ends_with(".zip"),
+        is_appimage: url.ends_with(".AppImage"),
+    })
+}
+
+/// Replace the version in an archive URL with `${finalAttrs.version}`, so
+/// that bumping `version` is enough to update the source. Only the filename
+/// and directory components that are exactly the version (or `v<version>`)
+/// are rewritten, to avoid touching unrelated digits in the host or path.
+fn template_archive_url(url: &str, version: &str) -> String {
+    let (dir, filename) = match url.rsplit_once('/') {
+        Some(parts) => parts,
+        None => return url.replace(version, "${finalAttrs.version}"),
+    };
+    let tagged_version = format!("v{}", version);
+    let dir = dir
+        .split('/')
+        .map(|segment| {
+            if segment == version {
+                "${finalAttrs.version}".to_owned()
+            } else if segment == tagged_version {
+                "v${finalAttrs.version}".to_owned()
+            } else {
+                segment.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    format!(
+        "{}/{}",
+        dir,
+        filename.replace(version, "${finalAttrs.version}")
+    )
+}
+
+/// Build a repo for a domain listed in the user's `[forges]` table. `path`
+/// is everything after the domain, e.g. "/group/subgroup/repo.git".
+pub fn configured_forge_repo(
+    domain: &str,
+    path: &str,
+    forge: &types::ForgeConfig,
+) -> Result<types::Repo> {
+    // Drop trailing slashes, a ".git" suffix and GitLab's "/-/tree/..." suffixes
+    let path = path.split("/-/").next().unwrap_or("");
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    if components.len() < 2 {
+        return Err(anyhow!(
+            "Error: please provide a url of shape '{}/<owner>/<repo>'",
+            domain
+        ));
+    }
+
+    match forge.kind {
+        types::ForgeKind::Gitlab => {
+            let mut gitlab_repo = types::GitlabRepo::new(domain, &components.join("/"));
+            if let Some(api_base) = &forge.api_base {
+                gitlab_repo.api_base = api_base.trim_end_matches('/').to_owned();
+            }
+            if let Some(token_env) = &forge.token_env {
+                gitlab_repo.token_env = token_env.clone();
+            }
+            validate_gitlab_repo(&gitlab_repo)?;
+            Ok(Gitlab(gitlab_repo))
+        }
+        types::ForgeKind::Gitea | types::ForgeKind::Forgejo => {
+            let mut gitea_repo = types::GiteaRepo::new(domain, components[0], components[1]);
+            if let Some(api_base) = &forge.api_base {
+                gitea_repo.api_base = api_base.trim_end_matches('/').to_owned();
+            }
+            if let Some(token_env) = &forge.token_env {
+                gitea_repo.token_env = token_env.clone();
+            }
+            validate_gitea_repo(&gitea_repo)?;
+            Ok(Gitea(gitea_repo))
+        }
+        types::ForgeKind::Sourcehut => {
+            if !components[0].starts_with('~') {
+                return Err(anyhow!(
+                    "Error: please provide a sourcehut url of shape '{}/~<owner>/<repo>'",
+                    domain
+                ));
+            }
+            let mut sourcehut_repo =
+                types::SourcehutRepo::new(domain, components[0], components[1]);
+            if let Some(api_base) = &forge.api_base {
+                sourcehut_repo.api_base = api_base.trim_end_matches('/').to_owned();
+            }
+            if let Some(token_env) = &forge.token_env {
+                sourcehut_repo.token_env = token_env.clone();
+            }
+            validate_sourcehut_repo(&sourcehut_repo)?;
+            Ok(Sourcehut(sourcehut_repo))
+        }
+    }
+}
+
 // This will just crazy the program, so no need to return a value
-fn validate_and_parse_url(url: &str, original_url: &str) -> Result<types::Repo> {
+fn validate_and_parse_url(
+    url: &str,
+    original_url: &str,
+    forges: &HashMap<String, types::ForgeConfig>,
+) -> Result<types::Repo> {
+    // Direct archive links are fetched as-is, whichever host serves them
+    if let Some(archive) = parse_archive_url(original_url) {
+        validate_url_component(&archive.url, "archive URL")?;
+        return Ok(Archive(archive));
+    }
+
+    if let Some(opam_repo) = parse_local_opam_url(original_url) {
+        validate_opam_repo(&opam_repo)?;
+        return Ok(Opam(opam_repo));
+    }
+
+    if let Some(maven_repo) = parse_maven_coordinates(original_url) {
+        validate_maven_repo(&maven_repo)?;
+        return Ok(Maven(maven_repo));
+    }
+
+    // Non-http git URLs can't belong to a forge we know how to query
+    if !original_url.starts_with("http") {
+        if let Some(git_repo) = parse_generic_git_url(original_url) {
+            validate_git_repo(&git_repo)?;
+            return Ok(Git(git_repo));
+        }
+    }
+
+    // Domains declared in the user's [forges] table take precedence over
+    // the built-in hosts
+    if let Some((domain, forge)) = url
+        .split('/')
+        .next()
+        .and_then(|domain| forges.get_key_value(domain))
+    {
+        return configured_forge_repo(domain, &url[domain.len()..], forge);
+    }
+
     if url.starts_with("github.com") {
         if !GITHUB_URL_REGEX.is_match(url) {
             return Err(anyhow!(
@@ -275,6 +876,167 @@ fn validate_and_parse_url(url: &str, original_url: &str) -> Result<types::Repo>
         };
         validate_pypi_repo(&pypi_repo)?;
         return Ok(Pypi(pypi_repo));
+    } else if url.starts_with("npmjs.com") || url.starts_with("www.npmjs.com") {
+        let captures = NPM_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide an npm url of shape 'www.npmjs.com/package/<name>'")
+        })?;
+
+        let npm_repo = types::NpmRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+        };
+        validate_npm_repo(&npm_repo)?;
+        Ok(Npm(npm_repo))
+    } else if url.starts_with("hackage.haskell.org") {
+        let captures = HACKAGE_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!(
+                "Error: please provide a hackage url of shape 'hackage.haskell.org/package/<name>'"
+            )
+        })?;
+
+        let hackage_repo = types::HackageRepo {
+            package: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        };
+        validate_hackage_repo(&hackage_repo)?;
+        Ok(Hackage(hackage_repo))
+    } else if url.starts_with("rubygems.org") {
+        let captures = RUBYGEMS_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a rubygems url of shape 'rubygems.org/gems/<name>'")
+        })?;
+
+        let rubygems_repo = types::RubygemsRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+        };
+        validate_rubygems_repo(&rubygems_repo)?;
+        Ok(Rubygems(rubygems_repo))
+    } else if url.starts_with("metacpan.org") {
+        let captures = METACPAN_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a metacpan url of shape 'metacpan.org/dist/<Dist>' or 'metacpan.org/pod/<Module>'")
+        })?;
+
+        let cpan_repo = types::CpanRepo {
+            name: captures.get(2).unwrap().as_str().to_owned(),
+            module: captures.get(1).unwrap().as_str() == "pod",
+        };
+        validate_cpan_repo(&cpan_repo)?;
+        Ok(Cpan(cpan_repo))
+    } else if url.starts_with("cran.r-project.org") || url.starts_with("cloud.r-project.org") {
+        let captures = CRAN_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a CRAN url of shape 'cran.r-project.org/package=<name>'")
+        })?;
+
+        let cran_repo = types::CranRepo {
+            package: captures.get(1).unwrap().as_str().to_owned(),
+            bioconductor: false,
+        };
+        validate_cran_repo(&cran_repo)?;
+        Ok(Cran(cran_repo))
+    } else if url.starts_with("bioconductor.org") || url.starts_with("www.bioconductor.org") {
+        let captures = BIOCONDUCTOR_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a Bioconductor url of shape 'bioconductor.org/packages/<name>'")
+        })?;
+
+        let cran_repo = types::CranRepo {
+            package: captures.get(1).unwrap().as_str().to_owned(),
+            bioconductor: true,
+        };
+        validate_cran_repo(&cran_repo)?;
+        Ok(Cran(cran_repo))
+    } else if url.starts_with("hex.pm") {
+        let captures = HEX_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a Hex url of shape 'hex.pm/packages/<name>'")
+        })?;
+
+        let hex_repo = types::HexRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        };
+        validate_hex_repo(&hex_repo)?;
+        Ok(Hex(hex_repo))
+    } else if url.starts_with("packagist.org") {
+        let captures = PACKAGIST_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a Packagist url of shape 'packagist.org/packages/<vendor>/<name>'")
+        })?;
+
+        let packagist_repo = types::PackagistRepo {
+            vendor: captures.get(1).unwrap().as_str().to_owned(),
+            name: captures.get(2).unwrap().as_str().to_owned(),
+        };
+        validate_packagist_repo(&packagist_repo)?;
+        Ok(Packagist(packagist_repo))
+    } else if url.starts_with("central.sonatype.com") {
+        let captures = MAVEN_CENTRAL_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a Maven Central url of shape 'central.sonatype.com/artifact/<groupId>/<artifactId>'")
+        })?;
+
+        let maven_repo = types::MavenRepo {
+            group_id: captures.get(1).unwrap().as_str().to_owned(),
+            artifact_id: captures.get(2).unwrap().as_str().to_owned(),
+            version: captures.get(3).map(|m| m.as_str().to_owned()),
+        };
+        validate_maven_repo(&maven_repo)?;
+        Ok(Maven(maven_repo))
+    } else if url.starts_with("luarocks.org") {
+        let captures = LUAROCKS_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a LuaRocks url of shape 'luarocks.org/modules/<user>/<rock>'")
+        })?;
+
+        let luarocks_repo = types::LuaRocksRepo {
+            user: captures.get(1).unwrap().as_str().to_owned(),
+            name: captures.get(2).unwrap().as_str().to_owned(),
+            version: captures.get(3).map(|m| m.as_str().to_owned()),
+        };
+        validate_luarocks_repo(&luarocks_repo)?;
+        Ok(LuaRocks(luarocks_repo))
+    } else if url.starts_with("pub.dev") {
+        let captures = PUB_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a pub.dev url of shape 'pub.dev/packages/<name>'")
+        })?;
+
+        let pub_repo = types::PubRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        };
+        validate_pub_repo(&pub_repo)?;
+        Ok(Pub(pub_repo))
+    } else if url.starts_with("opam.ocaml.org") {
+        let captures = OPAM_URL_REGEX
+            .captures(url)
+            .filter(|c| c.get(2).is_none_or(|name| name.as_str() == &c[1]))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Error: please provide an opam url of shape 'opam.ocaml.org/packages/<name>'"
+                )
+            })?;
+
+        let opam_repo = types::OpamRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(3).map(|m| m.as_str().to_owned()),
+            repository: None,
+        };
+        validate_opam_repo(&opam_repo)?;
+        Ok(Opam(opam_repo))
+    } else if url.starts_with("pkg.go.dev") {
+        let captures = GO_MODULE_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a Go module url of shape 'pkg.go.dev/<module>'")
+        })?;
+
+        let go_module_repo = types::GoModuleRepo {
+            path: captures.get(1).unwrap().as_str().to_owned(),
+            version: captures.get(2).map(|m| m.as_str().to_owned()),
+        };
+        validate_go_module_repo(&go_module_repo)?;
+        Ok(GoModule(go_module_repo))
+    } else if url.starts_with("crates.io") {
+        let captures = CRATES_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a crates.io url of shape 'crates.io/crates/<name>'")
+        })?;
+
+        let crates_repo = types::CratesRepo {
+            name: captures.get(1).unwrap().as_str().to_owned(),
+        };
+        validate_crates_repo(&crates_repo)?;
+        Ok(Crates(crates_repo))
     } else if url.starts_with("gitlab.com") {
         if !GITLAB_URL_REGEX.is_match(url) {
             return Err(anyhow!(
@@ -283,38 +1045,54 @@ fn validate_and_parse_url(url: &str, original_url: &str) -> Result<types::Repo>
         }
 
         let captures = GITLAB_URL_REGEX.captures(url).unwrap();
-        let project_path = captures.get(1).unwrap().as_str().to_owned();
-
-        // Split the project path to extract owner and repo
-        let path_parts: Vec<&str> = project_path.split('/').collect();
-        if path_parts.is_empty() {
-            return Err(anyhow!("Invalid GitLab project path"));
-        }
+        let gitlab_repo = types::GitlabRepo::new("gitlab.com", captures.get(1).unwrap().as_str());
+        validate_gitlab_repo(&gitlab_repo)?;
+        return Ok(Gitlab(gitlab_repo));
+    } else if url.starts_with("git.sr.ht") {
+        let captures = SOURCEHUT_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!("Error: please provide a sourcehut url of shape 'git.sr.ht/~<owner>/<repo>'")
+        })?;
 
-        let owner = path_parts[0].to_owned();
-        let repo = path_parts[path_parts.len() - 1].to_owned();
+        let sourcehut_repo = types::SourcehutRepo::new(
+            "git.sr.ht",
+            captures.get(1).unwrap().as_str(),
+            captures.get(2).unwrap().as_str(),
+        );
+        validate_sourcehut_repo(&sourcehut_repo)?;
+        Ok(Sourcehut(sourcehut_repo))
+    } else if url.starts_with("bitbucket.org") {
+        let captures = BITBUCKET_URL_REGEX.captures(url).ok_or_else(|| {
+            anyhow!(
+                "Error: please provide a bitbucket url of shape 'bitbucket.org/<workspace>/<repo>'"
+            )
+        })?;
 
-        let gitlab_repo = types::GitlabRepo {
-            domain: "gitlab.com".to_owned(),
-            project_path,
-            owner,
-            repo,
+        let bitbucket_repo = types::BitbucketRepo {
+            workspace: captures.get(1).unwrap().as_str().to_owned(),
+            repo: captures.get(2).unwrap().as_str().to_owned(),
         };
-        validate_gitlab_repo(&gitlab_repo)?;
-        return Ok(Gitlab(gitlab_repo));
+        validate_bitbucket_repo(&bitbucket_repo)?;
+        Ok(Bitbucket(bitbucket_repo))
     } else if GITEA_HOSTS.iter().any(|host| url.starts_with(host)) {
         let captures = GITEA_URL_REGEX.captures(url).ok_or_else(|| {
             anyhow!("Error: please provide a gitea url of shape '<domain>/<owner>/<repo>'")
         })?;
 
-        let gitea_repo = types::GiteaRepo {
-            domain: captures.name("domain").unwrap().as_str().to_owned(),
-            owner: captures.name("owner").unwrap().as_str().to_owned(),
-            repo: captures.name("repo").unwrap().as_str().to_owned(),
-        };
+        let gitea_repo = types::GiteaRepo::new(
+            captures.name("domain").unwrap().as_str(),
+            captures.name("owner").unwrap().as_str(),
+            captures.name("repo").unwrap().as_str(),
+        );
         validate_gitea_repo(&gitea_repo)?;
         return Ok(Gitea(gitea_repo));
     } else {
+        // Plain git hosting (cgit, kernel.org, ...) is recognised by the
+        // `.git` suffix and doesn't need any API probing
+        if let Some(git_repo) = parse_generic_git_url(original_url) {
+            validate_git_repo(&git_repo)?;
+            return Ok(Git(git_repo));
+        }
+
         // Try to auto-detect the platform for unknown domains
         let captures = GITEA_URL_REGEX.captures(url).ok_or_else(|| {
             anyhow!("Error: please provide a url of shape '<domain>/<owner>/<repo>'")
@@ -329,11 +1107,7 @@ fn validate_and_parse_url(url: &str, original_url: &str) -> Result<types::Repo>
         match detect_forge_platform(domain) {
             Some("gitea") => {
                 eprintln!("Detected Gitea instance at {}", domain);
-                let gitea_repo = types::GiteaRepo {
-                    domain: domain.to_owned(),
-                    owner,
-                    repo,
-                };
+                let gitea_repo = types::GiteaRepo::new(domain, &owner, &repo);
                 validate_gitea_repo(&gitea_repo)?;
                 Ok(Gitea(gitea_repo))
             }
@@ -341,17 +1115,12 @@ fn validate_and_parse_url(url: &str, original_url: &str) -> Result<types::Repo>
                 eprintln!("Detected GitLab instance at {}", domain);
                 // For detected GitLab instances, construct the project_path
                 let project_path = format!("{}/{}", owner, repo);
-                let gitlab_repo = types::GitlabRepo {
-                    domain: domain.to_owned(),
-                    project_path: project_path.clone(),
-                    owner,
-                    repo,
-                };
+                let gitlab_repo = types::GitlabRepo::new(domain, &project_path);
                 validate_gitlab_repo(&gitlab_repo)?;
                 Ok(Gitlab(gitlab_repo))
             }
             _ => Err(anyhow!(
-                "{} is not a recognized forge platform. Could not detect Gitea or GitLab API at {}. Only github.com, gitlab.com, pypi.org, and self-hosted Gitea/GitLab instances are supported.",
+                "{} is not a recognized forge platform. Could not detect Gitea or GitLab API at {}. Only github.com, gitlab.com, git.sr.ht, bitbucket.org, pypi.org, and self-hosted Gitea/GitLab instances are supported. Self-hosted instances can also be declared in the [forges] table of config.toml.",
                 original_url,
                 domain
             ))
@@ -386,22 +1155,238 @@ pub fn fetch_pypi_project_info(pypi_repo: &types::PypiRepo) -> types::PypiRespon
     }
 }
 
-pub fn fetch_github_repo_info(repo: &types::GithubRepo) -> types::GhRepoResponse {
-    // Validate repo components to prevent injection attacks
-    if let Err(e) = validate_github_repo(repo) {
-        error!(target: LOG_TARGET, "Invalid repository: {}", e);
-        eprintln!("Error: {}", e);
-        exit(1);
-    }
-
-    let request_client = Client::new();
-    let mut request = request_client
+/// Fetch the manifest of `version` (or a dist-tag such as "latest") of an
+/// npm package.
+pub fn fetch_npm_manifest(
+    registry: &str,
+    repo: &types::NpmRepo,
+    version: &str,
+) -> Result<types::NpmVersionResponse> {
+    let request = Client::new()
         .get(format!(
-            "https://api.github.com/repos/{}/{}",
-            repo.owner, repo.repo
+            "{}/{}/{}",
+            registry,
+            // Scoped packages are addressed as "@scope%2fname"
+            repo.name.replace('/', "%2f"),
+            version
         ))
-        .header("User-Agent", "reqwest")
-        .header("Accept", "application/vnd.github.v3+json");
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Fetch the non-deprecated and deprecated versions of a Hackage package.
+fn fetch_hackage_versions(
+    base: &str,
+    repo: &types::HackageRepo,
+) -> Result<types::HackagePreferredResponse> {
+    let request = Client::new()
+        .get(format!("{}/package/{}/preferred", base, repo.package))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Download the latest revision of the .cabal file of one release.
+fn fetch_hackage_cabal_file(
+    base: &str,
+    repo: &types::HackageRepo,
+    version: &str,
+) -> Result<String> {
+    let request = Client::new()
+        .get(format!(
+            "{}/package/{}-{}/{}.cabal",
+            base, repo.package, version, repo.package
+        ))
+        .header("User-Agent", "nix-template");
+
+    Ok(get_json(request)?)
+}
+
+/// Fetch the latest release of a gem, or the given `version`.
+fn fetch_rubygem(
+    base: &str,
+    repo: &types::RubygemsRepo,
+    version: Option<&str>,
+) -> Result<types::RubygemResponse> {
+    let url = match version {
+        Some(version) => format!(
+            "{}/api/v2/rubygems/{}/versions/{}.json",
+            base, repo.name, version
+        ),
+        None => format!("{}/api/v1/gems/{}.json", base, repo.name),
+    };
+    let request = Client::new()
+        .get(url)
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Read the executables from the specification inside a .gem file; the
+/// JSON API doesn't report them.
+fn fetch_gem_executables(
+    base: &str,
+    repo: &types::RubygemsRepo,
+    version: &str,
+) -> Result<Vec<String>> {
+    let dir = tempfile_dir().ok_or_else(|| anyhow!("could not create a temporary directory"))?;
+    let gem_path = dir.join(format!("{}-{}.gem", repo.name, version));
+    let gem = Client::new()
+        .get(format!("{}/gems/{}-{}.gem", base, repo.name, version))
+        .header("User-Agent", "nix-template")
+        .send()?
+        .error_for_status()?
+        .bytes()?;
+    std::fs::write(&gem_path, &gem)?;
+
+    // A .gem is a plain tar holding the gzipped YAML spec as metadata.gz
+    let untar = Command::new("tar")
+        .arg("-xf")
+        .arg(&gem_path)
+        .arg("-C")
+        .arg(&dir)
+        .arg("metadata.gz")
+        .output()?;
+    if !untar.status.success() {
+        return Err(anyhow!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&untar.stderr).trim()
+        ));
+    }
+    let gemspec = Command::new("gzip")
+        .arg("-dc")
+        .arg(dir.join("metadata.gz"))
+        .output()?;
+    if !gemspec.status.success() {
+        return Err(anyhow!(
+            "gzip failed: {}",
+            String::from_utf8_lossy(&gemspec.stderr).trim()
+        ));
+    }
+
+    Ok(crate::deps::ruby::parse_gemspec_executables(
+        &String::from_utf8_lossy(&gemspec.stdout),
+    ))
+}
+
+/// Fetch the latest release of a CPAN distribution, or the release of
+/// `(author, version)`.
+fn fetch_cpan_release(
+    api_base: &str,
+    distribution: &str,
+    pinned: Option<(&str, &str)>,
+) -> Result<types::CpanRelease> {
+    let url = match pinned {
+        Some((author, version)) => format!(
+            "{}/release/{}/{}-{}",
+            api_base, author, distribution, version
+        ),
+        None => format!("{}/release/{}", api_base, distribution),
+    };
+    let request = Client::new()
+        .get(url)
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Look up the distribution shipping a module.
+fn fetch_cpan_module(api_base: &str, module: &str) -> Result<types::CpanModule> {
+    let request = Client::new()
+        .get(format!("{}/module/{}", api_base, module))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Fetch a Hex package with its metadata and latest versions.
+fn fetch_hex_package(api_base: &str, name: &str) -> Result<types::HexPackage> {
+    let request = Client::new()
+        .get(format!("{}/packages/{}", api_base, name))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Fetch the dependencies and build tools of one release of a Hex package.
+fn fetch_hex_release(api_base: &str, name: &str, version: &str) -> Result<types::HexRelease> {
+    let request = Client::new()
+        .get(format!(
+            "{}/packages/{}/releases/{}",
+            api_base, name, version
+        ))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Current version of an R package according to the `PACKAGES` index of a
+/// CRAN-like repository.
+fn fetch_r_package_version(repo_base: &str, package: &str) -> Result<Option<String>> {
+    use crate::deps::r::parse_dcf_field;
+
+    let request = Client::new()
+        .get(format!("{}/src/contrib/PACKAGES", repo_base))
+        .header("User-Agent", "nix-template");
+    let index = get_json(request)?;
+
+    Ok(index
+        .split("\n\n")
+        .find(|entry| parse_dcf_field(entry, "Package").as_deref() == Some(package))
+        .and_then(|entry| parse_dcf_field(entry, "Version")))
+}
+
+/// Current Bioconductor release, e.g. "3.20".
+fn fetch_bioconductor_release(base: &str) -> Result<String> {
+    let request = Client::new()
+        .get(format!("{}/config.yaml", base))
+        .header("User-Agent", "nix-template");
+    let config = get_json(request)?;
+
+    config
+        .lines()
+        .find_map(|line| line.strip_prefix("release_version:"))
+        .map(|version| version.trim().trim_matches('"').to_owned())
+        .ok_or_else(|| anyhow!("no release_version in {}/config.yaml", base))
+}
+
+pub fn fetch_crate_info(api_base: &str, repo: &types::CratesRepo) -> Result<types::CratesResponse> {
+    let request = Client::new()
+        .get(format!("{}/crates/{}", api_base, repo.name))
+        // crates.io rejects requests without an identifying User-Agent
+        .header(
+            "User-Agent",
+            "nix-template (https://github.com/jonringer/nix-template)",
+        )
+        .header("Accept", "application/json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+pub fn fetch_github_repo_info(repo: &types::GithubRepo) -> types::GhRepoResponse {
+    // Validate repo components to prevent injection attacks
+    if let Err(e) = validate_github_repo(repo) {
+        error!(target: LOG_TARGET, "Invalid repository: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+
+    let request_client = Client::new();
+    let mut request = request_client
+        .get(format!(
+            "https://api.github.com/repos/{}/{}",
+            repo.owner, repo.repo
+        ))
+        .header("User-Agent", "reqwest")
+        .header("Accept", "application/vnd.github.v3+json");
 
     if let Ok(github_token) = std::env::var("GITHUB_TOKEN") {
         debug!(target: LOG_TARGET, "Using github token for github api request");
@@ -458,1120 +1443,7076 @@ pub fn fetch_github_release_info(repo: &types::GithubRepo) -> types::GhReleaseRe
     }
 }
 
-pub fn fill_github_info(repo: &types::GithubRepo, info: &mut types::ExpressionInfo) {
-    if info.pname == "CHANGE" {
-        info.pname = repo.repo.to_string();
+/// Options controlling which revision `read_meta_from_url` resolves.
+#[derive(Debug, Default)]
+pub struct FetchOptions {
+    /// Consider prerelease tags (`--include-prereleases`)
+    pub include_prereleases: bool,
+    /// Pin a commit instead of the latest release (`--unstable`)
+    pub unstable: bool,
+    /// Commit, branch or tag to pin in unstable mode (`--rev`)
+    pub rev: Option<String>,
+    /// Exact release to package instead of the latest (`--tag`, or `-v`
+    /// together with a URL)
+    pub tag: Option<String>,
+    /// How tags split into prefix and version (`--tag-regex`,
+    /// `--version-transform`)
+    pub tag_scheme: TagScheme,
+    /// Fetch crates.io packages from their linked GitHub repository
+    /// instead (`--follow-repository`)
+    pub follow_repository: bool,
+    /// Self-hosted forges declared in the user config
+    pub forges: HashMap<String, types::ForgeConfig>,
+    /// `GOPROXY` setting used to resolve Go modules; see `go_proxy_base`
+    pub go_proxy: Option<String>,
+}
+
+impl FetchOptions {
+    fn wants_unstable(&self) -> bool {
+        self.unstable || self.rev.is_some()
     }
+}
 
-    eprintln!("Determining latest release for {}", &repo.repo);
-    let mut releases = fetch_github_release_info(&repo);
-    if !releases.is_empty() {
-        releases.sort_by(|a, b| {
-            VersionCompare::compare(&b.tag_name, &a.tag_name)
-                .unwrap()
-                .ord()
-                .unwrap()
-        });
-        releases = releases.into_iter().filter(|a| !a.prerelease).collect();
-        let parsed_version = VERSION_REGEX
-            .captures(&releases.first().unwrap().tag_name)
-            .unwrap();
-        info.version = parsed_version.get(2).unwrap().as_str().to_owned();
-        info.tag_prefix = parsed_version.get(1).unwrap().as_str().to_owned();
+/// How upstream tags map to versions. By default `VERSION_REGEX` guesses
+/// the prefix; `--tag-regex` replaces the guess with a regex carrying
+/// `prefix` and `version` named captures, and `--version-transform` names
+/// the separator the tags use in place of dots.
+#[derive(Debug, Clone, Default)]
+pub struct TagScheme {
+    regex: Option<Regex>,
+    separator: Option<char>,
+}
 
-        // Validate version components before using in commands
-        if let Err(e) = validate_version_components(&info.version, &info.tag_prefix) {
-            error!(target: LOG_TARGET, "Invalid version from GitHub API: {}", e);
-            eprintln!("Error: {}", e);
-            exit(1);
-        }
+/// A tag split by `TagScheme::split`
+#[derive(Debug, PartialEq)]
+pub struct SplitTag {
+    pub prefix: String,
+    pub version: String,
+}
 
-        eprintln!("Determining sha256 for {}", &repo.repo);
-        let sha256_cmd = Command::new("nix-prefetch-url")
-            .args(&["--unpack", "--type", "sha256"])
-            .arg(format!(
-                "https://github.com/{}/{}/archive/refs/tags/{}{}.tar.gz",
-                &repo.owner, &repo.repo, &info.tag_prefix, &info.version
-            ))
-            .output();
-        let output =
-            String::from_utf8_lossy(&sha256_cmd.expect("failed to run 'nix-prefetch-url'").stdout)
-                .trim()
-                .to_owned();
-        info.src_sha = to_sri(&output);
-    } else {
-        eprintln!(
-            "No releases found for github.com/{}/{}",
-            &repo.owner, &repo.repo
-        );
+impl TagScheme {
+    /// Build a scheme from `--tag-regex` and `--version-transform`
+    /// (`underscores` or `dashes`).
+    pub fn new(tag_regex: Option<&str>, transform: Option<&str>) -> Result<TagScheme> {
+        let regex = match tag_regex {
+            Some(pattern) => {
+                let regex = Regex::new(pattern)?;
+                if !regex.capture_names().any(|n| n == Some("version")) {
+                    return Err(anyhow!(
+                        "tag regex '{}' has no (?P<version>...) capture",
+                        pattern
+                    ));
+                }
+                Some(regex)
+            }
+            None => None,
+        };
+        let separator = match transform {
+            None => None,
+            Some("underscores") => Some('_'),
+            Some("dashes") => Some('-'),
+            Some(other) => return Err(anyhow!("unknown version transform '{}'", other)),
+        };
+        Ok(TagScheme { regex, separator })
     }
 
-    let repo_info = fetch_github_repo_info(&repo);
-    if repo_info.license.key != "other" {
-        info.license = GITHUB_TO_NIXPKGS_LICENSE
-            .get(&*repo_info.license.key)
-            .unwrap_or(&"CHANGE")
-            .to_string();
+    /// Split `tag` into prefix and (transformed) version. Tags the scheme
+    /// doesn't match, or that `tag_for` couldn't rebuild verbatim (so the
+    /// rendered `rev` would point elsewhere), yield `None`.
+    pub fn split(&self, tag: &str) -> Option<SplitTag> {
+        let (prefix, raw_version) = match &self.regex {
+            Some(regex) => {
+                let captures = regex.captures(tag)?;
+                (
+                    captures.name("prefix").map_or("", |m| m.as_str()),
+                    captures.name("version")?.as_str(),
+                )
+            }
+            None => {
+                let captures = VERSION_REGEX.captures(tag)?;
+                (
+                    captures.get(1).unwrap().as_str(),
+                    captures.get(2).unwrap().as_str(),
+                )
+            }
+        };
+        let version = match self.separator {
+            Some(separator) => raw_version.replace(separator, "."),
+            None => raw_version.to_owned(),
+        };
+        if self.tag_for(prefix, &version) != tag {
+            return None;
+        }
+        Some(SplitTag {
+            prefix: prefix.to_owned(),
+            version,
+        })
     }
-    info.description = repo_info.description.unwrap_or("CHANGE".to_owned());
 
-    if info.pname == "CHANGE" {
-        info.pname = repo.repo.clone();
+    /// Rebuild the upstream tag from a prefix and version
+    pub fn tag_for(&self, prefix: &str, version: &str) -> String {
+        match self.separator {
+            Some(separator) => {
+                format!("{}{}", prefix, version.replace('.', &separator.to_string()))
+            }
+            None => format!("{}{}", prefix, version),
+        }
     }
-    if info.owner == "CHANGE" {
-        info.owner = repo.owner.clone();
+
+    /// The version as written in the tag, if it looks like one
+    fn version_of(&self, tag: &str) -> Option<String> {
+        self.split(tag)
+            .map(|t| t.version)
+            .filter(|v| v.starts_with(char::is_numeric))
+    }
+
+    /// Record `tag`'s prefix, version and separator in `info`, exiting if
+    /// the tag doesn't fit the scheme or isn't safe to use in commands.
+    fn apply(&self, info: &mut types::ExpressionInfo, tag: &str, source: &str) {
+        let split = match self.split(tag) {
+            Some(split) => split,
+            None => {
+                error!(target: LOG_TARGET, "Tag '{}' from {} does not match the tag regex", tag, source);
+                eprintln!("Error: Tag '{}' does not match the tag regex", tag);
+                exit(1);
+            }
+        };
+        info.version = split.version;
+        info.tag_prefix = split.prefix;
+        info.tag_version_separator = self.separator.map(String::from).unwrap_or_default();
+
+        // Validate version components before using in commands
+        if let Err(e) = validate_version_components(&info.version, &info.tag_prefix) {
+            error!(target: LOG_TARGET, "Invalid version from {}: {}", source, e);
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
     }
 }
 
-/// Populate `info` with metadata from a GitLab repository.
-///
-/// Uses GitLab's API v4 to fetch release and project information.
-/// Supports nested groups (e.g., gitlab.com/org/subgroup/repo).
-/// The project_path is URL-encoded for API calls.
-pub fn fill_gitlab_info(
-    repo: &types::GitlabRepo,
+/// Commit pinned by an unstable version.
+struct PinnedCommit {
+    sha: String,
+    /// ISO 8601 commit timestamp; only the date part is used
+    date: String,
+}
+
+/// Set an `0-unstable-YYYY-MM-DD` version pinned to `commit` and prefetch
+/// the unpacked `archive_url` of that commit.
+fn apply_unstable_version(
     info: &mut types::ExpressionInfo,
-    include_prereleases: bool,
+    commit: PinnedCommit,
+    archive_url: &str,
 ) {
-    // Validate repo components to prevent injection attacks
-    if let Err(e) = validate_gitlab_repo(repo) {
-        error!(target: LOG_TARGET, "Invalid GitLab repository: {}", e);
+    let date = commit.date.get(..10).unwrap_or("");
+    if !UNSTABLE_DATE_REGEX.is_match(date) {
+        eprintln!("Warning: Unexpected commit date '{}'", commit.date);
+        return;
+    }
+    if let Err(e) = validate_url_component(&commit.sha, "commit") {
+        error!(target: LOG_TARGET, "Invalid commit from forge API: {}", e);
         eprintln!("Error: {}", e);
         exit(1);
     }
 
-    if info.pname == "CHANGE" {
-        info.pname = repo.repo.to_string();
+    info.version = format!("0-unstable-{}", date);
+    info.tag_prefix = String::new();
+    info.src_rev = commit.sha;
+    eprintln!(
+        "Using unstable version {} ({})",
+        &info.version, &info.src_rev
+    );
+
+    prefetch_unpacked_sha(info, archive_url);
+}
+
+/// Download `url` into the Nix store, returning its SRI hash and store
+/// path. With `unpack` the hash is of the unpacked tree (as used by
+/// `fetchzip` and the forge fetchers), otherwise of the file itself (as used
+/// by `fetchurl`).
+fn prefetch_url(url: &str, unpack: bool) -> Option<(String, std::path::PathBuf)> {
+    let mut command = Command::new("nix-prefetch-url");
+    if unpack {
+        command.arg("--unpack");
     }
-    info.fetcher = types::Fetcher::gitlab;
-    if info.owner == "CHANGE" {
-        info.owner = repo.owner.clone();
+    let output = command
+        .args(["--print-path", "--type", "sha256"])
+        .arg(url)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut lines = stdout.lines();
+            let sha = to_sri(lines.next()?.trim());
+            let path = std::path::PathBuf::from(lines.next()?.trim());
+            Some((sha, path))
+        }
+        Ok(output) => {
+            debug!(
+                target: LOG_TARGET,
+                "nix-prefetch-url {} failed: {}",
+                url,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            None
+        }
+        Err(e) => {
+            eprintln!("Warning: Could not run nix-prefetch-url: {}", e);
+            None
+        }
     }
+}
 
-    let request_client = Client::new();
+/// Store the SRI hash of the unpacked `archive_url` in `info.src_sha`,
+/// warning (and leaving the placeholder) if nix-prefetch-url fails.
+fn prefetch_unpacked_sha(info: &mut types::ExpressionInfo, archive_url: &str) {
+    eprintln!("Determining sha256 for {}", &info.pname);
+    match prefetch_url(archive_url, true) {
+        Some((sha, _)) => info.src_sha = sha,
+        None => eprintln!("Warning: Could not prefetch {}", archive_url),
+    }
+}
 
-    // URL-encode the project path for API calls
+fn fetch_github_commit(repo: &types::GithubRepo, rev: &str) -> Result<PinnedCommit> {
+    let mut request = Client::new()
+        .get(format!(
+            "https://api.github.com/repos/{}/{}/commits/{}",
+            repo.owner,
+            repo.repo,
+            urlencoding::encode(rev)
+        ))
+        .header("User-Agent", "reqwest")
+        .header("Accept", "application/vnd.github.v3+json");
+
+    if let Ok(github_token) = std::env::var("GITHUB_TOKEN") {
+        request = request.header("Authorization", format!("token {}", github_token));
+    }
+
+    let commit: types::GhCommitResponse = serde_json::from_str(&get_json(request)?)?;
+    Ok(PinnedCommit {
+        sha: commit.sha,
+        date: commit.commit.committer.date,
+    })
+}
+
+/// Pin `rev` (or the default branch HEAD) of a GitHub repository.
+fn fill_github_unstable(
+    repo: &types::GithubRepo,
+    info: &mut types::ExpressionInfo,
+    rev: Option<&str>,
+) {
+    let rev = rev.unwrap_or("HEAD");
+    eprintln!(
+        "Determining commit {} for github.com/{}/{}",
+        rev, &repo.owner, &repo.repo
+    );
+    match fetch_github_commit(repo, rev) {
+        Ok(commit) => {
+            let archive_url = format!(
+                "https://github.com/{}/{}/archive/{}.tar.gz",
+                &repo.owner, &repo.repo, &commit.sha
+            );
+            apply_unstable_version(info, commit, &archive_url);
+        }
+        Err(e) => eprintln!("Warning: Could not resolve GitHub commit {}: {}", rev, e),
+    }
+}
+
+fn fetch_gitlab_commit(repo: &types::GitlabRepo, rev: Option<&str>) -> Result<PinnedCommit> {
+    let request_client = Client::new();
     let project_path_encoded = urlencoding::encode(&repo.project_path);
+    let with_token =
+        |request: reqwest::blocking::RequestBuilder| match std::env::var(&repo.token_env) {
+            Ok(token) => request.header("PRIVATE-TOKEN", token),
+            Err(_) => request,
+        };
 
-    eprintln!("Determining latest release for {}", &repo.project_path);
+    let rev = match rev {
+        Some(rev) => rev.to_owned(),
+        None => {
+            let project_url = format!("{}/projects/{}", repo.api_base, project_path_encoded);
+            let project: types::GitlabProjectResponse =
+                serde_json::from_str(&get_json(with_token(
+                    request_client
+                        .get(&project_url)
+                        .header("User-Agent", "nix-template"),
+                ))?)?;
+            project
+                .default_branch
+                .ok_or_else(|| anyhow!("project has no default branch"))?
+        }
+    };
 
-    // Try the /permalink/latest endpoint first (GitLab 15.4+)
-    let latest_url = format!(
-        "https://{}/api/v4/projects/{}/releases/permalink/latest",
-        repo.domain, project_path_encoded
+    let commit_url = format!(
+        "{}/projects/{}/repository/commits/{}",
+        repo.api_base,
+        project_path_encoded,
+        urlencoding::encode(&rev)
     );
+    let commit: types::GitlabCommitResponse = serde_json::from_str(&get_json(with_token(
+        request_client
+            .get(&commit_url)
+            .header("User-Agent", "nix-template"),
+    ))?)?;
+    Ok(PinnedCommit {
+        sha: commit.id,
+        date: commit.committed_date,
+    })
+}
 
-    let mut latest_request = request_client
-        .get(&latest_url)
-        .header("User-Agent", "nix-template")
+/// Pin `rev` (or the default branch HEAD) of a GitLab repository.
+fn fill_gitlab_unstable(
+    repo: &types::GitlabRepo,
+    info: &mut types::ExpressionInfo,
+    rev: Option<&str>,
+) {
+    eprintln!(
+        "Determining commit {} for {}/{}",
+        rev.unwrap_or("HEAD"),
+        &repo.domain,
+        &repo.project_path
+    );
+    match fetch_gitlab_commit(repo, rev) {
+        Ok(commit) => {
+            let archive_url = format!(
+                "https://{}/{}/-/archive/{}/{}-{}.tar.gz",
+                &repo.domain, &repo.project_path, &commit.sha, &repo.repo, &commit.sha
+            );
+            apply_unstable_version(info, commit, &archive_url);
+        }
+        Err(e) => eprintln!("Warning: Could not resolve GitLab commit: {}", e),
+    }
+}
+
+fn fetch_gitea_commit(repo: &types::GiteaRepo, rev: Option<&str>) -> Result<PinnedCommit> {
+    // Without `sha`, the listing starts at the default branch
+    let mut commits_url = format!(
+        "{}/repos/{}/{}/commits?limit=1&stat=false",
+        repo.api_base, repo.owner, repo.repo
+    );
+    if let Some(rev) = rev {
+        commits_url = format!("{}&sha={}", commits_url, urlencoding::encode(rev));
+    }
+    let mut request = Client::new()
+        .get(&commits_url)
+        .header("User-Agent", "reqwest")
         .header("Accept", "application/json");
 
-    if let Ok(token) = std::env::var("GITLAB_TOKEN") {
-        latest_request = latest_request.header("PRIVATE-TOKEN", token);
+    if let Ok(token) = std::env::var(&repo.token_env) {
+        request = request.header("Authorization", format!("token {}", token));
     }
 
-    // Try latest endpoint first, fall back to list if not available
-    let release_result = get_json(latest_request);
+    let commits: Vec<types::GhCommitResponse> = serde_json::from_str(&get_json(request)?)?;
+    let commit = commits
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("no commits found"))?;
+    Ok(PinnedCommit {
+        sha: commit.sha,
+        date: commit.commit.committer.date,
+    })
+}
 
-    let release_body = match release_result {
-        Ok(body) => Some(body),
-        Err(_) => {
-            // Fallback: fetch all releases and find latest
-            eprintln!("Latest release endpoint not available, fetching all releases...");
-            let list_url = format!(
-                "https://{}/api/v4/projects/{}/releases",
-                repo.domain, project_path_encoded
+/// Pin `rev` (or the default branch HEAD) of a Gitea/Forgejo repository.
+fn fill_gitea_unstable(
+    repo: &types::GiteaRepo,
+    info: &mut types::ExpressionInfo,
+    rev: Option<&str>,
+) {
+    eprintln!(
+        "Determining commit {} for {}/{}/{}",
+        rev.unwrap_or("HEAD"),
+        &repo.domain,
+        &repo.owner,
+        &repo.repo
+    );
+    match fetch_gitea_commit(repo, rev) {
+        Ok(commit) => {
+            let archive_url = format!(
+                "https://{}/{}/{}/archive/{}.tar.gz",
+                &repo.domain, &repo.owner, &repo.repo, &commit.sha
             );
+            apply_unstable_version(info, commit, &archive_url);
+        }
+        Err(e) => eprintln!("Warning: Could not resolve Gitea commit: {}", e),
+    }
+}
 
-            let mut list_request = request_client
-                .get(&list_url)
-                .header("User-Agent", "nix-template")
-                .header("Accept", "application/json");
-
-            if let Ok(token) = std::env::var("GITLAB_TOKEN") {
-                list_request = list_request.header("PRIVATE-TOKEN", token);
-            }
-
-            match get_json(list_request) {
-                Ok(body) => Some(body),
-                Err(e) => {
-                    eprintln!("Warning: Could not fetch GitLab releases: {}", e);
-                    None
-                }
-            }
+/// Upper bound on the number of tag pages walked per repository, so a
+/// misbehaving API can't keep us paging forever.
+const MAX_TAG_PAGES: usize = 50;
+
+/// Walk a page-numbered tags endpoint. `fetch_page` is called with 1-based
+/// page numbers until it returns fewer than `per_page` tag names.
+fn collect_tag_pages(
+    per_page: usize,
+    mut fetch_page: impl FnMut(usize) -> Result<Vec<String>>,
+) -> Result<Vec<String>> {
+    let mut tags = Vec::new();
+    for page in 1..=MAX_TAG_PAGES {
+        let names = fetch_page(page)?;
+        let last_page = names.len() < per_page;
+        tags.extend(names);
+        if last_page {
+            break;
         }
-    };
+    }
+    Ok(tags)
+}
 
-    if let Some(body) = release_body {
-        // Parse as list even if we got single release from /latest (wrap in array if needed)
-        let releases_result: Result<types::GitlabReleaseResponse, _> = serde_json::from_str(&body);
-
-        let mut releases = match releases_result {
-            Ok(r) => r,
-            Err(_) => {
-                // Maybe it's a single release object from /latest endpoint
-                let single: Result<types::GitlabReleaseElement, _> = serde_json::from_str(&body);
-                match single {
-                    Ok(r) => vec![r],
-                    Err(e) => {
-                        eprintln!("Warning: Could not parse GitLab release data: {}", e);
-                        vec![]
-                    }
-                }
-            }
-        };
+/// Pick the newest version among a forge's tags, for repositories that
+/// push tags without creating releases. Tags without a version (e.g.
+/// "nightly") or outside `scheme` are ignored and prereleases are dropped
+/// with `is_prerelease_tag` unless `include_prereleases` is set.
+fn latest_forge_tag(
+    mut tags: Vec<String>,
+    include_prereleases: bool,
+    scheme: &TagScheme,
+) -> Option<String> {
+    tags.retain(|t| scheme.version_of(t).is_some());
+    if !include_prereleases {
+        tags.retain(|t| !is_prerelease_tag(t));
+    }
+    tags.into_iter()
+        .max_by(|a, b| compare_tag_versions(scheme, a, b))
+}
 
-        if !releases.is_empty() {
-            // Sort by released_at timestamp (most recent first)
-            releases.sort_by(|a, b| b.released_at.cmp(&a.released_at));
+/// Order two tags by their versions under `scheme`; both must have one.
+fn compare_tag_versions(scheme: &TagScheme, a: &str, b: &str) -> std::cmp::Ordering {
+    VersionCompare::compare(
+        &scheme.version_of(a).unwrap(),
+        &scheme.version_of(b).unwrap(),
+    )
+    .ok()
+    .and_then(|c| c.ord())
+    .unwrap_or(std::cmp::Ordering::Equal)
+}
 
-            // Filter out prereleases using heuristic (unless --include-prereleases is set)
-            if !include_prereleases {
-                releases = releases
-                    .into_iter()
-                    .filter(|r| !is_prerelease_tag(&r.tag_name))
-                    .collect();
-            }
+/// Find `wanted` among `tags`, either verbatim or as the version part of a
+/// prefixed tag, so `1.2.3` also finds `v1.2.3`. A verbatim match wins.
+fn find_tag(tags: &[String], wanted: &str, scheme: &TagScheme) -> Option<String> {
+    tags.iter()
+        .find(|t| *t == wanted)
+        .or_else(|| {
+            tags.iter()
+                .find(|t| scheme.split(t).is_some_and(|split| split.version == wanted))
+        })
+        .cloned()
+}
 
-            if let Some(latest) = releases.first() {
-                let parsed_version = VERSION_REGEX.captures(&latest.tag_name).unwrap();
-                info.version = parsed_version.get(2).unwrap().as_str().to_owned();
-                info.tag_prefix = parsed_version.get(1).unwrap().as_str().to_owned();
+/// Resolve `wanted` against a forge's tag list, exiting with an error when
+/// the tags can't be listed or the release doesn't exist.
+fn require_tag(
+    tags: Result<Vec<String>>,
+    wanted: &str,
+    location: &str,
+    scheme: &TagScheme,
+) -> String {
+    let tags = match tags {
+        Ok(tags) => tags,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not list tags for {}: {}", location, e);
+            eprintln!("Error: Could not list tags for {}: {}", location, e);
+            exit(1);
+        }
+    };
+    match find_tag(&tags, wanted, scheme) {
+        Some(tag) => tag,
+        None => {
+            error!(target: LOG_TARGET, "No release or tag '{}' found for {}", wanted, location);
+            eprintln!(
+                "Error: No release or tag '{}' found for {}",
+                wanted, location
+            );
+            exit(1);
+        }
+    }
+}
 
-                // Validate version components before using in commands
-                if let Err(e) = validate_version_components(&info.version, &info.tag_prefix) {
-                    error!(target: LOG_TARGET, "Invalid version from GitLab API: {}", e);
-                    eprintln!("Error: {}", e);
-                    exit(1);
-                }
+fn fetch_github_tags(api_base: &str, repo: &types::GithubRepo) -> Result<Vec<String>> {
+    const PER_PAGE: usize = 100;
+    let request_client = Client::new();
+    collect_tag_pages(PER_PAGE, |page| {
+        let mut request = request_client
+            .get(format!(
+                "{}/repos/{}/{}/tags?per_page={}&page={}",
+                api_base, repo.owner, repo.repo, PER_PAGE, page
+            ))
+            .header("User-Agent", "reqwest")
+            .header("Accept", "application/vnd.github.v3+json");
 
-                eprintln!("Determining sha256 for {}", &repo.repo);
+        if let Ok(github_token) = std::env::var("GITHUB_TOKEN") {
+            request = request.header("Authorization", format!("token {}", github_token));
+        }
 
-                // GitLab archive URL format: /-/archive/{tag}/{repo}-{tag}.tar.gz
-                let archive_url = format!(
-                    "https://{}/{}/-/archive/{}{}/{}-{}{}.tar.gz",
-                    &repo.domain,
-                    &repo.project_path,
-                    &info.tag_prefix,
-                    &info.version,
-                    &repo.repo,
-                    &info.tag_prefix,
-                    &info.version
-                );
+        let tags: types::GhTagResponse = serde_json::from_str(&get_json(request)?)?;
+        Ok(tags.into_iter().map(|t| t.name).collect())
+    })
+}
 
-                let sha256_cmd = Command::new("nix-prefetch-url")
-                    .args(&["--unpack", "--type", "sha256"])
-                    .arg(&archive_url)
-                    .output();
-
-                match sha256_cmd {
-                    Ok(output) => {
-                        if output.status.success() {
-                            info.src_sha =
-                                to_sri(String::from_utf8_lossy(&output.stdout).to_string().trim());
-                        } else {
-                            eprintln!(
-                                "Warning: nix-prefetch-url failed: {}",
-                                String::from_utf8_lossy(&output.stderr)
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Warning: Could not run nix-prefetch-url: {}", e);
-                    }
-                }
-            }
-        } else {
-            // No releases found, fallback to tags
+/// Resolve the latest GitHub release, falling back to the tags API.
+/// Returns false when neither yields a version.
+fn fill_github_release(
+    repo: &types::GithubRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) -> bool {
+    let scheme = &options.tag_scheme;
+    eprintln!("Determining latest release for {}", &repo.repo);
+    let latest_release = fetch_github_release_info(repo)
+        .into_iter()
+        .filter(|r| options.include_prereleases || !r.prerelease)
+        .map(|r| r.tag_name)
+        .filter(|t| scheme.version_of(t).is_some())
+        .max_by(|a, b| compare_tag_versions(scheme, a, b));
+    let latest_tag = match latest_release {
+        Some(tag) => tag,
+        None => {
             eprintln!("No releases found, trying tags API...");
-            let tags_url = format!(
-                "https://{}/api/v4/projects/{}/repository/tags",
-                repo.domain, project_path_encoded
-            );
-
-            let mut tags_request = request_client
-                .get(&tags_url)
-                .header("User-Agent", "nix-template")
-                .header("Accept", "application/json");
-
-            if let Ok(token) = std::env::var("GITLAB_TOKEN") {
-                tags_request = tags_request.header("PRIVATE-TOKEN", token);
-            }
-
-            match get_json(tags_request) {
-                Ok(tags_body) => {
-                    let tags_result: Result<types::GitlabTagsResponse, _> =
-                        serde_json::from_str(&tags_body);
-                    if let Ok(mut tags) = tags_result {
-                        if !tags.is_empty() {
-                            // Sort by tag name using version comparison
-                            tags.sort_by(|a, b| {
-                                VersionCompare::compare(&b.name, &a.name)
-                                    .unwrap()
-                                    .ord()
-                                    .unwrap()
-                            });
-
-                            // Filter out prereleases (unless --include-prereleases is set)
-                            if !include_prereleases {
-                                tags = tags
-                                    .into_iter()
-                                    .filter(|t| !is_prerelease_tag(&t.name))
-                                    .collect();
-                            }
-
-                            if let Some(latest_tag) = tags.first() {
-                                if let Some(captures) = VERSION_REGEX.captures(&latest_tag.name) {
-                                    info.version = captures.get(2).unwrap().as_str().to_owned();
-                                    info.tag_prefix = captures.get(1).unwrap().as_str().to_owned();
-
-                                    eprintln!("Determining sha256 for {}", &repo.repo);
-
-                                    let archive_url = format!(
-                                        "https://{}/{}/-/archive/{}{}/{}-{}{}.tar.gz",
-                                        &repo.domain,
-                                        &repo.project_path,
-                                        &info.tag_prefix,
-                                        &info.version,
-                                        &repo.repo,
-                                        &info.tag_prefix,
-                                        &info.version
-                                    );
-
-                                    let sha256_cmd = Command::new("nix-prefetch-url")
-                                        .args(&["--unpack", "--type", "sha256"])
-                                        .arg(&archive_url)
-                                        .output();
-
-                                    match sha256_cmd {
-                                        Ok(output) => {
-                                            if output.status.success() {
-                                                info.src_sha = to_sri(
-                                                    String::from_utf8_lossy(&output.stdout)
-                                                        .to_string()
-                                                        .trim(),
-                                                );
-                                            } else {
-                                                eprintln!(
-                                                    "Warning: nix-prefetch-url failed: {}",
-                                                    String::from_utf8_lossy(&output.stderr)
-                                                );
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!(
-                                                "Warning: Could not run nix-prefetch-url: {}",
-                                                e
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            let tags = match fetch_github_tags(GITHUB_API_BASE, repo) {
+                Ok(tags) => latest_forge_tag(tags, options.include_prereleases, scheme),
                 Err(e) => {
-                    eprintln!("Warning: Could not fetch GitLab tags: {}", e);
+                    eprintln!("Warning: Could not fetch GitHub tags: {}", e);
+                    None
                 }
+            };
+            match tags {
+                Some(tag) => tag,
+                None => return false,
             }
         }
-    }
+    };
 
-    // Fetch project metadata for description and license
-    let project_url = format!(
-        "https://{}/api/v4/projects/{}",
-        repo.domain, project_path_encoded
+    apply_github_tag(repo, info, &latest_tag, scheme);
+    true
+}
+
+/// Set the version and tag prefix from `tag` and prefetch its archive.
+fn apply_github_tag(
+    repo: &types::GithubRepo,
+    info: &mut types::ExpressionInfo,
+    tag: &str,
+    scheme: &TagScheme,
+) {
+    scheme.apply(info, tag, "GitHub API");
+
+    let archive_url = format!(
+        "https://github.com/{}/{}/archive/refs/tags/{}.tar.gz",
+        &repo.owner, &repo.repo, tag
     );
+    prefetch_unpacked_sha(info, &archive_url);
+}
 
-    let mut project_request = request_client
-        .get(&project_url)
-        .header("User-Agent", "nix-template")
-        .header("Accept", "application/json");
+/// Pin the release or tag `wanted` (`--tag`, or `-v` with a URL).
+fn fill_github_tag(
+    repo: &types::GithubRepo,
+    info: &mut types::ExpressionInfo,
+    wanted: &str,
+    scheme: &TagScheme,
+) {
+    let location = format!("github.com/{}/{}", &repo.owner, &repo.repo);
+    eprintln!("Looking up tag {} for {}", wanted, &location);
+    let tags = fetch_github_tags(GITHUB_API_BASE, repo);
+    let tag = require_tag(tags, wanted, &location, scheme);
+    apply_github_tag(repo, info, &tag, scheme);
+}
 
-    if let Ok(token) = std::env::var("GITLAB_TOKEN") {
-        project_request = project_request.header("PRIVATE-TOKEN", token);
+pub fn fill_github_info(
+    repo: &types::GithubRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    // AppImages are named after their desktop entry unless -p was given
+    let pname_given = info.pname != "CHANGE";
+    if info.pname == "CHANGE" {
+        info.pname = repo.repo.to_string();
     }
 
-    match get_json(project_request) {
-        Ok(body) => {
-            let project: Result<types::GitlabProjectResponse, _> = serde_json::from_str(&body);
-            if let Ok(proj) = project {
-                if let Some(desc) = proj.description {
-                    info.description = desc;
-                }
-                info.homepage = proj.web_url;
-
-                if let Some(license) = proj.license {
-                    info.license = GITHUB_TO_NIXPKGS_LICENSE
-                        .get(license.key.as_str())
-                        .unwrap_or(&"CHANGE")
-                        .to_string();
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Warning: Could not fetch GitLab project metadata: {}", e);
-            info.homepage = format!("https://{}/{}", repo.domain, repo.project_path);
-        }
+    if options.wants_unstable() {
+        fill_github_unstable(repo, info, options.rev.as_deref());
+    } else if let Some(tag) = &options.tag {
+        fill_github_tag(repo, info, tag, &options.tag_scheme);
+    } else if !fill_github_release(repo, info, options) {
+        eprintln!(
+            "No releases or tags found for github.com/{}/{}; falling back to an unstable version",
+            &repo.owner, &repo.repo
+        );
+        fill_github_unstable(repo, info, None);
     }
-}
 
-/// Populate `info` with metadata from a Gitea repository.
-///
-/// Gitea's REST API closely mirrors GitHub's: `/api/v1/repos/<owner>/<repo>`
-/// returns repo metadata, and `/api/v1/repos/<owner>/<repo>/releases`
-/// returns releases. We deserialize into the same response structs we use
-/// for GitHub to keep the code path narrow.
-///
-/// Releases are not always present on a Gitea instance, so the function
-/// gracefully degrades to leaving the version/hash placeholders alone if
-/// the API call fails or returns no releases.
-pub fn fill_gitea_info(repo: &types::GiteaRepo, info: &mut types::ExpressionInfo) {
-    // Validate repo components to prevent injection attacks
-    if let Err(e) = validate_gitea_repo(repo) {
-        error!(target: LOG_TARGET, "Invalid Gitea repository: {}", e);
-        eprintln!("Error: {}", e);
-        exit(1);
+    let repo_info = fetch_github_repo_info(&repo);
+    if repo_info.license.key != "other" {
+        info.license = GITHUB_TO_NIXPKGS_LICENSE
+            .get(&*repo_info.license.key)
+            .unwrap_or(&"CHANGE")
+            .to_string();
     }
+    info.description = repo_info.description.unwrap_or("CHANGE".to_owned());
 
     if info.pname == "CHANGE" {
-        info.pname = repo.repo.to_string();
+        info.pname = repo.repo.clone();
     }
-    info.fetcher = types::Fetcher::gitea;
-    info.domain = repo.domain.clone();
     if info.owner == "CHANGE" {
         info.owner = repo.owner.clone();
     }
 
-    let request_client = Client::new();
-
-    eprintln!(
-        "Determining latest release for {}/{}",
-        &repo.owner, &repo.repo
-    );
-    let releases_url = format!(
-        "https://{}/api/v1/repos/{}/{}/releases",
-        repo.domain, repo.owner, repo.repo
-    );
-    let mut releases_request = request_client
-        .get(&releases_url)
-        .header("User-Agent", "reqwest")
-        .header("Accept", "application/json");
-
-    // Add GITEA_TOKEN if available (using Authorization header)
-    if let Ok(token) = std::env::var("GITEA_TOKEN") {
-        releases_request = releases_request.header("Authorization", format!("token {}", token));
-    }
-
-    match get_json(releases_request) {
-        Ok(body) => {
-            let parsed: Result<types::GhReleaseResponse, _> = serde_json::from_str(&body);
-            match parsed {
-                Ok(mut releases) if !releases.is_empty() => {
-                    releases.sort_by(|a, b| {
-                        VersionCompare::compare(&b.tag_name, &a.tag_name)
-                            .unwrap()
-                            .ord()
-                            .unwrap()
-                    });
-                    releases = releases.into_iter().filter(|a| !a.prerelease).collect();
-                    if let Some(latest) = releases.first() {
-                        let parsed_version = VERSION_REGEX.captures(&latest.tag_name).unwrap();
-                        info.version = parsed_version.get(2).unwrap().as_str().to_owned();
-                        info.tag_prefix = parsed_version.get(1).unwrap().as_str().to_owned();
-
-                        // Validate version components before using in commands
-                        if let Err(e) = validate_version_components(&info.version, &info.tag_prefix)
-                        {
-                            error!(target: LOG_TARGET, "Invalid version from Gitea API: {}", e);
-                            eprintln!("Error: {}", e);
-                            exit(1);
-                        }
-
-                        eprintln!("Determining sha256 for {}", &repo.repo);
-                        // Gitea archive URL: <domain>/<owner>/<repo>/archive/<tag>.tar.gz
-                        let archive_url = format!(
-                            "https://{}/{}/{}/archive/{}{}.tar.gz",
-                            &repo.domain, &repo.owner, &repo.repo, &info.tag_prefix, &info.version,
-                        );
-                        let sha256_cmd = Command::new("nix-prefetch-url")
-                            .args(&["--unpack", "--type", "sha256"])
-                            .arg(&archive_url)
-                            .output();
-                        if let Ok(out) = sha256_cmd {
-                            let raw = std::str::from_utf8(&out.stdout)
-                                .unwrap_or("")
-                                .trim()
-                                .to_owned();
-                            if !raw.is_empty() {
-                                info.src_sha = to_sri(&raw);
-                            }
-                        }
-                    }
-                }
-                Ok(_) => eprintln!(
-                    "No releases found for {}/{}/{}",
-                    &repo.domain, &repo.owner, &repo.repo
-                ),
-                Err(e) => error!(
-                    target: LOG_TARGET,
-                    "Unable to parse Gitea releases response: {:?}", e
-                ),
-            }
+    // Prebuilt binaries come from the release's assets rather than the
+    // source; unstable versions have no release to take them from
+    if matches!(info.template, Template::Binary(_)) {
+        if !info.src_rev.is_empty() {
+            eprintln!(
+                "Error: The binary template packages release assets, but github.com/{}/{} was pinned to commit {}",
+                &repo.owner, &repo.repo, &info.src_rev
+            );
+            exit(1);
         }
-        Err(e) => eprintln!("Failed to fetch Gitea releases: {}", e),
+        fill_github_release_assets(GITHUB_API_BASE, repo, info);
     }
+    if matches!(info.template, Template::AppImage(_)) && info.src_rev.is_empty() {
+        fill_github_appimage_asset(GITHUB_API_BASE, repo, pname_given, info);
+    }
+}
 
-    // Repo metadata: description and homepage.
-    let repo_url = format!(
-        "https://{}/api/v1/repos/{}/{}",
-        repo.domain, repo.owner, repo.repo
-    );
-    let repo_request = request_client
-        .get(&repo_url)
-        .header("User-Agent", "reqwest")
-        .header("Accept", "application/json");
-
-    if let Ok(body) = get_json(repo_request) {
-        // Gitea's repo response has a `description` field; reuse the
-        // GhRepoResponse deserializer where compatible.
-        if let Ok(parsed) = serde_json::from_str::<types::GhRepoResponse>(&body) {
-            if info.description == "CHANGE" {
-                info.description = parsed.description.unwrap_or_else(|| "CHANGE".to_owned());
-            }
+/// Extensions of release assets that are archives to unpack.
+const ARCHIVE_ASSET_EXTENSIONS: &[&str] = &[
+    ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2", ".tbz2", ".tar.zst", ".zip",
+];
+
+/// Whether a release asset is an archive (`Some(true)`), a bare executable
+/// (`Some(false)`) or neither, e.g. a checksum, signature or installer.
+fn release_asset_kind(name: &str) -> Option<bool> {
+    let name = name.to_lowercase();
+    if ARCHIVE_ASSET_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+        return Some(true);
+    }
+    // A short alphanumeric suffix is an extension (.sha256, .deb, .exe);
+    // anything else after the last dot belongs to the version
+    match name.rsplit_once('.') {
+        Some((_, extension))
+            if extension.len() <= 8 && extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            None
         }
+        _ => Some(false),
     }
-
-    info.homepage = format!("https://{}/{}/{}", repo.domain, repo.owner, repo.repo);
 }
 
-pub fn fill_pypi_info(pypi_repo: &types::PypiRepo, info: &mut types::ExpressionInfo) {
-    eprintln!("Determining latest release for {}", &pypi_repo.project);
-    let pypi_response = fetch_pypi_project_info(pypi_repo);
-    if info.pname == "CHANGE" {
-        info.pname = pypi_repo.project.clone();
+/// Nix systems a release asset was built for, judging by its name, e.g.
+/// "tool-x86_64-unknown-linux-gnu.tar.gz" or "tool_linux_amd64.zip". macOS
+/// assets without a single architecture are taken to be universal.
+fn release_asset_systems(name: &str) -> Vec<&'static str> {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name.split(|c: char| !c.is_ascii_alphanumeric()).collect();
+    let has = |word: &str| words.contains(&word);
+    let linux = has("linux");
+    let darwin = has("darwin") || has("macos") || has("osx") || has("mac") || has("apple");
+    let x86_64 = name.contains("x86_64") || name.contains("x86-64") || has("amd64") || has("x64");
+    let aarch64 = has("aarch64") || has("arm64");
+
+    match (linux, darwin, x86_64, aarch64) {
+        (true, false, true, false) => vec!["x86_64-linux"],
+        (true, false, false, true) => vec!["aarch64-linux"],
+        (false, true, true, false) => vec!["x86_64-darwin"],
+        (false, true, false, true) => vec!["aarch64-darwin"],
+        (false, true, _, _) => vec!["x86_64-darwin", "aarch64-darwin"],
+        _ => Vec::new(),
     }
+}
 
-    let mut releases: Vec<String> = pypi_response
-        .releases
-        .keys()
-        .map(|a| a.to_owned())
-        .filter(|v| STABLE_RELEASE_REGEX.is_match(v))
+/// Pick one release asset per Nix system. Archives win over bare
+/// executables, so that every system is unpacked the same way; then glibc
+/// over musl builds, single-architecture over universal builds and
+/// shorter names. Returns the selected (system, asset name) pairs and
+/// whether they are archives.
+fn select_release_assets<'a>(names: &[&'a str]) -> (Vec<(&'static str, &'a str)>, bool) {
+    let candidates: Vec<(&str, bool, Vec<&str>)> = names
+        .iter()
+        .filter_map(|name| {
+            let archive = release_asset_kind(name)?;
+            let systems = release_asset_systems(name);
+            (!systems.is_empty()).then_some((*name, archive, systems))
+        })
         .collect();
+    let archives = candidates.iter().any(|(_, archive, _)| *archive);
+
+    let selected = ["x86_64-linux", "aarch64-linux", "x86_64-darwin", "aarch64-darwin"]
+        .iter()
+        .filter_map(|system| {
+            candidates
+                .iter()
+                .filter(|(_, archive, systems)| *archive == archives && systems.contains(system))
+                .min_by_key(|(name, _, systems)| {
+                    (name.to_lowercase().contains("musl"), systems.len(), name.len())
+                })
+                .map(|(name, _, _)| (*system, *name))
+        })
+        .collect();
+    (selected, archives)
+}
 
-    if !releases.is_empty() {
-        releases.sort_by(|a, b| VersionCompare::compare(&b, &a).unwrap().ord().unwrap());
-
-        let latest_version = releases.first().unwrap();
+/// Fetch the GitHub release published for `tag`.
+fn fetch_github_release(
+    api_base: &str,
+    repo: &types::GithubRepo,
+    tag: &str,
+) -> Result<types::GhReleaseResponseElement> {
+    let mut request = Client::new()
+        .get(format!(
+            "{}/repos/{}/{}/releases/tags/{}",
+            api_base,
+            repo.owner,
+            repo.repo,
+            urlencoding::encode(tag)
+        ))
+        .header("User-Agent", "reqwest")
+        .header("Accept", "application/vnd.github.v3+json");
 
-        let latest_release = pypi_response
-            .releases
-            .get(latest_version)
-            .unwrap()
-            .iter()
-            .filter(|a| a.packagetype == "sdist")
-            .next();
+    if let Ok(github_token) = std::env::var("GITHUB_TOKEN") {
+        request = request.header("Authorization", format!("token {}", github_token));
+    }
 
-        info.version = latest_version.clone();
-        info.homepage = pypi_response.info.home_page.unwrap_or("CHANGE".to_string());
-        info.description = pypi_response.info.summary.trim_end_matches(".").to_string();
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
 
-        info.license = PYPI_TO_NIXPKGS_LICENSE
-            .get(&*pypi_response.info.license)
-            .unwrap_or(&"CHANGE")
-            .to_string();
+/// Fill the per-system sources of the binary template from the assets of
+/// the release of the resolved tag, prefetching each of them. Exits with an
+/// error when the release has no assets for Linux or macOS.
+fn fill_github_release_assets(
+    api_base: &str,
+    repo: &types::GithubRepo,
+    info: &mut types::ExpressionInfo,
+) {
+    let tag = info.upstream_tag();
+    let release = match fetch_github_release(api_base, repo, &tag) {
+        Ok(release) => release,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not list the assets of release {}: {}", &tag, e);
+            eprintln!("Error: Could not list the assets of release {}: {}", &tag, e);
+            exit(1);
+        }
+    };
+    let names: Vec<&str> = release.assets.iter().map(|a| a.name.as_str()).collect();
+    let (selected, archives) = select_release_assets(&names);
+    if selected.is_empty() {
+        eprintln!("Error: No Linux or macOS assets found in release {}", &tag);
+        exit(1);
+    }
 
-        // Grab dependencies, filter out extras, normalize names
-        debug!(
-            "Python dependencies before normalization: {:?}",
-            &pypi_response.info.requires_dist
-        );
-        let mut dependencies: Vec<String> = pypi_response
-            .info
-            .requires_dist
-            .unwrap_or_else(|| Vec::new())
-            .into_iter()
-            .filter(|s| !s.contains("extra =="))
-            .map(|s| {
-                s.split(" ")
-                    .next()
-                    .unwrap()
-                    // Remove version information
-                    .chars()
-                    .take_while(|&ch| ch != '!' && ch != '<' && ch != '>' && ch != '=')
-                    .collect::<String>()
-                    // Normalize name to adhere to Nixpkgs conventions
-                    .replace(".", "-")
-                    .replace("_", "-")
-            })
-            .collect();
-        dependencies.sort();
-        debug!("dependencies after normalization: {:?}", &dependencies);
-        info.propagated_build_inputs = dependencies;
-
-        match latest_release {
-            Some(dist) => {
-                info.fetcher = types::Fetcher::pypi;
-                info.src_sha = to_sri(&dist.digests.sha256);
-            }
+    let mut sources = Vec::new();
+    for (system, name) in selected {
+        let url = match release.assets.iter().find(|a| a.name == name) {
+            Some(asset) => &asset.browser_download_url,
+            None => continue,
+        };
+        eprintln!("Determining sha256 for {} ({})", name, system);
+        let hash = match prefetch_url(url, false) {
+            Some((sha, _)) => sha,
             None => {
-                eprintln!(
-                    "Unable to find sdist for {}. Using default template",
-                    &pypi_repo.project
-                );
+                eprintln!("Warning: Could not prefetch {}", url);
+                FAKE_SRI_HASH.to_owned()
             }
-        }
-    } else {
-        eprintln!(
-            "No releases found for pypi.org/project/{}",
-            &pypi_repo.project
-        );
+        };
+        sources.push(types::BinarySource {
+            system: system.to_owned(),
+            url: template_archive_url(url, &info.version),
+            hash,
+        });
+    }
+    if let Some(config) = info.template.binary_config_mut() {
+        config.sources = sources;
+        config.archives = archives;
+    }
+    if info.main_program.is_empty() {
+        info.main_program = info.pname.clone();
     }
 }
 
-/// Prefetch the `cargoHash` (for `rust` template) or `vendorHash` (for `go`
-/// template) by performing a build with `lib.fakeHash` and parsing the
-/// resulting hash mismatch from `nix-build`'s stderr.
-///
-/// The expression at `info` must already have a known `src_sha`. The function
-/// renders the package expression to a temporary file, invokes `nix-build`
-/// against it via `callPackage`, and extracts the SRI hash from the
-/// "got:" line that nix prints on hash mismatch.
-///
-/// Returns `None` if the build did not produce a hash mismatch (e.g. nix is
-/// not installed, the source failed to fetch, the hash placeholder was
-/// already correct, etc.). Logs progress to stderr.
-pub fn prefetch_dependency_hash(info: &types::ExpressionInfo) -> Option<String> {
-    use std::io::Write;
-
-    // Only Rust, Go, npm, and pnpm packages need dependency hash prefetching.
-    match info.template {
-        Template::Rust(_) | Template::Go(_) | Template::Node(_) => (),
-        _ => return None,
-    }
+/// Pick the AppImage among the assets of a release, preferring x86_64
+/// builds (those naming no other architecture) and then shorter names.
+fn select_appimage_asset<'a>(names: &[&'a str]) -> Option<&'a str> {
+    names
+        .iter()
+        .filter(|name| name.ends_with(".AppImage"))
+        .min_by_key(|name| {
+            let words: Vec<String> = name
+                .to_lowercase()
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .map(|word| word.to_owned())
+                .collect();
+            let other_arch = ["aarch64", "arm64", "armhf", "armv7l", "i386", "i686"]
+                .iter()
+                .any(|arch| words.iter().any(|word| word == arch));
+            (other_arch, name.len())
+        })
+        .copied()
+}
 
-    // Skip when the hash is already determined:
-    // - Rust with cargoLock.lockFile doesn't need cargoHash.
-    // - Go with a committed vendor/ directory uses vendorHash = null.
-    if info.use_cargo_lock_file {
-        return None;
+/// Use the AppImage among the assets of the release of the resolved tag
+/// as the source of the AppImage template.
+fn fill_github_appimage_asset(
+    api_base: &str,
+    repo: &types::GithubRepo,
+    pname_given: bool,
+    info: &mut types::ExpressionInfo,
+) {
+    let tag = info.upstream_tag();
+    let release = match fetch_github_release(api_base, repo, &tag) {
+        Ok(release) => release,
+        Err(e) => {
+            eprintln!("Warning: Could not list the assets of release {}: {}", &tag, e);
+            return;
+        }
+    };
+    let names: Vec<&str> = release.assets.iter().map(|a| a.name.as_str()).collect();
+    let asset = match select_appimage_asset(&names)
+        .and_then(|name| release.assets.iter().find(|a| a.name == name))
+    {
+        Some(asset) => asset,
+        None => {
+            eprintln!("Warning: No AppImage found in release {}", &tag);
+            return;
+        }
+    };
+
+    let url = asset.browser_download_url.clone();
+    eprintln!("Determining sha256 for {}", &asset.name);
+    info.src_sha = match prefetch_url(&url, false) {
+        Some((sha, _)) => sha,
+        None => {
+            eprintln!("Warning: Could not prefetch {}", &url);
+            FAKE_SRI_HASH.to_owned()
+        }
+    };
+    info.fetcher = types::Fetcher::url;
+    info.src_url = template_archive_url(&url, &info.version);
+    apply_appimage_contents(&url, pname_given, info);
+}
+
+/// Fill version, description and the desktop integration of the AppImage
+/// template from the desktop entry of the AppImage at `url`, and pname
+/// unless the user gave one.
+fn apply_appimage_contents(url: &str, pname_given: bool, info: &mut types::ExpressionInfo) {
+    use crate::deps::appimage::{find_icon, read_desktop_entry};
+
+    eprintln!("Extracting the desktop entry of {}", url);
+    let contents = match crate::source::extract_appimage(info, url) {
+        Some(path) => path,
+        None => {
+            eprintln!("Warning: Could not extract {}", url);
+            return;
+        }
+    };
+    let entry = match read_desktop_entry(&contents) {
+        Some(entry) => entry,
+        None => {
+            eprintln!("Warning: No desktop entry found in {}", url);
+            return;
+        }
+    };
+
+    if !pname_given {
+        info.pname = entry.pname();
     }
-    if info.template.is_go() && info.vendor_hash == types::VENDOR_HASH_NULL {
-        return None;
+    // wrapType2 installs the program as $out/bin/${pname}
+    info.main_program = info.pname.clone();
+    if let Some(version) = &entry.version {
+        let version = version.strip_prefix('v').unwrap_or(version);
+        if validate_version_components(version, "").is_ok() {
+            info.version = version.to_owned();
+            info.src_url = template_archive_url(url, version);
+        }
+    }
+    if info.description == "CHANGE" {
+        if let Some(comment) = &entry.comment {
+            info.description = comment.trim_end_matches('.').to_owned();
+        }
     }
+    if let Some(config) = info.template.appimage_config_mut() {
+        config.desktop_file = Some(entry.file_name.clone());
+        config.exec = entry.exec_command().map(|command| command.to_owned());
+        config.icon = find_icon(&contents, &entry);
+    }
+}
 
-    // We can't prefetch without a real source hash to feed the builder.
-    if info.src_sha.is_empty()
-        || info
-            .src_sha
-            .starts_with("0000000000000000000000000000000000000000000000000000")
-    {
-        eprintln!("Skipping hash prefetch: src_sha is not yet known");
-        return None;
+fn fetch_gitlab_tags(repo: &types::GitlabRepo) -> Result<Vec<String>> {
+    const PER_PAGE: usize = 100;
+    let request_client = Client::new();
+    let project_path_encoded = urlencoding::encode(&repo.project_path);
+    collect_tag_pages(PER_PAGE, |page| {
+        let mut request = request_client
+            .get(format!(
+                "{}/projects/{}/repository/tags?per_page={}&page={}",
+                repo.api_base, project_path_encoded, PER_PAGE, page
+            ))
+            .header("User-Agent", "nix-template")
+            .header("Accept", "application/json");
+
+        if let Ok(token) = std::env::var(&repo.token_env) {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+
+        let tags: types::GitlabTagsResponse = serde_json::from_str(&get_json(request)?)?;
+        Ok(tags.into_iter().map(|t| t.name).collect())
+    })
+}
+
+/// Resolve the latest GitLab release, falling back to the tags API.
+/// Returns false when neither yields a version.
+fn fill_gitlab_release(
+    repo: &types::GitlabRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) -> bool {
+    let scheme = &options.tag_scheme;
+    let request_client = Client::new();
+
+    // URL-encode the project path for API calls
+    let project_path_encoded = urlencoding::encode(&repo.project_path);
+
+    eprintln!("Determining latest release for {}", &repo.project_path);
+
+    // Try the /permalink/latest endpoint first (GitLab 15.4+)
+    let latest_url = format!(
+        "{}/projects/{}/releases/permalink/latest",
+        repo.api_base, project_path_encoded
+    );
+
+    let mut latest_request = request_client
+        .get(&latest_url)
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    if let Ok(token) = std::env::var(&repo.token_env) {
+        latest_request = latest_request.header("PRIVATE-TOKEN", token);
     }
 
-    // Render the expression with a fake dependency hash so that nix
-    // is forced to fetch the dependencies and emit a "got:" line.
-    let probe_info = types::ExpressionInfo {
-        pname: info.pname.clone(),
-        version: info.version.clone(),
-        license: info.license.clone(),
-        maintainer: info.maintainer.clone(),
-        fetcher: info.fetcher.clone(),
-        template: info.template.clone(),
-        path_to_write: std::path::PathBuf::new(),
-        top_level_path: std::path::PathBuf::new(),
-        // Documentation links and meta would clutter / break the probe expression
-        // (e.g. `licenses.CHANGE` does not exist).
-        include_documentation_links: false,
-        include_meta: false,
-        tag_prefix: info.tag_prefix.clone(),
-        owner: info.owner.clone(),
-        src_sha: info.src_sha.clone(),
-        description: info.description.clone(),
-        homepage: info.homepage.clone(),
-        propagated_build_inputs: info.propagated_build_inputs.clone(),
-        cargo_hash: FAKE_SRI_HASH.to_owned(),
-        vendor_hash: FAKE_SRI_HASH.to_owned(),
-        npm_deps_hash: FAKE_SRI_HASH.to_owned(),
-        pnpm_deps_hash: FAKE_SRI_HASH.to_owned(),
-        project_file: String::new(),
-        domain: info.domain.clone(),
-        // Probe expressions don't need to render the inferred deps;
-        // we want a minimal expression that just exercises src + cargo.
-        build_inputs: Vec::new(),
-        native_build_inputs: Vec::new(),
-        use_cargo_lock_file: false,
-        cargo_lock_git_deps: Vec::new(),
-        go_module_path: String::new(),
-        python_format: "setuptools".to_owned(),
-        mvn_hash: FAKE_SRI_HASH.to_owned(),
-        mix_fod_hash: FAKE_SRI_HASH.to_owned(),
-        gradle_hash: FAKE_SRI_HASH.to_owned(),
-    };
+    // Try latest endpoint first, fall back to list if not available
+    let release_result = get_json(latest_request);
 
-    let probe_expr = crate::expression::generate_expression(&probe_info);
-    let probe_text = probe_info.format(&probe_expr);
+    let release_body = match release_result {
+        Ok(body) => Some(body),
+        Err(_) => {
+            // Fallback: fetch all releases and find latest
+            eprintln!("Latest release endpoint not available, fetching all releases...");
+            let list_url = format!(
+                "{}/projects/{}/releases",
+                repo.api_base, project_path_encoded
+            );
 
-    // Write to a temp file (deleted when the variable goes out of scope).
-    let tmp_dir = match tempfile_dir() {
-        Some(d) => d,
-        None => {
-            eprintln!("Skipping hash prefetch: unable to create temporary directory");
-            return None;
+            let mut list_request = request_client
+                .get(&list_url)
+                .header("User-Agent", "nix-template")
+                .header("Accept", "application/json");
+
+            if let Ok(token) = std::env::var(&repo.token_env) {
+                list_request = list_request.header("PRIVATE-TOKEN", token);
+            }
+
+            match get_json(list_request) {
+                Ok(body) => Some(body),
+                Err(e) => {
+                    eprintln!("Warning: Could not fetch GitLab releases: {}", e);
+                    None
+                }
+            }
         }
     };
-    let probe_path = tmp_dir.join("probe.nix");
-    {
-        let mut f = match std::fs::File::create(&probe_path) {
-            Ok(f) => f,
+
+    let mut latest_tag = None;
+    if let Some(body) = release_body {
+        // Parse as list even if we got single release from /latest (wrap in array if needed)
+        let releases_result: Result<types::GitlabReleaseResponse, _> = serde_json::from_str(&body);
+
+        let mut releases = match releases_result {
+            Ok(r) => r,
+            Err(_) => {
+                // Maybe it's a single release object from /latest endpoint
+                let single: Result<types::GitlabReleaseElement, _> = serde_json::from_str(&body);
+                match single {
+                    Ok(r) => vec![r],
+                    Err(e) => {
+                        eprintln!("Warning: Could not parse GitLab release data: {}", e);
+                        vec![]
+                    }
+                }
+            }
+        };
+
+        // Sort by released_at timestamp (most recent first)
+        releases.sort_by(|a, b| b.released_at.cmp(&a.released_at));
+
+        // Filter out prereleases using heuristic (unless --include-prereleases is set)
+        latest_tag = releases
+            .into_iter()
+            .filter(|r| scheme.version_of(&r.tag_name).is_some())
+            .find(|r| options.include_prereleases || !is_prerelease_tag(&r.tag_name))
+            .map(|r| r.tag_name);
+    }
+
+    if latest_tag.is_none() {
+        // No releases found, fallback to tags
+        eprintln!("No releases found, trying tags API...");
+        latest_tag = match fetch_gitlab_tags(repo) {
+            Ok(tags) => latest_forge_tag(tags, options.include_prereleases, scheme),
             Err(e) => {
-                eprintln!(
-                    "Skipping hash prefetch: failed to write probe expression: {}",
-                    e
-                );
-                return None;
+                eprintln!("Warning: Could not fetch GitLab tags: {}", e);
+                None
             }
         };
-        if let Err(e) = f.write_all(probe_text.as_bytes()) {
-            eprintln!(
-                "Skipping hash prefetch: failed to write probe expression: {}",
-                e
-            );
-            return None;
-        }
     }
 
-    let kind = match &info.template {
-        Template::Rust(_) => "cargoHash",
-        Template::Go(_) => "vendorHash",
-        Template::Node(config) => match config.variant {
-            crate::types::NodeVariant::Npm => "npmDepsHash",
-            crate::types::NodeVariant::Pnpm => "pnpmDepsHash",
-        },
-        _ => unreachable!(),
+    let latest_tag = match latest_tag {
+        Some(tag) => tag,
+        None => return false,
     };
-    eprintln!(
-        "Prefetching {} for {} (this may take a while)...",
-        kind, &info.pname
+    apply_gitlab_tag(repo, info, &latest_tag, scheme);
+    true
+}
+
+/// Set the version and tag prefix from `tag` and prefetch its archive.
+fn apply_gitlab_tag(
+    repo: &types::GitlabRepo,
+    info: &mut types::ExpressionInfo,
+    tag: &str,
+    scheme: &TagScheme,
+) {
+    scheme.apply(info, tag, "GitLab API");
+
+    // GitLab archive URL format: /-/archive/{tag}/{repo}-{tag}.tar.gz
+    let archive_url = format!(
+        "https://{}/{}/-/archive/{}/{}-{}.tar.gz",
+        &repo.domain, &repo.project_path, tag, &repo.repo, tag
     );
+    prefetch_unpacked_sha(info, &archive_url);
+}
 
-    let output = Command::new("nix-build")
-        .args(&["--no-out-link", "-E"])
-        .arg(format!(
-            "(import <nixpkgs> {{}}).callPackage {} {{}}",
-            probe_path.display()
-        ))
-        .output();
+/// Pin the release or tag `wanted` (`--tag`, or `-v` with a URL).
+fn fill_gitlab_tag(
+    repo: &types::GitlabRepo,
+    info: &mut types::ExpressionInfo,
+    wanted: &str,
+    scheme: &TagScheme,
+) {
+    let location = format!("{}/{}", &repo.domain, &repo.project_path);
+    eprintln!("Looking up tag {} for {}", wanted, &location);
+    let tag = require_tag(fetch_gitlab_tags(repo), wanted, &location, scheme);
+    apply_gitlab_tag(repo, info, &tag, scheme);
+}
 
-    let output = match output {
-        Ok(o) => o,
-        Err(e) => {
-            eprintln!("Skipping hash prefetch: failed to invoke nix-build: {}", e);
-            return None;
-        }
-    };
+/// Populate `info` with metadata from a GitLab repository.
+///
+/// Uses GitLab's API v4 to fetch release and project information.
+/// Supports nested groups (e.g., gitlab.com/org/subgroup/repo).
+/// The project_path is URL-encoded for API calls.
+pub fn fill_gitlab_info(
+    repo: &types::GitlabRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    // Validate repo components to prevent injection attacks
+    if let Err(e) = validate_gitlab_repo(repo) {
+        error!(target: LOG_TARGET, "Invalid GitLab repository: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
 
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    debug!(target: LOG_TARGET, "nix-build stderr: {}", stderr);
+    if info.pname == "CHANGE" {
+        info.pname = repo.repo.to_string();
+    }
+    info.fetcher = types::Fetcher::gitlab;
+    info.domain = repo.domain.clone();
+    if info.owner == "CHANGE" {
+        info.owner = repo.owner.clone();
+    }
 
-    let captured = GOT_HASH_REGEX
-        .captures_iter(&stderr)
-        .filter_map(|c| c.get(1).map(|m| m.as_str().to_owned()))
-        // The first hash mismatch is the dependency hash (cargo deps / go
-        // vendor tree); subsequent hashes (if any) belong to later phases.
-        .next();
+    if options.wants_unstable() {
+        fill_gitlab_unstable(repo, info, options.rev.as_deref());
+    } else if let Some(tag) = &options.tag {
+        fill_gitlab_tag(repo, info, tag, &options.tag_scheme);
+    } else if !fill_gitlab_release(repo, info, options) {
+        eprintln!(
+            "No releases or tags found for {}; falling back to an unstable version",
+            &repo.project_path
+        );
+        fill_gitlab_unstable(repo, info, None);
+    }
+
+    let request_client = Client::new();
+
+    // URL-encode the project path for API calls
+    let project_path_encoded = urlencoding::encode(&repo.project_path);
+
+    // Fetch project metadata for description and license
+    let project_url = format!("{}/projects/{}", repo.api_base, project_path_encoded);
+
+    let mut project_request = request_client
+        .get(&project_url)
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    if let Ok(token) = std::env::var(&repo.token_env) {
+        project_request = project_request.header("PRIVATE-TOKEN", token);
+    }
+
+    match get_json(project_request) {
+        Ok(body) => {
+            let project: Result<types::GitlabProjectResponse, _> = serde_json::from_str(&body);
+            if let Ok(proj) = project {
+                if let Some(desc) = proj.description {
+                    info.description = desc;
+                }
+                info.homepage = proj.web_url;
+
+                if let Some(license) = proj.license {
+                    info.license = GITHUB_TO_NIXPKGS_LICENSE
+                        .get(license.key.as_str())
+                        .unwrap_or(&"CHANGE")
+                        .to_string();
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Warning: Could not fetch GitLab project metadata: {}", e);
+            info.homepage = format!("https://{}/{}", repo.domain, repo.project_path);
+        }
+    }
+}
+
+fn fetch_gitea_tags(repo: &types::GiteaRepo) -> Result<Vec<String>> {
+    // Gitea caps `limit` at the instance's MAX_RESPONSE_ITEMS, 50 by default
+    const PER_PAGE: usize = 50;
+    let request_client = Client::new();
+    collect_tag_pages(PER_PAGE, |page| {
+        let mut request = request_client
+            .get(format!(
+                "{}/repos/{}/{}/tags?limit={}&page={}",
+                repo.api_base, repo.owner, repo.repo, PER_PAGE, page
+            ))
+            .header("User-Agent", "reqwest")
+            .header("Accept", "application/json");
+
+        if let Ok(token) = std::env::var(&repo.token_env) {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+
+        let tags: types::GhTagResponse = serde_json::from_str(&get_json(request)?)?;
+        Ok(tags.into_iter().map(|t| t.name).collect())
+    })
+}
+
+/// Resolve the latest Gitea release, falling back to the tags API.
+/// Returns false when neither yields a version.
+fn fill_gitea_release(
+    repo: &types::GiteaRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) -> bool {
+    let scheme = &options.tag_scheme;
+    let request_client = Client::new();
+
+    eprintln!(
+        "Determining latest release for {}/{}",
+        &repo.owner, &repo.repo
+    );
+    let releases_url = format!(
+        "{}/repos/{}/{}/releases",
+        repo.api_base, repo.owner, repo.repo
+    );
+    let mut releases_request = request_client
+        .get(&releases_url)
+        .header("User-Agent", "reqwest")
+        .header("Accept", "application/json");
+
+    // Add GITEA_TOKEN if available (using Authorization header)
+    if let Ok(token) = std::env::var(&repo.token_env) {
+        releases_request = releases_request.header("Authorization", format!("token {}", token));
+    }
+
+    let mut latest_tag = None;
+    match get_json(releases_request) {
+        Ok(body) => {
+            let parsed: Result<types::GhReleaseResponse, _> = serde_json::from_str(&body);
+            match parsed {
+                Ok(releases) => {
+                    latest_tag = releases
+                        .into_iter()
+                        .filter(|r| options.include_prereleases || !r.prerelease)
+                        .map(|r| r.tag_name)
+                        .filter(|t| scheme.version_of(t).is_some())
+                        .max_by(|a, b| compare_tag_versions(scheme, a, b));
+                }
+                Err(e) => error!(
+                    target: LOG_TARGET,
+                    "Unable to parse Gitea releases response: {:?}", e
+                ),
+            }
+        }
+        Err(e) => eprintln!("Failed to fetch Gitea releases: {}", e),
+    }
+
+    if latest_tag.is_none() {
+        eprintln!(
+            "No releases found for {}/{}/{}, trying tags API...",
+            &repo.domain, &repo.owner, &repo.repo
+        );
+        latest_tag = match fetch_gitea_tags(repo) {
+            Ok(tags) => latest_forge_tag(tags, options.include_prereleases, scheme),
+            Err(e) => {
+                eprintln!("Warning: Could not fetch Gitea tags: {}", e);
+                None
+            }
+        };
+    }
+
+    let latest_tag = match latest_tag {
+        Some(tag) => tag,
+        None => return false,
+    };
+    apply_gitea_tag(repo, info, &latest_tag, scheme);
+    true
+}
+
+/// Set the version and tag prefix from `tag` and prefetch its archive.
+fn apply_gitea_tag(
+    repo: &types::GiteaRepo,
+    info: &mut types::ExpressionInfo,
+    tag: &str,
+    scheme: &TagScheme,
+) {
+    scheme.apply(info, tag, "Gitea API");
+
+    // Gitea archive URL: <domain>/<owner>/<repo>/archive/<tag>.tar.gz
+    let archive_url = format!(
+        "https://{}/{}/{}/archive/{}.tar.gz",
+        &repo.domain, &repo.owner, &repo.repo, tag
+    );
+    prefetch_unpacked_sha(info, &archive_url);
+}
+
+/// Pin the release or tag `wanted` (`--tag`, or `-v` with a URL).
+fn fill_gitea_tag(
+    repo: &types::GiteaRepo,
+    info: &mut types::ExpressionInfo,
+    wanted: &str,
+    scheme: &TagScheme,
+) {
+    let location = format!("{}/{}/{}", &repo.domain, &repo.owner, &repo.repo);
+    eprintln!("Looking up tag {} for {}", wanted, &location);
+    let tag = require_tag(fetch_gitea_tags(repo), wanted, &location, scheme);
+    apply_gitea_tag(repo, info, &tag, scheme);
+}
+
+/// Populate `info` with metadata from a Gitea repository.
+///
+/// Gitea's REST API closely mirrors GitHub's: `/api/v1/repos/<owner>/<repo>`
+/// returns repo metadata, and `/api/v1/repos/<owner>/<repo>/releases`
+/// returns releases. We deserialize into the same response structs we use
+/// for GitHub to keep the code path narrow.
+///
+/// Releases are not always present on a Gitea instance, so the function
+/// falls back to the repository's tags and then to an unstable version of
+/// the default branch.
+pub fn fill_gitea_info(
+    repo: &types::GiteaRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    // Validate repo components to prevent injection attacks
+    if let Err(e) = validate_gitea_repo(repo) {
+        error!(target: LOG_TARGET, "Invalid Gitea repository: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+
+    if info.pname == "CHANGE" {
+        info.pname = repo.repo.to_string();
+    }
+    info.fetcher = types::Fetcher::gitea;
+    info.domain = repo.domain.clone();
+    if info.owner == "CHANGE" {
+        info.owner = repo.owner.clone();
+    }
+
+    if options.wants_unstable() {
+        fill_gitea_unstable(repo, info, options.rev.as_deref());
+    } else if let Some(tag) = &options.tag {
+        fill_gitea_tag(repo, info, tag, &options.tag_scheme);
+    } else if !fill_gitea_release(repo, info, options) {
+        eprintln!(
+            "No releases or tags found for {}/{}/{}; falling back to an unstable version",
+            &repo.domain, &repo.owner, &repo.repo
+        );
+        fill_gitea_unstable(repo, info, None);
+    }
+
+    let request_client = Client::new();
+
+    // Repo metadata: description and homepage.
+    let repo_url = format!("{}/repos/{}/{}", repo.api_base, repo.owner, repo.repo);
+    let mut repo_request = request_client
+        .get(&repo_url)
+        .header("User-Agent", "reqwest")
+        .header("Accept", "application/json");
+
+    if let Ok(token) = std::env::var(&repo.token_env) {
+        repo_request = repo_request.header("Authorization", format!("token {}", token));
+    }
+
+    if let Ok(body) = get_json(repo_request) {
+        // Gitea's repo response has a `description` field; reuse the
+        // GhRepoResponse deserializer where compatible.
+        if let Ok(parsed) = serde_json::from_str::<types::GhRepoResponse>(&body) {
+            if info.description == "CHANGE" {
+                info.description = parsed.description.unwrap_or_else(|| "CHANGE".to_owned());
+            }
+        }
+    }
+
+    info.homepage = format!("https://{}/{}/{}", repo.domain, repo.owner, repo.repo);
+}
+
+/// Collect every tag name of a Sourcehut git repository.
+///
+/// The refs endpoint is paginated (`next` is the cursor for `?start=`), so
+/// all pages are walked before returning. Branch refs are dropped and the
+/// `refs/tags/` prefix is stripped.
+fn fetch_sourcehut_tags(repo: &types::SourcehutRepo) -> Result<Vec<String>> {
+    let request_client = Client::new();
+    let mut tags = Vec::new();
+    let mut start: Option<String> = None;
+
+    loop {
+        let mut refs_url = format!("{}/{}/repos/{}/refs", repo.api_base, repo.owner, repo.repo);
+        if let Some(cursor) = &start {
+            refs_url = format!("{}?start={}", refs_url, urlencoding::encode(cursor));
+        }
+
+        let mut request = request_client
+            .get(&refs_url)
+            .header("User-Agent", "nix-template")
+            .header("Accept", "application/json");
+
+        if let Ok(token) = std::env::var(&repo.token_env) {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+
+        let body = get_json(request)?;
+        let page: types::SourcehutRefsResponse = serde_json::from_str(&body)?;
+        tags.extend(
+            page.results
+                .into_iter()
+                .filter_map(|r| r.name.strip_prefix("refs/tags/").map(|t| t.to_owned())),
+        );
+
+        start = match page.next {
+            Some(serde_json::Value::String(s)) => Some(s),
+            Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        };
+        if start.is_none() {
+            break;
+        }
+    }
+
+    Ok(tags)
+}
+
+/// Populate `info` with metadata from a Sourcehut git repository.
+///
+/// Sourcehut has no concept of releases, so the latest version is taken
+/// from the repository's tags. The hash is computed from the tag's
+/// `archive/<tag>.tar.gz` tarball, which is what `fetchFromSourcehut` uses.
+pub fn fill_sourcehut_info(
+    repo: &types::SourcehutRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    // Validate repo components to prevent injection attacks
+    if let Err(e) = validate_sourcehut_repo(repo) {
+        error!(target: LOG_TARGET, "Invalid Sourcehut repository: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+
+    if info.pname == "CHANGE" {
+        info.pname = repo.repo.to_string();
+    }
+    info.fetcher = types::Fetcher::sourcehut;
+    info.domain = repo.fetcher_domain().to_owned();
+    if info.owner == "CHANGE" {
+        info.owner = repo.owner.clone();
+    }
+
+    eprintln!(
+        "Determining latest release for {}/{}",
+        &repo.owner, &repo.repo
+    );
+    match fetch_sourcehut_tags(repo) {
+        Ok(tags) if !tags.is_empty() => {
+            // Filter out prereleases (unless --include-prereleases is set)
+            let latest_tag =
+                latest_forge_tag(tags, options.include_prereleases, &options.tag_scheme);
+
+            if let Some(latest_tag) = latest_tag {
+                options.tag_scheme.apply(info, &latest_tag, "Sourcehut API");
+
+                // Sourcehut archive URL: <domain>/~<owner>/<repo>/archive/<tag>.tar.gz
+                let archive_url = format!(
+                    "https://{}/{}/{}/archive/{}.tar.gz",
+                    &repo.domain, &repo.owner, &repo.repo, &latest_tag,
+                );
+                prefetch_unpacked_sha(info, &archive_url);
+            }
+        }
+        Ok(_) => eprintln!(
+            "No tags found for {}/{}/{}",
+            &repo.domain, &repo.owner, &repo.repo
+        ),
+        Err(e) => eprintln!("Warning: Could not fetch Sourcehut refs: {}", e),
+    }
+
+    // Repo metadata: description.
+    let repo_url = format!("{}/{}/repos/{}", repo.api_base, repo.owner, repo.repo);
+    let mut repo_request = Client::new()
+        .get(&repo_url)
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    if let Ok(token) = std::env::var(&repo.token_env) {
+        repo_request = repo_request.header("Authorization", format!("token {}", token));
+    }
+
+    if let Ok(body) = get_json(repo_request) {
+        if let Ok(parsed) = serde_json::from_str::<types::SourcehutRepoResponse>(&body) {
+            if let Some(desc) = parsed.description.filter(|d| !d.is_empty()) {
+                info.description = desc;
+            }
+        }
+    }
+
+    info.homepage = format!("https://{}/{}/{}", repo.domain, repo.owner, repo.repo);
+}
+
+/// Base URL of the Bitbucket Cloud REST API. Overridable through
+/// `BITBUCKET_API_URL` so the lookups can be pointed at a mock server.
+fn bitbucket_api_base() -> String {
+    std::env::var("BITBUCKET_API_URL")
+        .map(|base| base.trim_end_matches('/').to_owned())
+        .unwrap_or_else(|_| "https://api.bitbucket.org/2.0".to_owned())
+}
+
+fn bitbucket_request(request_client: &Client, url: &str) -> reqwest::blocking::RequestBuilder {
+    let request = request_client
+        .get(url)
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    // Add BITBUCKET_TOKEN if available (repository/workspace access token)
+    match std::env::var("BITBUCKET_TOKEN") {
+        Ok(token) => request.header("Authorization", format!("Bearer {}", token)),
+        Err(_) => request,
+    }
+}
+
+/// Collect every tag name of a Bitbucket Cloud repository.
+///
+/// The refs endpoint is paginated; `next` holds the absolute URL of the
+/// following page and is followed until exhausted.
+fn fetch_bitbucket_tags(api_base: &str, repo: &types::BitbucketRepo) -> Result<Vec<String>> {
+    let request_client = Client::new();
+    let mut tags = Vec::new();
+    let mut next = Some(format!(
+        "{}/repositories/{}/{}/refs/tags?pagelen=100",
+        api_base, repo.workspace, repo.repo
+    ));
+
+    while let Some(page_url) = next {
+        let body = get_json(bitbucket_request(&request_client, &page_url))?;
+        let page: types::BitbucketRefsResponse = serde_json::from_str(&body)?;
+        tags.extend(page.values.into_iter().map(|r| r.name));
+        next = page.next;
+    }
+
+    Ok(tags)
+}
+
+fn fetch_bitbucket_repo_info(
+    api_base: &str,
+    repo: &types::BitbucketRepo,
+) -> Result<types::BitbucketRepoResponse> {
+    let repo_url = format!("{}/repositories/{}/{}", api_base, repo.workspace, repo.repo);
+    let body = get_json(bitbucket_request(&Client::new(), &repo_url))?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Populate `info` with metadata from a Bitbucket Cloud repository.
+///
+/// Bitbucket has no releases, so the latest version is taken from the
+/// repository's tags. The hash is computed from the `get/<tag>.tar.gz`
+/// archive, which is what `fetchFromBitbucket` downloads.
+pub fn fill_bitbucket_info(
+    repo: &types::BitbucketRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    // Validate repo components to prevent injection attacks
+    if let Err(e) = validate_bitbucket_repo(repo) {
+        error!(target: LOG_TARGET, "Invalid Bitbucket repository: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+
+    if info.pname == "CHANGE" {
+        info.pname = repo.repo.to_string();
+    }
+    info.fetcher = types::Fetcher::bitbucket;
+    if info.owner == "CHANGE" {
+        info.owner = repo.workspace.clone();
+    }
+
+    let api_base = bitbucket_api_base();
+
+    eprintln!(
+        "Determining latest release for {}/{}",
+        &repo.workspace, &repo.repo
+    );
+    match fetch_bitbucket_tags(&api_base, repo) {
+        Ok(tags) if !tags.is_empty() => {
+            // Filter out prereleases (unless --include-prereleases is set)
+            let latest_tag =
+                latest_forge_tag(tags, options.include_prereleases, &options.tag_scheme);
+
+            if let Some(latest_tag) = latest_tag {
+                options.tag_scheme.apply(info, &latest_tag, "Bitbucket API");
+
+                // Bitbucket archive URL: bitbucket.org/<workspace>/<repo>/get/<tag>.tar.gz
+                let archive_url = format!(
+                    "https://bitbucket.org/{}/{}/get/{}.tar.gz",
+                    &repo.workspace, &repo.repo, &latest_tag,
+                );
+                prefetch_unpacked_sha(info, &archive_url);
+            }
+        }
+        Ok(_) => eprintln!(
+            "No tags found for bitbucket.org/{}/{}",
+            &repo.workspace, &repo.repo
+        ),
+        Err(e) => eprintln!("Warning: Could not fetch Bitbucket tags: {}", e),
+    }
+
+    // Repo metadata: description and homepage.
+    info.homepage = format!("https://bitbucket.org/{}/{}", repo.workspace, repo.repo);
+    match fetch_bitbucket_repo_info(&api_base, repo) {
+        Ok(parsed) => {
+            if let Some(desc) = parsed.description.filter(|d| !d.is_empty()) {
+                info.description = desc;
+            }
+            if let Some(website) = parsed.website.filter(|w| !w.is_empty()) {
+                info.homepage = website;
+            }
+        }
+        Err(e) => debug!(target: LOG_TARGET, "Could not fetch Bitbucket repo info: {}", e),
+    }
+}
+
+/// List the tags of a git repository with `git ls-remote --tags`.
+fn fetch_git_tags(url: &str) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["ls-remote", "--tags", "--refs", "--"])
+        .arg(url)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git ls-remote failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // `--refs` drops the peeled `refs/tags/<tag>^{}` entries
+    let tags = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .filter_map(|r| r.strip_prefix("refs/tags/"))
+        .map(|t| t.to_owned())
+        .collect();
+    Ok(tags)
+}
+
+/// Pick the highest version among `tags`. Tags whose version part (as
+/// split by `scheme`) isn't a plain release are skipped unless
+/// `include_prereleases` is set.
+fn latest_version_tag<'a>(
+    tags: &'a [String],
+    include_prereleases: bool,
+    scheme: &TagScheme,
+) -> Option<&'a String> {
+    tags.iter()
+        .filter(|tag| match scheme.version_of(tag) {
+            Some(_) if include_prereleases => true,
+            Some(version) => STABLE_RELEASE_REGEX.is_match(&version),
+            None => false,
+        })
+        .max_by(|a, b| compare_tag_versions(scheme, a, b))
+}
+
+/// Populate `info` from a plain git repository.
+///
+/// There is no API to ask for a description or license, so only the
+/// version (from tags) and the `fetchgit` hash are filled in.
+pub fn fill_git_info(
+    repo: &types::GitRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    // Validate repo components to prevent injection attacks
+    if let Err(e) = validate_git_repo(repo) {
+        error!(target: LOG_TARGET, "Invalid git repository: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+
+    if info.pname == "CHANGE" {
+        info.pname = repo.repo.to_string();
+    }
+    info.fetcher = types::Fetcher::git;
+    info.src_url = repo.url.clone();
+    info.homepage = if repo.url.starts_with("http") {
+        repo.url.trim_end_matches(".git").to_owned()
+    } else {
+        "CHANGE".to_owned()
+    };
+
+    eprintln!("Determining latest release for {}", &repo.url);
+    let tags = match fetch_git_tags(&repo.url) {
+        Ok(tags) => tags,
+        Err(e) => {
+            eprintln!("Warning: Could not list tags of {}: {}", &repo.url, e);
+            return;
+        }
+    };
+    let latest_tag =
+        match latest_version_tag(&tags, options.include_prereleases, &options.tag_scheme) {
+            Some(tag) => tag,
+            None => {
+                eprintln!("No version tags found for {}", &repo.url);
+                return;
+            }
+        };
+
+    options.tag_scheme.apply(info, latest_tag, "git tags");
+
+    eprintln!("Determining sha256 for {}", &repo.repo);
+    // fetchgit clones submodules unless told otherwise, so hash them too
+    if let Some(hash) = prefetch_git_hash(
+        &repo.url,
+        &format!("refs/tags/{}", latest_tag),
+        &["--fetch-submodules"],
+    ) {
+        info.src_sha = hash;
+    }
+}
+
+/// Run `nix-prefetch-git` for `rev` of `url` and return the SRI hash.
+fn prefetch_git_hash(url: &str, rev: &str, extra_args: &[&str]) -> Option<String> {
+    let prefetch_cmd = Command::new("nix-prefetch-git")
+        .args(["--quiet", "--url"])
+        .arg(url)
+        .arg("--rev")
+        .arg(rev)
+        .args(extra_args)
+        .output();
+
+    match prefetch_cmd {
+        Ok(output) if output.status.success() => {
+            let parsed: serde_json::Value =
+                serde_json::from_slice(&output.stdout).unwrap_or(serde_json::Value::Null);
+            // Newer nix-prefetch-git emits an SRI `hash`; older ones only `sha256`
+            if let Some(hash) = parsed["hash"].as_str() {
+                Some(hash.to_owned())
+            } else {
+                parsed["sha256"].as_str().map(to_sri)
+            }
+        }
+        Ok(output) => {
+            eprintln!(
+                "Warning: nix-prefetch-git failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            None
+        }
+        Err(e) => {
+            eprintln!("Warning: Could not run nix-prefetch-git: {}", e);
+            None
+        }
+    }
+}
+
+/// Clone URL of a git-hosted repository, used to refetch the source with
+/// submodules or LFS objects.
+fn git_clone_url(repo: &types::Repo) -> Option<String> {
+    match repo {
+        Github(repo) => Some(format!(
+            "https://github.com/{}/{}.git",
+            repo.owner, repo.repo
+        )),
+        Gitlab(repo) => Some(format!("https://{}/{}.git", repo.domain, repo.project_path)),
+        Gitea(repo) => Some(format!(
+            "https://{}/{}/{}.git",
+            repo.domain, repo.owner, repo.repo
+        )),
+        Sourcehut(repo) => Some(format!(
+            "https://{}/{}/{}",
+            repo.domain, repo.owner, repo.repo
+        )),
+        Bitbucket(repo) => Some(format!(
+            "https://bitbucket.org/{}/{}.git",
+            repo.workspace, repo.repo
+        )),
+        Git(repo) => Some(repo.url.clone()),
+        Pypi(_) | Crates(_) | Npm(_) | Hackage(_) | Rubygems(_) | Cpan(_) | Cran(_) | Hex(_)
+        | Packagist(_) | Maven(_) | LuaRocks(_) | Pub(_) | Opam(_) | GoModule(_) | Archive(_) => {
+            None
+        }
+    }
+}
+
+/// Enable `fetchSubmodules`/`fetchLFS` when the unpacked source has a
+/// `.gitmodules` file or LFS-tracked paths, and recompute the hash to match.
+///
+/// Forge archives never contain submodules or LFS objects, so the hash
+/// prefetched from the archive is replaced with the one of a full checkout.
+fn apply_git_features(info: &mut types::ExpressionInfo, clone_url: &str) {
+    let source_path = match crate::source::materialise_source(info) {
+        Some(path) => path,
+        None => return,
+    };
+    let features = crate::source::detect_git_features(&source_path);
+    if !features.submodules && !features.lfs {
+        return;
+    }
+
+    let mut prefetch_args = Vec::new();
+    if features.submodules {
+        eprintln!("Found .gitmodules; fetching submodules");
+        info.fetch_submodules = true;
+        prefetch_args.push("--fetch-submodules");
+    }
+    if features.lfs {
+        eprintln!("Found git LFS attributes; fetching LFS objects");
+        info.fetch_lfs = true;
+        prefetch_args.push("--fetch-lfs");
+    }
+
+    use_fetchgit_if_needed(info, clone_url);
+
+    let rev = if !info.src_rev.is_empty() {
+        info.src_rev.clone()
+    } else {
+        format!("refs/tags/{}", info.upstream_tag())
+    };
+
+    eprintln!(
+        "Determining sha256 for {} with a full checkout",
+        &info.pname
+    );
+    // A stale archive hash would silently mismatch; prefer an obvious placeholder
+    info.src_sha = prefetch_git_hash(clone_url, &rev, &prefetch_args)
+        .unwrap_or_else(|| FAKE_SRI_HASH.to_owned());
+}
+
+/// Switch to `fetchgit` when the forge fetcher can't provide the checkout
+/// `info` asks for: fetchFromBitbucket only downloads archives, and
+/// fetchFromSourcehut has no `fetchLFS`.
+fn use_fetchgit_if_needed(info: &mut types::ExpressionInfo, clone_url: &str) {
+    let needs_fetchgit = match info.fetcher {
+        types::Fetcher::bitbucket => info.fetch_submodules || info.fetch_lfs,
+        types::Fetcher::sourcehut => info.fetch_lfs,
+        _ => false,
+    };
+    if needs_fetchgit {
+        info.fetcher = types::Fetcher::git;
+        info.src_url = clone_url.to_owned();
+    }
+}
+
+/// Populate `info` from a release archive URL.
+///
+/// Name and version come from the filename; the hash is prefetched (flat
+/// for `fetchurl`, unpacked for `fetchzip`). AppImages select the AppImage
+/// template, which reads them from the embedded desktop entry instead.
+pub fn fill_archive_info(archive: &types::ArchiveRepo, info: &mut types::ExpressionInfo) {
+    let pname_given = info.pname != "CHANGE";
+    if info.pname == "CHANGE" {
+        if let Some(pname) = &archive.pname {
+            info.pname = pname.clone();
+        }
+    }
+    if let Some(version) = &archive.version {
+        // Validate version components before using in commands
+        if let Err(e) = validate_version_components(version, "") {
+            error!(target: LOG_TARGET, "Invalid version in archive URL: {}", e);
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+        info.version = version.clone();
+    } else {
+        eprintln!(
+            "Warning: Could not infer a version from {}; the url is rendered verbatim",
+            &archive.url
+        );
+    }
+
+    info.fetcher = if archive.is_zip {
+        types::Fetcher::zip
+    } else {
+        types::Fetcher::url
+    };
+    info.src_url = match &archive.version {
+        Some(version) => template_archive_url(&archive.url, version),
+        None => archive.url.clone(),
+    };
+    info.homepage = "CHANGE".to_owned();
+
+    eprintln!("Determining sha256 for {}", &archive.url);
+    match prefetch_url(&archive.url, archive.is_zip) {
+        Some((sha, _)) => info.src_sha = sha,
+        None => eprintln!("Warning: Could not prefetch {}", &archive.url),
+    }
+
+    if archive.is_appimage {
+        if info.template == Template::Auto {
+            info.template = Template::appimage();
+        }
+        if matches!(info.template, Template::AppImage(_)) {
+            apply_appimage_contents(&archive.url, pname_given, info);
+        }
+    }
+}
+
+/// Metadata of one PyPI release (`/pypi/<project>/<version>/json`).
+/// Exits with an error when the release does not exist.
+fn fetch_pypi_release_info(
+    api_base: &str,
+    pypi_repo: &types::PypiRepo,
+    version: &str,
+) -> types::PypiReleaseResponse {
+    if let Err(e) = validate_version_components(version, "") {
+        error!(target: LOG_TARGET, "Invalid version: {}", e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+
+    let request_client = Client::new();
+    let request = request_client
+        .get(format!(
+            "{}/{}/{}/json",
+            api_base, pypi_repo.project, version
+        ))
+        .header("User-Agent", "reqwest")
+        .header("Content", "application/json");
+
+    let body = match get_json(request) {
+        Ok(body) => body,
+        Err(e) => {
+            error!(
+                target: LOG_TARGET,
+                "No release '{}' found for pypi.org/project/{}: {}", version, pypi_repo.project, e
+            );
+            eprintln!(
+                "Error: No release '{}' found for pypi.org/project/{}",
+                version, pypi_repo.project
+            );
+            exit(1);
+        }
+    };
+    let jd = &mut serde_json::Deserializer::from_str(&body);
+    match serde_path_to_error::deserialize(jd) {
+        Ok(s) => s,
+        Err(e) => {
+            error!(
+                target: LOG_TARGET,
+                "Unable to parse response from pypi.io to json: {:?}", e
+            );
+            exit(1)
+        }
+    }
+}
+
+/// Translate an SPDX license expression such as "MIT OR Apache-2.0", or the
+/// legacy "MIT/Apache-2.0", into space-separated nixpkgs license attributes.
+/// Returns "CHANGE" if any of the licenses is unknown.
+pub fn spdx_to_nixpkgs_license(expression: &str) -> String {
+    let mut licenses: Vec<&str> = Vec::new();
+    let mut tokens = expression
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '(' || c == ')')
+        .filter(|t| !t.is_empty());
+    while let Some(token) = tokens.next() {
+        match token {
+            "OR" | "AND" => {}
+            // Exceptions don't change the nixpkgs attribute
+            "WITH" => {
+                tokens.next();
+            }
+            id => match SPDX_TO_NIXPKGS_LICENSE.get(id) {
+                Some(license) if !licenses.contains(license) => licenses.push(license),
+                Some(_) => {}
+                None => return "CHANGE".to_owned(),
+            },
+        }
+    }
+
+    if licenses.is_empty() {
+        "CHANGE".to_owned()
+    } else {
+        licenses.join(" ")
+    }
+}
+
+/// Pick `wanted` among all published versions, or else the newest
+/// non-deprecated one.
+fn select_hackage_version(
+    versions: &types::HackagePreferredResponse,
+    wanted: Option<&str>,
+) -> Option<String> {
+    if let Some(wanted) = wanted {
+        let wanted = wanted.strip_prefix('v').unwrap_or(wanted);
+        return versions
+            .normal_version
+            .iter()
+            .chain(versions.deprecated_version.iter())
+            .find(|v| *v == wanted)
+            .cloned();
+    }
+
+    versions
+        .normal_version
+        .iter()
+        .max_by(|a, b| {
+            VersionCompare::compare(a, b)
+                .ok()
+                .and_then(|c| c.ord())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .cloned()
+}
+
+/// Fill pname, description, license and homepage from a .cabal file, and
+/// pick the Haskell template variant for it.
+fn apply_cabal_file(
+    repo: &types::HackageRepo,
+    cabal_path: &std::path::Path,
+    info: &mut types::ExpressionInfo,
+) {
+    use crate::deps::haskell;
+
+    if info.pname == "CHANGE" {
+        info.pname = haskell::extract_package_name(cabal_path).unwrap_or(repo.package.clone());
+    }
+
+    let contents = std::fs::read_to_string(cabal_path).unwrap_or_default();
+    info.description = haskell::extract_cabal_field(&contents, "synopsis")
+        .map(|s| s.trim_end_matches('.').to_owned())
+        .unwrap_or("CHANGE".to_owned());
+    info.homepage = haskell::extract_cabal_field(&contents, "homepage")
+        .unwrap_or_else(|| format!("{}/package/{}", HACKAGE_BASE, repo.package));
+    info.license = match haskell::extract_cabal_field(&contents, "license") {
+        Some(license) => match CABAL_TO_NIXPKGS_LICENSE.get(license.as_str()) {
+            Some(license) => license.to_string(),
+            None => spdx_to_nixpkgs_license(&license),
+        },
+        None => "CHANGE".to_owned(),
+    };
+
+    let is_executable = haskell::is_executable_package(cabal_path);
+    match &mut info.template {
+        Template::Haskell(config) => config.is_executable = is_executable,
+        Template::Auto => {
+            info.template = Template::Haskell(crate::templates::types::HaskellConfig {
+                build_system: crate::templates::types::HaskellBuildSystem::Cabal,
+                ghc_version: None,
+                is_executable,
+            })
+        }
+        _ => {}
+    }
+}
+
+/// Populate `info` from Hackage: the newest non-deprecated release (or the
+/// one selected with `--tag`), metadata from its .cabal file, and the hash of
+/// the unpacked sdist. That is the `sha256` `callHackageDirect` expects, and
+/// also what the `fetchzip` block of other templates takes.
+pub fn fill_hackage_info(
+    repo: &types::HackageRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("hackage.haskell.org/package/{}", &repo.package);
+    eprintln!("Determining latest release for {}", &location);
+    let versions = match fetch_hackage_versions(HACKAGE_BASE, repo) {
+        Ok(versions) => versions,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+
+    // --tag wins over a version linked in the URL
+    let wanted = options.tag.as_deref().or(repo.version.as_deref());
+    let version = match (select_hackage_version(&versions, wanted), wanted) {
+        (Some(version), _) => version,
+        (None, Some(wanted)) => {
+            error!(target: LOG_TARGET, "No release '{}' found for {}", wanted, &location);
+            eprintln!("Error: No release '{}' found for {}", wanted, &location);
+            exit(1);
+        }
+        (None, None) => {
+            eprintln!("No releases found for {}", &location);
+            return;
+        }
+    };
+    info.fetcher = types::Fetcher::hackage;
+    info.version = version.clone();
+
+    // deps::haskell works on files, so keep the .cabal file next to the
+    // other prefetch artifacts
+    let cabal_path = fetch_hackage_cabal_file(HACKAGE_BASE, repo, &version)
+        .ok()
+        .and_then(|contents| {
+            let path = tempfile_dir()?.join(format!("{}.cabal", &repo.package));
+            std::fs::write(&path, contents).ok()?;
+            Some(path)
+        });
+    match cabal_path {
+        Some(cabal_path) => apply_cabal_file(repo, &cabal_path, info),
+        None => {
+            eprintln!(
+                "Warning: Could not download the .cabal file for {}",
+                &location
+            );
+            if info.pname == "CHANGE" {
+                info.pname = repo.package.clone();
+            }
+        }
+    }
+
+    let sdist_url = format!(
+        "{}/package/{}-{}/{}-{}.tar.gz",
+        HACKAGE_BASE, &repo.package, &version, &repo.package, &version
+    );
+    prefetch_unpacked_sha(info, &sdist_url);
+}
+
+/// `mirror://cpan` form of a CPAN download URL, which nixpkgs uses so any
+/// CPAN mirror can serve the tarball.
+fn cpan_mirror_url(download_url: &str) -> String {
+    match download_url.find("/authors/id/") {
+        Some(start) => format!("mirror://cpan{}", &download_url[start..]),
+        None => download_url.to_owned(),
+    }
+}
+
+/// nixpkgs licenses for CPAN::Meta license strings, or "CHANGE" when any of
+/// them is unknown.
+fn cpan_licenses_to_nixpkgs(licenses: &[String]) -> String {
+    let mapped: Option<Vec<&str>> = licenses
+        .iter()
+        .map(|license| CPAN_TO_NIXPKGS_LICENSE.get(license.as_str()).copied())
+        .collect();
+    match mapped {
+        Some(mapped) if !mapped.is_empty() => mapped.join(" "),
+        _ => "CHANGE".to_owned(),
+    }
+}
+
+/// Whether a release is built with Module::Build (Build.PL) rather than
+/// ExtUtils::MakeMaker, judging by its configure requirements.
+fn cpan_uses_module_build(metadata: &serde_json::Value) -> bool {
+    let requires = &metadata["prereqs"]["configure"]["requires"];
+    requires.get("Module::Build").is_some() || requires.get("Module::Build::Tiny").is_some()
+}
+
+/// Fill metadata, source, build system and `perlPackages` dependencies from
+/// a MetaCPAN release.
+fn apply_cpan_release(release: &types::CpanRelease, info: &mut types::ExpressionInfo) {
+    use crate::deps::perl;
+
+    if info.pname == "CHANGE" {
+        info.pname = release.distribution.clone();
+    }
+    info.fetcher = types::Fetcher::cpan;
+    info.version = release.version.clone();
+    info.src_url = template_archive_url(&cpan_mirror_url(&release.download_url), &release.version);
+    info.src_sha = match &release.checksum_sha256 {
+        Some(sha) => to_sri(sha),
+        None => FAKE_SRI_HASH.to_owned(),
+    };
+    info.description = match &release.abstract_ {
+        Some(summary) => summary.trim().trim_end_matches('.').to_owned(),
+        None => "CHANGE".to_owned(),
+    };
+    info.homepage = release
+        .resources
+        .homepage
+        .clone()
+        .or_else(|| {
+            release
+                .resources
+                .repository
+                .as_ref()
+                .and_then(|repository| repository.web.clone())
+        })
+        .unwrap_or_else(|| format!("https://metacpan.org/dist/{}", release.distribution));
+    info.license = cpan_licenses_to_nixpkgs(&release.license);
+
+    if let Some(modules) = perl::meta_json_requires(&release.metadata) {
+        info.propagated_build_inputs
+            .extend(perl::modules_to_perl_packages(&modules));
+    }
+
+    let build_system = if cpan_uses_module_build(&release.metadata) {
+        crate::templates::types::PerlBuildSystem::Module
+    } else {
+        crate::templates::types::PerlBuildSystem::Package
+    };
+    match &mut info.template {
+        Template::Perl(config) => config.build_system = build_system,
+        Template::Auto => {
+            info.template = Template::Perl(crate::templates::types::PerlConfig { build_system })
+        }
+        _ => {}
+    }
+}
+
+/// Populate `info` from MetaCPAN: the latest release of a distribution (or
+/// of the one shipping a module), or the one selected with `--tag`.
+pub fn fill_cpan_info(
+    repo: &types::CpanRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let distribution = if repo.module {
+        match fetch_cpan_module(METACPAN_API_BASE, &repo.name) {
+            Ok(module) => module.distribution,
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not query metacpan.org/pod/{}: {}", &repo.name, e);
+                eprintln!(
+                    "Error: Could not query metacpan.org/pod/{}: {}",
+                    &repo.name, e
+                );
+                exit(1);
+            }
+        }
+    } else {
+        repo.name.clone()
+    };
+
+    let location = format!("metacpan.org/dist/{}", &distribution);
+    eprintln!("Determining latest release for {}", &location);
+    let latest = match fetch_cpan_release(METACPAN_API_BASE, &distribution, None) {
+        Ok(release) => release,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+
+    let wanted = options
+        .tag
+        .as_deref()
+        .map(|tag| tag.strip_prefix('v').unwrap_or(tag));
+    let release = match wanted {
+        Some(version) if version != latest.version => {
+            // Older releases are addressed by their uploader
+            match fetch_cpan_release(
+                METACPAN_API_BASE,
+                &distribution,
+                Some((&latest.author, version)),
+            ) {
+                Ok(release) => release,
+                Err(e) => {
+                    error!(target: LOG_TARGET, "No release '{}' found for {}: {}", version, &location, e);
+                    eprintln!("Error: No release '{}' found for {}", version, &location);
+                    exit(1);
+                }
+            }
+        }
+        _ => latest,
+    };
+
+    apply_cpan_release(&release, info);
+}
+
+/// nixpkgs licenses for an R `License` field, e.g. "GPL-2 | GPL-3" or
+/// "MIT + file LICENSE", or "CHANGE" when any alternative is unknown.
+fn r_license_to_nixpkgs(license: &str) -> String {
+    let mapped: Option<Vec<&str>> = license
+        .split('|')
+        .map(|alternative| {
+            let alternative = alternative.trim().trim_end_matches("+ file LICENSE").trim();
+            R_TO_NIXPKGS_LICENSE.get(alternative).copied()
+        })
+        .collect();
+    match mapped {
+        Some(mapped) if !mapped.is_empty() => mapped.join(" "),
+        _ => "CHANGE".to_owned(),
+    }
+}
+
+/// Fill pname, description, homepage, license, `rPackages` dependencies and
+/// system requirements from an R package's DESCRIPTION file.
+fn apply_r_description(
+    repo: &types::CranRepo,
+    description_path: &std::path::Path,
+    info: &mut types::ExpressionInfo,
+) {
+    use crate::deps::r;
+
+    if info.pname == "CHANGE" {
+        info.pname = r::parse_package_name(description_path).unwrap_or(repo.package.clone());
+    }
+
+    let contents = std::fs::read_to_string(description_path).unwrap_or_default();
+    info.description = r::parse_dcf_field(&contents, "Title")
+        .map(|s| s.trim_end_matches('.').to_owned())
+        .unwrap_or("CHANGE".to_owned());
+    info.homepage = r::parse_dcf_field(&contents, "URL")
+        .and_then(|urls| {
+            urls.split(|c: char| c == ',' || c.is_whitespace())
+                .find(|url| url.starts_with("http"))
+                .map(|url| url.to_owned())
+        })
+        .unwrap_or_else(|| match repo.bioconductor {
+            true => format!("{}/packages/{}", BIOCONDUCTOR_BASE, repo.package),
+            false => format!("{}/package={}", CRAN_BASE, repo.package),
+        });
+    info.license = match r::parse_dcf_field(&contents, "License") {
+        Some(license) => r_license_to_nixpkgs(&license),
+        None => "CHANGE".to_owned(),
+    };
+
+    let mut dependencies: Vec<String> = r::parse_r_dependencies(description_path)
+        .iter()
+        .map(|package| r::r_package_attr(package))
+        .collect();
+    dependencies.sort();
+    dependencies.dedup();
+    info.propagated_build_inputs.extend(dependencies);
+
+    if let Some(system_requirements) = r::parse_dcf_field(&contents, "SystemRequirements") {
+        let (build_inputs, native_build_inputs) = r::map_system_requirements(&system_requirements);
+        info.build_inputs.extend(build_inputs);
+        info.native_build_inputs.extend(native_build_inputs);
+    }
+
+    if info.template == Template::Auto {
+        info.template = Template::R;
+    }
+}
+
+/// Populate `info` from CRAN or Bioconductor: the current release (or the
+/// one selected with `--tag`), and metadata and dependencies from the
+/// DESCRIPTION file of its source tarball.
+pub fn fill_cran_info(
+    repo: &types::CranRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let (location, repo_base, bioc_release) = if repo.bioconductor {
+        let location = format!("bioconductor.org/packages/{}", &repo.package);
+        let release = match fetch_bioconductor_release(BIOCONDUCTOR_BASE) {
+            Ok(release) => release,
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+                eprintln!("Error: Could not query {}: {}", &location, e);
+                exit(1);
+            }
+        };
+        let repo_base = format!("{}/packages/{}/bioc", BIOCONDUCTOR_BASE, &release);
+        (location, repo_base, Some(release))
+    } else {
+        let location = format!("cran.r-project.org/package={}", &repo.package);
+        (location, CRAN_BASE.to_owned(), None)
+    };
+
+    let version = match options.tag.as_deref() {
+        Some(tag) => {
+            let version = tag.strip_prefix('v').unwrap_or(tag).to_owned();
+            if let Err(e) = validate_version_components(&version, "") {
+                error!(target: LOG_TARGET, "Invalid version: {}", e);
+                eprintln!("Error: {}", e);
+                exit(1);
+            }
+            version
+        }
+        None => {
+            eprintln!("Determining latest release for {}", &location);
+            match fetch_r_package_version(&repo_base, &repo.package) {
+                Ok(Some(version)) => version,
+                Ok(None) => {
+                    eprintln!("No releases found for {}", &location);
+                    return;
+                }
+                Err(e) => {
+                    error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+                    eprintln!("Error: Could not query {}: {}", &location, e);
+                    exit(1);
+                }
+            }
+        }
+    };
+
+    let tarball = format!("{}_{}.tar.gz", &repo.package, &version);
+    let mut urls = vec![format!("{}/src/contrib/{}", &repo_base, &tarball)];
+    if !repo.bioconductor {
+        // Superseded CRAN releases move to the archive
+        urls.push(format!(
+            "{}/src/contrib/Archive/{}/{}",
+            CRAN_BASE, &repo.package, &tarball
+        ));
+    }
+    eprintln!("Determining sha256 for {}", &tarball);
+    let (sha, tarball_path) = match urls.iter().find_map(|url| prefetch_url(url, false)) {
+        Some(prefetched) => prefetched,
+        None => {
+            error!(target: LOG_TARGET, "No release '{}' found for {}", &version, &location);
+            eprintln!("Error: No release '{}' found for {}", &version, &location);
+            exit(1);
+        }
+    };
+
+    info.version = version.clone();
+    info.src_sha = sha;
+    match &bioc_release {
+        Some(release) => {
+            info.fetcher = types::Fetcher::url;
+            info.src_url = format!(
+                "mirror://bioc/{}/bioc/src/contrib/{}_${{finalAttrs.version}}.tar.gz",
+                release, &repo.package
+            );
+        }
+        None => info.fetcher = types::Fetcher::cran,
+    }
+
+    // deps::r works on files, so unpack DESCRIPTION next to the other
+    // prefetch artifacts
+    let description_path = tempfile_dir().and_then(|dir| {
+        let member = format!("{}/DESCRIPTION", &repo.package);
+        let status = Command::new("tar")
+            .arg("-xzf")
+            .arg(&tarball_path)
+            .arg("-C")
+            .arg(&dir)
+            .arg(&member)
+            .status()
+            .ok()?;
+        Some(dir.join(member)).filter(|path| status.success() && path.is_file())
+    });
+    match description_path {
+        Some(description_path) => apply_r_description(repo, &description_path, info),
+        None => {
+            eprintln!(
+                "Warning: Could not read the DESCRIPTION file of {}",
+                &location
+            );
+            if info.pname == "CHANGE" {
+                info.pname = repo.package.clone();
+            }
+        }
+    }
+}
+
+/// Fill version, description, homepage, licenses, fetcher and dependencies
+/// from a Hex package and one of its releases.
+fn apply_hex_release(
+    package: &types::HexPackage,
+    release: &types::HexRelease,
+    info: &mut types::ExpressionInfo,
+) {
+    use crate::deps::elixir::map_mix_packages_to_nix;
+
+    if info.pname == "CHANGE" {
+        info.pname = package.name.clone();
+    }
+    info.version = release.version.clone();
+    info.fetcher = types::Fetcher::hex;
+    info.description = package
+        .meta
+        .description
+        .as_deref()
+        .map(|description| description.trim().trim_end_matches('.').to_owned())
+        .unwrap_or("CHANGE".to_owned());
+    info.homepage = package
+        .meta
+        .links
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("github"))
+        .map(|(_, link)| link.clone())
+        .or_else(|| package.html_url.clone())
+        .unwrap_or_else(|| format!("https://hex.pm/packages/{}", &package.name));
+    info.license = match package.meta.licenses.is_empty() {
+        true => "CHANGE".to_owned(),
+        false => spdx_to_nixpkgs_license(&package.meta.licenses.join(" OR ")),
+    };
+
+    let mut dependencies: Vec<&str> = release
+        .requirements
+        .iter()
+        .filter(|(_, requirement)| !requirement.optional)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    dependencies.sort_unstable();
+    info.propagated_build_inputs
+        .extend(dependencies.iter().map(|name| name.to_string()));
+
+    // The package itself may ship a NIF, as bcrypt_elixir does
+    let (build_inputs, native_build_inputs) =
+        map_mix_packages_to_nix(dependencies.into_iter().chain([package.name.as_str()]));
+    info.build_inputs.extend(build_inputs);
+    info.native_build_inputs.extend(native_build_inputs);
+
+    let is_mix = release.meta.build_tools.iter().any(|tool| tool == "mix");
+    let is_rebar3 = release.meta.build_tools.iter().any(|tool| tool == "rebar3");
+    if info.template == Template::Auto {
+        info.template = Template::elixir_library();
+    }
+    if let Some(config) = info.template.elixir_config_mut() {
+        if is_rebar3 && !is_mix {
+            config.variant = types::ElixirVariant::Rebar3;
+        }
+    }
+}
+
+/// Unpack the `contents.tar.gz` inside a Hex tarball into a temporary
+/// directory and return that directory.
+fn unpack_hex_tarball(tarball: &std::path::Path) -> Option<std::path::PathBuf> {
+    let dir = tempfile_dir()?.join(tarball.file_stem()?);
+    let contents = dir.join("contents");
+    std::fs::create_dir_all(&contents).ok()?;
+
+    let outer = Command::new("tar")
+        .arg("-xf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(&dir)
+        .arg("contents.tar.gz")
+        .status()
+        .ok()?;
+    let inner = Command::new("tar")
+        .arg("-xzf")
+        .arg(dir.join("contents.tar.gz"))
+        .arg("-C")
+        .arg(&contents)
+        .status()
+        .ok()?;
+    Some(contents).filter(|_| outer.success() && inner.success())
+}
+
+/// Refine the Elixir variant and native dependencies from the `mix.exs`
+/// and `mix.lock` of an unpacked Hex package.
+fn apply_hex_contents(contents: &std::path::Path, info: &mut types::ExpressionInfo) {
+    use crate::deps::elixir::{detect_elixir_variant, infer_native_dependencies};
+
+    let mix_exs = contents.join("mix.exs");
+    if let Some(config) = info.template.elixir_config_mut() {
+        if config.variant != types::ElixirVariant::Rebar3 && mix_exs.is_file() {
+            config.variant = detect_elixir_variant(&mix_exs);
+        }
+    }
+
+    let mix_lock = contents.join("mix.lock");
+    if mix_lock.is_file() {
+        let (build_inputs, native_build_inputs) = infer_native_dependencies(&mix_lock);
+        for input in build_inputs {
+            if !info.build_inputs.contains(&input) {
+                info.build_inputs.push(input);
+            }
+        }
+        for input in native_build_inputs {
+            if !info.native_build_inputs.contains(&input) {
+                info.native_build_inputs.push(input);
+            }
+        }
+    }
+}
+
+/// Populate `info` from Hex: the latest release (or the one selected with
+/// `--tag` or the URL), its metadata and dependencies, and the variant
+/// and native dependencies from the `mix.exs`/`mix.lock` of its tarball.
+pub fn fill_hex_info(
+    repo: &types::HexRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("hex.pm/packages/{}", &repo.name);
+    let package = match fetch_hex_package(HEX_API_BASE, &repo.name) {
+        Ok(package) => package,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+
+    let version = match options.tag.as_deref().or(repo.version.as_deref()) {
+        Some(tag) => {
+            let version = tag.strip_prefix('v').unwrap_or(tag).to_owned();
+            if let Err(e) = validate_version_components(&version, "") {
+                error!(target: LOG_TARGET, "Invalid version: {}", e);
+                eprintln!("Error: {}", e);
+                exit(1);
+            }
+            version
+        }
+        None => {
+            let latest = match options.include_prereleases {
+                true => package.latest_version.clone(),
+                false => package.latest_stable_version.clone(),
+            };
+            match latest.or_else(|| package.latest_version.clone()) {
+                Some(version) => version,
+                None => {
+                    eprintln!("No releases found for {}", &location);
+                    return;
+                }
+            }
+        }
+    };
+
+    let release = match fetch_hex_release(HEX_API_BASE, &repo.name, &version) {
+        Ok(release) => release,
+        Err(e) => {
+            error!(target: LOG_TARGET, "No release '{}' found for {}: {}", &version, &location, e);
+            eprintln!("Error: No release '{}' found for {}", &version, &location);
+            exit(1);
+        }
+    };
+    apply_hex_release(&package, &release, info);
+
+    let tarball_url = format!("{}/tarballs/{}-{}.tar", HEX_REPO_BASE, &repo.name, &version);
+    eprintln!("Determining sha256 for {}-{}.tar", &repo.name, &version);
+    let tarball = match prefetch_url(&tarball_url, false) {
+        Some((sha, tarball)) => {
+            info.src_sha = sha;
+            tarball
+        }
+        None => {
+            eprintln!("Warning: Could not prefetch {}", &tarball_url);
+            return;
+        }
+    };
+    match unpack_hex_tarball(&tarball) {
+        Some(contents) => apply_hex_contents(&contents, info),
+        None => eprintln!("Warning: Could not unpack the Hex tarball of {}", &location),
+    }
+}
+
+/// Fetch a Packagist package with all of its versions.
+fn fetch_packagist_package(
+    base: &str,
+    repo: &types::PackagistRepo,
+) -> Result<types::PackagistPackage> {
+    let request = Client::new()
+        .get(format!(
+            "{}/packages/{}/{}.json",
+            base, repo.vendor, repo.name
+        ))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/json");
+
+    let response: types::PackagistResponse = serde_json::from_str(&get_json(request)?)?;
+    Ok(response.package)
+}
+
+/// The Packagist release named `tag` (with or without a leading "v"), or
+/// else the latest tagged release. Branches ("dev-main", "2.x-dev") are
+/// never selected.
+fn select_packagist_version<'a>(
+    package: &'a types::PackagistPackage,
+    tag: Option<&str>,
+    include_prereleases: bool,
+) -> Option<&'a types::PackagistVersion> {
+    if let Some(tag) = tag {
+        let bare = tag.strip_prefix('v').unwrap_or(tag);
+        return [tag.to_owned(), bare.to_owned(), format!("v{}", bare)]
+            .iter()
+            .find_map(|candidate| package.versions.get(candidate));
+    }
+
+    package
+        .versions
+        .values()
+        .filter(|v| !v.version.starts_with("dev-") && !v.version.ends_with("-dev"))
+        .filter(|v| {
+            include_prereleases
+                || STABLE_RELEASE_REGEX.is_match(v.version.strip_prefix('v').unwrap_or(&v.version))
+        })
+        .max_by(|a, b| {
+            VersionCompare::compare(
+                a.version.strip_prefix('v').unwrap_or(&a.version),
+                b.version.strip_prefix('v').unwrap_or(&b.version),
+            )
+            .ok()
+            .and_then(|c| c.ord())
+            .unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+/// Fill description, license and homepage from a Packagist release.
+fn apply_packagist_version(
+    package: &types::PackagistPackage,
+    version: &types::PackagistVersion,
+    info: &mut types::ExpressionInfo,
+) {
+    info.description = version
+        .description
+        .as_deref()
+        .or(package.description.as_deref())
+        .map(|description| description.trim().trim_end_matches('.').to_owned())
+        .unwrap_or("CHANGE".to_owned());
+    info.license = match version.license.is_empty() {
+        true => "CHANGE".to_owned(),
+        false => spdx_to_nixpkgs_license(&version.license.join(" OR ")),
+    };
+    if let Some(homepage) = &version.homepage {
+        info.homepage = homepage.clone();
+    }
+    if info.template == Template::Auto {
+        info.template = Template::php();
+    }
+}
+
+/// Select the PHP version and extensions and infer native dependencies
+/// from the `composer.json` of the package source.
+fn apply_composer_json(source: &std::path::Path, info: &mut types::ExpressionInfo) {
+    use crate::deps::php::{detect_php_extensions, detect_php_version, infer_native_dependencies};
+
+    let composer_json = source.join("composer.json");
+    if !composer_json.is_file() {
+        eprintln!("Warning: composer.json not found in the package source");
+        return;
+    }
+    if !source.join("composer.lock").is_file() {
+        eprintln!("Warning: composer.lock not found; buildComposerProject2 needs one");
+        eprintln!("         Run 'composer update' and set composerLock = ./composer.lock;");
+    }
+
+    if let Some(config) = info.template.php_config_mut() {
+        config.extensions = detect_php_extensions(&composer_json);
+        config.version = detect_php_version(&composer_json);
+    }
+    let (build_inputs, native_build_inputs) = infer_native_dependencies(&composer_json);
+    for input in build_inputs {
+        if !info.build_inputs.contains(&input) {
+            info.build_inputs.push(input);
+        }
+    }
+    for input in native_build_inputs {
+        if !info.native_build_inputs.contains(&input) {
+            info.native_build_inputs.push(input);
+        }
+    }
+}
+
+/// Fetch release `tag` of a registry package from its source repository:
+/// through the forge's API for GitHub, GitLab and Gitea, with `fetchgit`
+/// otherwise.
+fn fill_tagged_source(
+    url: &str,
+    tag: &str,
+    source_name: &str,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let trimmed_url = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    match validate_and_parse_url(trimmed_url, url, &options.forges) {
+        // Forges that can select a tag through their API
+        Ok(Github(_) | Gitlab(_) | Gitea(_)) => {
+            let source_options = FetchOptions {
+                tag: Some(tag.to_owned()),
+                forges: options.forges.clone(),
+                ..Default::default()
+            };
+            read_meta_from_url(url.trim_end_matches(".git"), info, &source_options);
+        }
+        _ => {
+            info.fetcher = types::Fetcher::git;
+            info.src_url = url.to_owned();
+            TagScheme::default().apply(info, tag, source_name);
+            eprintln!("Determining sha256 for {}", &info.pname);
+            let rev = format!("refs/tags/{}", tag);
+            if let Some(hash) = prefetch_git_hash(url, &rev, &[]) {
+                info.src_sha = hash;
+            }
+        }
+    }
+}
+
+/// Populate `info` from Packagist: resolve the latest stable release (or
+/// the one selected with `--tag`) and fetch it from its source repository,
+/// then read `composer.json` from the source.
+pub fn fill_packagist_info(
+    repo: &types::PackagistRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("packagist.org/packages/{}/{}", &repo.vendor, &repo.name);
+    let package = match fetch_packagist_package(PACKAGIST_BASE, repo) {
+        Ok(package) => package,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+    let version = match select_packagist_version(
+        &package,
+        options.tag.as_deref(),
+        options.include_prereleases,
+    ) {
+        Some(version) => version,
+        None => {
+            match &options.tag {
+                Some(tag) => eprintln!("Error: No release '{}' found for {}", tag, &location),
+                None => eprintln!("Error: No releases found for {}", &location),
+            }
+            exit(1);
+        }
+    };
+    let source = match &version.source {
+        Some(source) if source.kind == "git" => source,
+        _ => {
+            error!(target: LOG_TARGET, "No git repository found for {}", &location);
+            eprintln!(
+                "Error: Could not determine the git repository of {}",
+                &location
+            );
+            exit(1);
+        }
+    };
+    if let Err(e) = validate_url_component(&source.url, "repository URL") {
+        error!(target: LOG_TARGET, "Invalid source of {}: {}", &location, e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+    eprintln!("{} is developed in {}", &package.name, &source.url);
+
+    if info.pname == "CHANGE" {
+        info.pname = repo.name.clone();
+    }
+    fill_tagged_source(&source.url, &version.version, "Packagist", info, options);
+    apply_packagist_version(&package, version, info);
+
+    match crate::source::materialise_source(info) {
+        Some(source) => apply_composer_json(&source, info),
+        None => eprintln!("Warning: Could not fetch the source of {}", &location),
+    }
+}
+
+/// Directory of a Maven artifact below a repository root, e.g.
+/// "org/apache/commons/commons-text".
+fn maven_artifact_path(repo: &types::MavenRepo) -> String {
+    format!("{}/{}", repo.group_id.replace('.', "/"), &repo.artifact_id)
+}
+
+/// Versions of a Maven artifact, as listed in its `maven-metadata.xml`.
+fn fetch_maven_versions(base: &str, repo: &types::MavenRepo) -> Result<Vec<String>> {
+    let request = Client::new()
+        .get(format!(
+            "{}/{}/maven-metadata.xml",
+            base,
+            maven_artifact_path(repo)
+        ))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/xml");
+
+    let metadata = get_json(request)?;
+    Ok(MAVEN_METADATA_VERSION_REGEX
+        .captures_iter(&metadata)
+        .map(|c| c[1].to_owned())
+        .collect())
+}
+
+/// The version named `tag` (with or without a leading "v"), or else the
+/// newest version. Milestones, release candidates and snapshots are only
+/// selected with `include_prereleases`.
+fn select_maven_version(
+    versions: &[String],
+    tag: Option<&str>,
+    include_prereleases: bool,
+) -> Option<String> {
+    if let Some(tag) = tag {
+        let bare = tag.strip_prefix('v').unwrap_or(tag);
+        return versions
+            .iter()
+            .find(|version| *version == tag || *version == bare)
+            .cloned();
+    }
+
+    versions
+        .iter()
+        .filter(|version| include_prereleases || !MAVEN_PRERELEASE_REGEX.is_match(version))
+        .max_by(|a, b| {
+            VersionCompare::compare(a.as_str(), b.as_str())
+                .ok()
+                .and_then(|c| c.ord())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .cloned()
+}
+
+/// Download the POM of one version of a Maven artifact into `dir`.
+fn fetch_maven_pom(
+    base: &str,
+    repo: &types::MavenRepo,
+    version: &str,
+    dir: &std::path::Path,
+) -> Result<std::path::PathBuf> {
+    let request = Client::new()
+        .get(format!(
+            "{}/{}/{}/{}-{}.pom",
+            base,
+            maven_artifact_path(repo),
+            version,
+            &repo.artifact_id,
+            version
+        ))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/xml");
+
+    let path = dir.join(format!("{}-{}.pom", &repo.artifact_id, version));
+    std::fs::write(&path, get_json(request)?)?;
+    Ok(path)
+}
+
+/// Clonable https URL of a POM's `<scm>` connection, e.g.
+/// "git@github.com:apache/commons-text.git" becomes
+/// "https://github.com/apache/commons-text".
+fn normalize_scm_url(url: &str) -> String {
+    let url = match url.strip_prefix("git@") {
+        Some(rest) => format!("https://{}", rest.replacen(':', "/", 1)),
+        None => url
+            .replacen("git://", "https://", 1)
+            .replacen("ssh://git@", "https://", 1),
+    };
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_owned()
+}
+
+/// Fill description, homepage and license from a POM, and select the JDK
+/// and native dependencies of the Maven template.
+fn apply_pom(
+    pom: &std::path::Path,
+    metadata: &crate::deps::maven::PomMetadata,
+    info: &mut types::ExpressionInfo,
+) {
+    use crate::deps::maven::{infer_jdk_version, infer_native_dependencies, pom_license_to_spdx};
+
+    if let Some(description) = &metadata.description {
+        info.description = description.trim().trim_end_matches('.').to_owned();
+    }
+    if let Some(homepage) = &metadata.url {
+        info.homepage = homepage.clone();
+    }
+    let licenses: Vec<&str> = metadata
+        .licenses
+        .iter()
+        .filter_map(|name| pom_license_to_spdx(name))
+        .collect();
+    if !licenses.is_empty() {
+        info.license = spdx_to_nixpkgs_license(&licenses.join(" OR "));
+    }
+
+    if info.template == Template::Auto {
+        info.template = Template::maven();
+    }
+    if let Some(config) = info.template.maven_config_mut() {
+        let jdk = infer_jdk_version(pom);
+        eprintln!("Detected JDK version: {}", &jdk);
+        config.jdk_version = Some(jdk.trim_start_matches("jdk").to_owned());
+    }
+    let (build_inputs, native_build_inputs) = infer_native_dependencies(pom);
+    info.build_inputs = build_inputs;
+    info.native_build_inputs = native_build_inputs;
+}
+
+/// Populate `info` from Maven Central: resolve the latest stable version
+/// (or the requested one), read its POM and fetch the matching tag of the
+/// repository named in the POM's `<scm>`.
+pub fn fill_maven_info(
+    repo: &types::MavenRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("{}:{}", &repo.group_id, &repo.artifact_id);
+    let versions = match fetch_maven_versions(MAVEN_CENTRAL_BASE, repo) {
+        Ok(versions) => versions,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+    let tag = options.tag.as_deref().or(repo.version.as_deref());
+    let version = match select_maven_version(&versions, tag, options.include_prereleases) {
+        Some(version) => version,
+        None => {
+            match tag {
+                Some(tag) => eprintln!("Error: No release '{}' found for {}", tag, &location),
+                None => eprintln!("Error: No releases found for {}", &location),
+            }
+            exit(1);
+        }
+    };
+
+    let pom = match tempfile_dir()
+        .ok_or_else(|| anyhow!("unable to create temporary directory"))
+        .and_then(|dir| fetch_maven_pom(MAVEN_CENTRAL_BASE, repo, &version, &dir))
+    {
+        Ok(pom) => pom,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not fetch the POM of {}: {}", &location, e);
+            eprintln!(
+                "Error: Could not fetch the POM of {}:{}: {}",
+                &location, &version, e
+            );
+            exit(1);
+        }
+    };
+    let metadata = crate::deps::maven::parse_pom_metadata(&pom).unwrap_or_default();
+    let scm_url = match &metadata.scm_url {
+        Some(url) => normalize_scm_url(url),
+        None => {
+            error!(target: LOG_TARGET, "No <scm> found in the POM of {}", &location);
+            eprintln!(
+                "Error: Could not determine the source repository of {}; its POM has no <scm>",
+                &location
+            );
+            exit(1);
+        }
+    };
+    if let Err(e) = validate_url_component(&scm_url, "repository URL") {
+        error!(target: LOG_TARGET, "Invalid source of {}: {}", &location, e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+    eprintln!("{} is developed in {}", &location, &scm_url);
+
+    // Forge fetchers name the repository after pname, so it's only set
+    // from the artifactId once the source has been resolved
+    let tag = metadata.scm_tag.clone().unwrap_or_else(|| version.clone());
+    fill_tagged_source(&scm_url, &tag, "Maven Central", info, options);
+    if info.pname == "CHANGE" {
+        info.pname = repo.artifact_id.clone();
+    }
+    apply_pom(&pom, &metadata, info);
+}
+
+/// Versions of a rock uploaded by a LuaRocks user, from the user's manifest.
+fn fetch_luarocks_versions(base: &str, repo: &types::LuaRocksRepo) -> Result<Vec<String>> {
+    let request = Client::new()
+        .get(format!("{}/manifests/{}/manifest", base, &repo.user))
+        .header("User-Agent", "nix-template");
+
+    let manifest = get_json(request)?;
+    Ok(crate::deps::lua::parse_manifest_versions(
+        &manifest, &repo.name,
+    ))
+}
+
+/// Split a rockspec version into its upstream version and revision, e.g.
+/// "1.1.0-1" into ("1.1.0", 1).
+fn split_rockspec_version(version: &str) -> (&str, u32) {
+    match version.rsplit_once('-') {
+        Some((upstream, revision)) => (upstream, revision.parse().unwrap_or(0)),
+        None => (version, 0),
+    }
+}
+
+/// The rockspec version named `tag`, either in full ("1.1.0-1") or as its
+/// upstream version ("1.1.0" or "v1.1.0"), or else the newest release.
+/// "scm" and "dev" rockspecs track a branch and are never selected; the
+/// highest revision of a version wins.
+fn select_luarocks_version(
+    versions: &[String],
+    tag: Option<&str>,
+    include_prereleases: bool,
+) -> Option<String> {
+    let compare = |a: &&String, b: &&String| {
+        let (a_version, a_revision) = split_rockspec_version(a);
+        let (b_version, b_revision) = split_rockspec_version(b);
+        VersionCompare::compare(a_version, b_version)
+            .ok()
+            .and_then(|c| c.ord())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a_revision.cmp(&b_revision))
+    };
+
+    if let Some(tag) = tag {
+        let bare = tag.strip_prefix('v').unwrap_or(tag);
+        return versions
+            .iter()
+            .filter(|version| *version == tag || split_rockspec_version(version).0 == bare)
+            .max_by(compare)
+            .cloned();
+    }
+
+    versions
+        .iter()
+        .filter(|version| {
+            let (upstream, _) = split_rockspec_version(version);
+            upstream != "scm"
+                && upstream != "dev"
+                && (include_prereleases || STABLE_RELEASE_REGEX.is_match(upstream))
+        })
+        .max_by(compare)
+        .cloned()
+}
+
+/// Download the rockspec of one version of a rock into `dir`.
+fn fetch_rockspec(
+    base: &str,
+    repo: &types::LuaRocksRepo,
+    version: &str,
+    dir: &std::path::Path,
+) -> Result<std::path::PathBuf> {
+    let filename = format!("{}-{}.rockspec", &repo.name, version);
+    let request = Client::new()
+        .get(format!("{}/manifests/{}/{}", base, &repo.user, &filename))
+        .header("User-Agent", "nix-template");
+
+    let path = dir.join(&filename);
+    std::fs::write(&path, get_json(request)?)?;
+    Ok(path)
+}
+
+/// Fill description, homepage, license and dependencies from a rockspec,
+/// and select the variant and Lua version of the Lua template.
+fn apply_rockspec(
+    rockspec_path: &std::path::Path,
+    rockspec: &crate::deps::lua::Rockspec,
+    info: &mut types::ExpressionInfo,
+) {
+    use crate::deps::lua::{
+        detect_lua_variant, infer_lua_version, map_rockspec_dependencies_to_nix,
+    };
+
+    if let Some(summary) = &rockspec.summary {
+        info.description = summary.trim().trim_end_matches('.').to_owned();
+    }
+    if let Some(homepage) = &rockspec.homepage {
+        info.homepage = homepage.clone();
+    }
+    if let Some(license) = &rockspec.license {
+        info.license = spdx_to_nixpkgs_license(license);
+    }
+
+    if info.template == Template::Auto {
+        info.template = Template::lua();
+    }
+    if let Some(config) = info.template.lua_config_mut() {
+        config.variant = detect_lua_variant(rockspec_path);
+        config.version = infer_lua_version(rockspec_path);
+    }
+    let (propagated_build_inputs, build_inputs) = map_rockspec_dependencies_to_nix(rockspec);
+    info.propagated_build_inputs.extend(propagated_build_inputs);
+    info.build_inputs.extend(build_inputs);
+}
+
+/// Populate `info` from LuaRocks: resolve the latest release (or the
+/// requested one), read its rockspec and fetch the source it names.
+pub fn fill_luarocks_info(
+    repo: &types::LuaRocksRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("luarocks.org/modules/{}/{}", &repo.user, &repo.name);
+    let versions = match fetch_luarocks_versions(LUAROCKS_BASE, repo) {
+        Ok(versions) => versions,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+    let tag = options.tag.as_deref().or(repo.version.as_deref());
+    let version = match select_luarocks_version(&versions, tag, options.include_prereleases) {
+        Some(version) => version,
+        None => {
+            match tag {
+                Some(tag) => eprintln!("Error: No release '{}' found for {}", tag, &location),
+                None => eprintln!("Error: No releases found for {}", &location),
+            }
+            exit(1);
+        }
+    };
+
+    let rockspec_path = match tempfile_dir()
+        .ok_or_else(|| anyhow!("unable to create temporary directory"))
+        .and_then(|dir| fetch_rockspec(LUAROCKS_BASE, repo, &version, &dir))
+    {
+        Ok(path) => path,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not fetch the rockspec of {}: {}", &location, e);
+            eprintln!(
+                "Error: Could not fetch the rockspec of {} {}: {}",
+                &location, &version, e
+            );
+            exit(1);
+        }
+    };
+    let rockspec = crate::deps::lua::parse_rockspec(&rockspec_path).unwrap_or_default();
+    let upstream_version = split_rockspec_version(&version).0.to_owned();
+
+    match rockspec.source_url.as_deref() {
+        Some(url) if url.starts_with("git") => {
+            let url = normalize_scm_url(url.trim_start_matches("git+"));
+            if let Err(e) = validate_url_component(&url, "repository URL") {
+                error!(target: LOG_TARGET, "Invalid source of {}: {}", &location, e);
+                eprintln!("Error: {}", e);
+                exit(1);
+            }
+            eprintln!("{} is developed in {}", &repo.name, &url);
+            let tag = rockspec.source_tag.clone().unwrap_or(upstream_version);
+            fill_tagged_source(&url, &tag, "LuaRocks", info, options);
+        }
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            info.version = upstream_version;
+            info.src_url = template_archive_url(url, &info.version);
+            eprintln!("Determining sha256 for {}-{}", &repo.name, &info.version);
+            // fetchzip hashes the unpacked tree, fetchurl the file itself
+            let unpack = url.ends_with(".zip");
+            info.fetcher = if unpack {
+                types::Fetcher::zip
+            } else {
+                types::Fetcher::url
+            };
+            match prefetch_url(url, unpack) {
+                Some((sha, _)) => info.src_sha = sha,
+                None => eprintln!("Warning: Could not prefetch {}", url),
+            }
+        }
+        Some(url) => {
+            eprintln!("Warning: Unsupported source of {}: {}", &location, url);
+            info.version = upstream_version;
+        }
+        None => {
+            eprintln!("Warning: The rockspec of {} has no source url", &location);
+            info.version = upstream_version;
+        }
+    }
+    // Forge fetchers name the repository after pname, so it's only set
+    // from the rock once the source has been resolved
+    if info.pname == "CHANGE" {
+        info.pname = repo.name.clone();
+    }
+    apply_rockspec(&rockspec_path, &rockspec, info);
+}
+
+/// Fetch a pub.dev package with all of its versions.
+fn fetch_pub_package(api_base: &str, name: &str) -> Result<types::PubPackage> {
+    let request = Client::new()
+        .get(format!("{}/packages/{}", api_base, name))
+        .header("User-Agent", "nix-template")
+        .header("Accept", "application/vnd.pub.v2+json");
+
+    Ok(serde_json::from_str(&get_json(request)?)?)
+}
+
+/// Fill pname, version, description, homepage and fetcher from one
+/// version of a pub.dev package.
+fn apply_pub_version(
+    package: &types::PubPackage,
+    version: &types::PubVersion,
+    info: &mut types::ExpressionInfo,
+) {
+    if info.pname == "CHANGE" {
+        info.pname = package.name.clone();
+    }
+    info.version = version.version.clone();
+    info.fetcher = types::Fetcher::pubdev;
+    info.description = version
+        .pubspec
+        .description
+        .as_deref()
+        .map(|description| description.trim().trim_end_matches('.').to_owned())
+        .unwrap_or("CHANGE".to_owned());
+    info.homepage = version
+        .pubspec
+        .homepage
+        .clone()
+        .or_else(|| version.pubspec.repository.clone())
+        .unwrap_or_else(|| format!("https://pub.dev/packages/{}", &package.name));
+    if info.template == Template::Auto {
+        info.template = Template::dart();
+    }
+}
+
+/// Select Flutter and read the executables and SDK constraint from the
+/// `pubspec.yaml` of an unpacked pub.dev archive.
+fn apply_pubspec(pubspec: &std::path::Path, info: &mut types::ExpressionInfo) {
+    use crate::deps::dart::{extract_dart_version, is_flutter_project, parse_dart_executables};
+
+    let flutter = is_flutter_project(pubspec);
+    let executables = parse_dart_executables(pubspec);
+    if let [executable] = executables.as_slice() {
+        if info.main_program.is_empty() {
+            info.main_program = executable.clone();
+        }
+    }
+    if let Some(config) = info.template.dart_config_mut() {
+        config.flutter = flutter;
+        config.executables = executables;
+        config.dart_version = extract_dart_version(pubspec);
+    }
+}
+
+/// Populate `info` from pub.dev: the latest version (or the one selected
+/// with `--tag` or the URL), and whether it is a Flutter application and
+/// its executables from the `pubspec.yaml` of its archive.
+pub fn fill_pub_info(
+    repo: &types::PubRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("pub.dev/packages/{}", &repo.name);
+    let package = match fetch_pub_package(PUB_API_BASE, &repo.name) {
+        Ok(package) => package,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+
+    let version = match options.tag.as_deref().or(repo.version.as_deref()) {
+        Some(tag) => {
+            let wanted = tag.strip_prefix('v').unwrap_or(tag);
+            match package.versions.iter().find(|v| v.version == wanted) {
+                Some(version) => version,
+                None => {
+                    error!(target: LOG_TARGET, "No release '{}' found for {}", tag, &location);
+                    eprintln!("Error: No release '{}' found for {}", tag, &location);
+                    exit(1);
+                }
+            }
+        }
+        None if options.include_prereleases => package
+            .versions
+            .iter()
+            .rfind(|v| !v.retracted)
+            .unwrap_or(&package.latest),
+        None => &package.latest,
+    };
+    apply_pub_version(&package, version, info);
+
+    eprintln!("Determining sha256 for {}-{}", &repo.name, &version.version);
+    match prefetch_url(&version.archive_url, true) {
+        Some((sha, contents)) => {
+            info.src_sha = sha;
+            apply_pubspec(&contents.join("pubspec.yaml"), info);
+        }
+        None => eprintln!("Warning: Could not prefetch {}", &version.archive_url),
+    }
+}
+
+/// Where opam packages are read from: a local opam-repository checkout, or
+/// opam.ocaml.org's repository through the GitHub API and raw files.
+enum OpamRepository<'a> {
+    Local(&'a std::path::Path),
+    Remote {
+        api_base: &'a str,
+        raw_base: &'a str,
+    },
+}
+
+/// Versions of an opam package, from the `<name>.<version>` directories
+/// below `packages/<name>`.
+fn list_opam_versions(repository: &OpamRepository, name: &str) -> Result<Vec<String>> {
+    let directories: Vec<String> = match repository {
+        OpamRepository::Local(root) => std::fs::read_dir(root.join("packages").join(name))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_owned))
+            .collect(),
+        OpamRepository::Remote { api_base, .. } => {
+            let request = Client::new()
+                .get(format!(
+                    "{}/repos/{}/contents/packages/{}",
+                    api_base, OPAM_REPOSITORY, name
+                ))
+                .header("User-Agent", "nix-template")
+                .header("Accept", "application/json");
+            let entries: types::GhContentsResponse = serde_json::from_str(&get_json(request)?)?;
+            entries
+                .into_iter()
+                .filter(|entry| entry.kind == "dir")
+                .map(|entry| entry.name)
+                .collect()
+        }
+    };
+
+    let prefix = format!("{}.", name);
+    Ok(directories
+        .iter()
+        .filter_map(|directory| directory.strip_prefix(&prefix))
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Contents of `packages/<name>/<name>.<version>/opam`.
+fn read_opam_file(repository: &OpamRepository, name: &str, version: &str) -> Result<String> {
+    let path = format!("packages/{}/{}.{}/opam", name, name, version);
+    match repository {
+        OpamRepository::Local(root) => Ok(std::fs::read_to_string(root.join(path))?),
+        OpamRepository::Remote { raw_base, .. } => {
+            let request = Client::new()
+                .get(format!("{}/{}", raw_base, path))
+                .header("User-Agent", "nix-template");
+            Ok(get_json(request)?)
+        }
+    }
+}
+
+/// Fill pname, description, homepage, license, source and dependencies
+/// from an opam file. Returns the sha256 checksum of the source, if the
+/// opam file lists one.
+fn apply_opam_file(
+    repo: &types::OpamRepo,
+    version: &str,
+    opam_path: &std::path::Path,
+    info: &mut types::ExpressionInfo,
+) -> Option<String> {
+    use crate::deps::ocaml::{
+        extract_package_name_from_opam, map_opam_dependencies_to_nix, parse_opam_file,
+    };
+
+    let opam = parse_opam_file(opam_path)?;
+    if info.pname == "CHANGE" {
+        info.pname = extract_package_name_from_opam(opam_path).unwrap_or(repo.name.clone());
+    }
+    // Jane Street packages are versioned "v0.17.0"
+    info.version = version.strip_prefix('v').unwrap_or(version).to_owned();
+    info.description = opam
+        .synopsis
+        .as_deref()
+        .map(|synopsis| synopsis.trim().trim_end_matches('.').to_owned())
+        .unwrap_or("CHANGE".to_owned());
+    info.homepage = opam
+        .homepage
+        .clone()
+        .unwrap_or_else(|| format!("https://opam.ocaml.org/packages/{}", &repo.name));
+    info.license = match opam.licenses.is_empty() {
+        true => "CHANGE".to_owned(),
+        false => spdx_to_nixpkgs_license(&opam.licenses.join(" AND ")),
+    };
+
+    match opam.src.as_deref() {
+        Some(src) if src.starts_with("http://") || src.starts_with("https://") => {
+            info.fetcher = types::Fetcher::url;
+            info.src_url = template_archive_url(src, &info.version);
+        }
+        Some(src) => eprintln!(
+            "Warning: Only archive sources are supported for opam packages, not {}",
+            src
+        ),
+        None => eprintln!(
+            "Warning: The opam file of {} has no url section",
+            &repo.name
+        ),
+    }
+
+    let (propagated_build_inputs, build_inputs, native_build_inputs) =
+        map_opam_dependencies_to_nix(&opam);
+    info.propagated_build_inputs.extend(propagated_build_inputs);
+    info.build_inputs.extend(build_inputs);
+    info.native_build_inputs.extend(native_build_inputs);
+
+    if info.template == Template::Auto {
+        info.template = Template::ocaml();
+    }
+    if let Template::Ocaml(config) = &mut info.template {
+        config.package_name = Some(repo.name.clone());
+    }
+
+    opam.checksums
+        .iter()
+        .find_map(|checksum| checksum.strip_prefix("sha256="))
+        .map(str::to_owned)
+}
+
+/// Populate `info` from the opam file of the latest release of an opam
+/// package (or the one selected with `--tag` or the URL), read from
+/// opam.ocaml.org's repository or a local checkout of one.
+pub fn fill_opam_info(
+    repo: &types::OpamRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("opam.ocaml.org/packages/{}", &repo.name);
+    let repository = match &repo.repository {
+        Some(root) => OpamRepository::Local(std::path::Path::new(root)),
+        None => OpamRepository::Remote {
+            api_base: GITHUB_API_BASE,
+            raw_base: OPAM_REPOSITORY_RAW_BASE,
+        },
+    };
+    let versions = match list_opam_versions(&repository, &repo.name) {
+        Ok(versions) => versions,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+
+    let version = match options.tag.as_deref().or(repo.version.as_deref()) {
+        Some(tag) => {
+            let bare = tag.strip_prefix('v').unwrap_or(tag);
+            let tagged = format!("v{}", bare);
+            match [tag, bare, tagged.as_str()]
+                .iter()
+                .find(|candidate| versions.iter().any(|v| v == **candidate))
+            {
+                Some(version) => version.to_string(),
+                None => {
+                    error!(target: LOG_TARGET, "No release '{}' found for {}", tag, &location);
+                    eprintln!("Error: No release '{}' found for {}", tag, &location);
+                    exit(1);
+                }
+            }
+        }
+        None => {
+            let latest = versions
+                .iter()
+                .filter(|v| {
+                    options.include_prereleases
+                        || STABLE_RELEASE_REGEX.is_match(v.strip_prefix('v').unwrap_or(v))
+                })
+                .max_by(|a, b| {
+                    VersionCompare::compare(a, b)
+                        .ok()
+                        .and_then(|c| c.ord())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            match latest {
+                Some(version) => version.clone(),
+                None => {
+                    eprintln!("No releases found for {}", &location);
+                    return;
+                }
+            }
+        }
+    };
+
+    let contents = match read_opam_file(&repository, &repo.name, &version) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not read the opam file of {}: {}", &location, e);
+            eprintln!("Error: Could not read the opam file of {}", &location);
+            exit(1);
+        }
+    };
+    let opam_path = match tempfile_dir().map(|dir| dir.join("opam")) {
+        Some(dir) if std::fs::create_dir_all(&dir).is_ok() => {
+            dir.join(format!("{}.opam", &repo.name))
+        }
+        _ => {
+            eprintln!("Error: Could not create a temporary directory");
+            exit(1);
+        }
+    };
+    if let Err(e) = std::fs::write(&opam_path, contents) {
+        eprintln!("Error: Could not write {}: {}", opam_path.display(), e);
+        exit(1);
+    }
+
+    let sha256 = apply_opam_file(repo, &version, &opam_path, info);
+    if info.src_url == "CHANGE" {
+        return;
+    }
+    match sha256 {
+        Some(sha256) => info.src_sha = to_sri(&sha256),
+        None => {
+            let src_url = info.src_url.replace("${finalAttrs.version}", &info.version);
+            eprintln!("Determining sha256 for {}", &src_url);
+            match prefetch_url(&src_url, false) {
+                Some((sha, _)) => info.src_sha = sha,
+                None => eprintln!("Warning: Could not prefetch {}", &src_url),
+            }
+        }
+    }
+}
+
+/// First usable entry of a `GOPROXY` list, or proxy.golang.org.
+///
+/// Entries are separated by "," or "|"; "direct" doesn't name a proxy and is
+/// skipped. `file://` directories are supported. Returns None when "off" is
+/// reached before any proxy, as Go then refuses to resolve modules.
+pub fn go_proxy_base(goproxy: Option<&str>) -> Option<String> {
+    let entry = goproxy
+        .into_iter()
+        .flat_map(|list| list.split([',', '|']))
+        .map(|entry| entry.trim().trim_end_matches('/'))
+        .find(|entry| !entry.is_empty() && *entry != "direct")
+        .unwrap_or(GO_PROXY_BASE);
+    match entry {
+        "off" => None,
+        proxy => Some(proxy.to_owned()),
+    }
+}
+
+/// Case-encode a module path or version for proxy requests: every
+/// uppercase letter becomes "!" followed by its lowercase.
+fn go_escape_path(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Read `path` from a GOPROXY, which may be a `file://` directory.
+fn go_proxy_get(proxy: &str, path: &str) -> Result<String> {
+    match proxy.strip_prefix("file://") {
+        Some(dir) => Ok(std::fs::read_to_string(
+            std::path::Path::new(dir).join(path),
+        )?),
+        None => {
+            let request = Client::new()
+                .get(format!("{}/{}", proxy, path))
+                .header("User-Agent", "nix-template");
+            Ok(get_json(request)?)
+        }
+    }
+}
+
+/// Resolve the module providing `path` and the info of its latest release,
+/// or of `version`. Packages below a module root are resolved by trying
+/// successively shorter prefixes of `path`.
+fn fetch_go_module_info(
+    proxy: &str,
+    path: &str,
+    version: Option<&str>,
+) -> Result<(String, types::GoProxyInfo)> {
+    let mut candidate = path;
+    let mut first_error = None;
+    loop {
+        let endpoint = match version {
+            Some(version) => format!(
+                "{}/@v/{}.info",
+                go_escape_path(candidate),
+                go_escape_path(version)
+            ),
+            None => format!("{}/@latest", go_escape_path(candidate)),
+        };
+        match go_proxy_get(proxy, &endpoint) {
+            Ok(body) => return Ok((candidate.to_owned(), serde_json::from_str(&body)?)),
+            Err(e) => {
+                debug!(target: LOG_TARGET, "{} is not a module: {}", candidate, e);
+                first_error.get_or_insert(e);
+            }
+        }
+        match candidate.rsplit_once('/') {
+            Some((parent, _)) => candidate = parent,
+            None => return Err(first_error.unwrap()),
+        }
+    }
+}
+
+/// Repository a Go module is developed in.
+#[derive(Debug, PartialEq)]
+struct GoModuleOrigin {
+    /// Clone URL
+    url: String,
+    /// Directory of the module inside the repository; empty at the root
+    subdir: String,
+}
+
+/// Whether a path element is a major version suffix such as "v2".
+fn is_go_major_suffix(element: &str) -> bool {
+    element
+        .strip_prefix('v')
+        .and_then(|major| major.parse::<u32>().ok())
+        .is_some_and(|major| major >= 2)
+}
+
+/// Directory of a module given the path elements below its repository
+/// root. A trailing major version suffix usually names a branch rather
+/// than a directory, so it is dropped.
+fn go_module_subdir(elements: &[&str]) -> String {
+    let elements = match elements.split_last() {
+        Some((last, rest)) if is_go_major_suffix(last) => rest,
+        _ => elements,
+    };
+    elements.join("/")
+}
+
+/// Repository of `module` on hosts whose import paths map onto
+/// repositories without a `go-get` lookup.
+fn go_known_host_origin(module: &str) -> Option<GoModuleOrigin> {
+    let elements: Vec<&str> = module.split('/').collect();
+    let (url, rest) = match elements.as_slice() {
+        ["golang.org", "x", repo, rest @ ..] => {
+            (format!("https://go.googlesource.com/{}", repo), rest)
+        }
+        [host @ ("github.com" | "gitlab.com" | "bitbucket.org" | "codeberg.org" | "git.sr.ht"), owner, repo, rest @ ..] => {
+            (format!("https://{}/{}/{}", host, owner, repo), rest)
+        }
+        _ => return None,
+    };
+    Some(GoModuleOrigin {
+        url,
+        subdir: go_module_subdir(rest),
+    })
+}
+
+/// Repository of `module` announced by the `go-import` meta tags of an
+/// import path's `?go-get=1` page. Only git repositories are supported.
+fn parse_go_import_meta(html: &str, module: &str) -> Option<GoModuleOrigin> {
+    GO_IMPORT_META_REGEX
+        .captures_iter(html)
+        .find_map(|captures| {
+            let mut fields = captures.get(1)?.as_str().split_whitespace();
+            let (prefix, vcs, url) = (fields.next()?, fields.next()?, fields.next()?);
+            if vcs != "git" {
+                return None;
+            }
+            let rest = match module.strip_prefix(prefix)? {
+                "" => "",
+                rest => rest.strip_prefix('/')?,
+            };
+            let elements: Vec<&str> = rest.split('/').filter(|e| !e.is_empty()).collect();
+            Some(GoModuleOrigin {
+                url: url.to_owned(),
+                subdir: go_module_subdir(&elements),
+            })
+        })
+}
+
+/// Repository of `module`: the origin reported by the proxy, a well-known
+/// host, or the `go-import` meta tag of a vanity import path.
+fn go_module_origin(module: &str, module_info: &types::GoProxyInfo) -> Option<GoModuleOrigin> {
+    if let Some(origin) = &module_info.origin {
+        if let (Some("git"), Some(url)) = (origin.vcs.as_deref(), &origin.url) {
+            return Some(GoModuleOrigin {
+                url: url.clone(),
+                subdir: origin.subdir.clone().unwrap_or_default(),
+            });
+        }
+    }
+    if let Some(origin) = go_known_host_origin(module) {
+        return Some(origin);
+    }
+
+    let request = Client::new()
+        .get(format!("https://{}?go-get=1", module))
+        .header("User-Agent", "nix-template");
+    match get_json(request) {
+        Ok(html) => parse_go_import_meta(&html, module),
+        Err(e) => {
+            debug!(target: LOG_TARGET, "Could not fetch go-import meta of {}: {}", module, e);
+            None
+        }
+    }
+}
+
+/// Date ("YYYY-MM-DD") and abbreviated commit of a pseudo-version.
+fn go_pseudo_version_commit(version: &str) -> Option<(String, String)> {
+    let captures = GO_PSEUDO_VERSION_REGEX.captures(version)?;
+    Some((
+        format!("{}-{}-{}", &captures[1], &captures[2], &captures[3]),
+        captures[4].to_owned(),
+    ))
+}
+
+/// Package name for an import path: its last element, skipping a major
+/// version suffix ("github.com/foo/bar/v2" -> "bar").
+fn go_package_pname(path: &str) -> String {
+    let mut elements: Vec<&str> = path.split('/').collect();
+    if elements.len() > 1 && elements.last().is_some_and(|e| is_go_major_suffix(e)) {
+        elements.pop();
+    }
+    elements.last().unwrap_or(&path).to_string()
+}
+
+/// Fetch a Go module developed in a repository without a forge API with
+/// `fetchgit`, pinned to `tag`, or to `commit` for pseudo-versions.
+fn fill_go_git_origin(
+    origin: &GoModuleOrigin,
+    tag: &str,
+    commit: Option<(String, String)>,
+    info: &mut types::ExpressionInfo,
+) {
+    info.fetcher = types::Fetcher::git;
+    info.src_url = origin.url.clone();
+
+    let rev = match commit {
+        Some((date, rev)) => {
+            info.version = format!("0-unstable-{}", date);
+            info.tag_prefix = String::new();
+            info.src_rev = rev.clone();
+            rev
+        }
+        None => {
+            TagScheme::default().apply(info, tag, "the Go module proxy");
+            format!("refs/tags/{}", tag)
+        }
+    };
+
+    eprintln!("Determining sha256 for {}", &info.pname);
+    if let Some(hash) = prefetch_git_hash(&origin.url, &rev, &["--fetch-submodules"]) {
+        info.src_sha = hash;
+    }
+}
+
+/// Populate `info` from a Go module: resolve its latest release (or the
+/// one selected with `--tag` or `@<version>`) through the GOPROXY, then
+/// fetch the source from the repository the module is developed in.
+pub fn fill_go_module_info(
+    repo: &types::GoModuleRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("pkg.go.dev/{}", &repo.path);
+    let proxy = match go_proxy_base(options.go_proxy.as_deref()) {
+        Some(proxy) => proxy,
+        None => {
+            error!(target: LOG_TARGET, "Go module resolution is disabled by GOPROXY=off");
+            eprintln!("Error: Go module resolution is disabled by GOPROXY=off");
+            exit(1);
+        }
+    };
+    let wanted = options
+        .tag
+        .as_deref()
+        .or(repo.version.as_deref())
+        .map(|version| match version.starts_with('v') {
+            true => version.to_owned(),
+            false => format!("v{}", version),
+        });
+
+    eprintln!("Resolving {} through {}", &location, &proxy);
+    let (module, module_info) = match fetch_go_module_info(&proxy, &repo.path, wanted.as_deref()) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+    if let Err(e) = validate_version_components(&module_info.version, "") {
+        error!(target: LOG_TARGET, "Invalid version from {}: {}", &proxy, e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+    if module != repo.path {
+        eprintln!(
+            "Note: {} is a package of module {}; set subPackages = [ \"{}\" ]",
+            &repo.path,
+            &module,
+            &repo.path[module.len() + 1..]
+        );
+    }
+
+    let origin = match go_module_origin(&module, &module_info) {
+        Some(origin) => origin,
+        None => {
+            error!(target: LOG_TARGET, "No git repository found for {}", &module);
+            eprintln!(
+                "Error: Could not determine the git repository of {}",
+                &module
+            );
+            exit(1);
+        }
+    };
+    if let Err(e) = validate_url_component(&origin.url, "repository URL") {
+        error!(target: LOG_TARGET, "Invalid origin of {}: {}", &module, e);
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+    eprintln!("Module {} is developed in {}", &module, &origin.url);
+
+    if info.pname == "CHANGE" {
+        info.pname = go_package_pname(&repo.path);
+    }
+    info.homepage = format!("https://pkg.go.dev/{}", &module);
+
+    // "+incompatible" marks v2+ releases of modules without a /vN suffix;
+    // the tag doesn't carry it
+    let version = module_info.version.trim_end_matches("+incompatible");
+    let tag = match origin.subdir.as_str() {
+        "" => version.to_owned(),
+        subdir => format!("{}/{}", subdir, version),
+    };
+    let commit = go_pseudo_version_commit(version).map(|(date, short)| {
+        let full = module_info.origin.as_ref().and_then(|o| o.hash.clone());
+        (date, full.unwrap_or(short))
+    });
+
+    let trimmed_url = origin
+        .url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    match validate_and_parse_url(trimmed_url, &origin.url, &options.forges) {
+        // Forges that can select a tag or commit through their API
+        Ok(Github(_) | Gitlab(_) | Gitea(_)) => {
+            let origin_options = FetchOptions {
+                tag: commit.is_none().then(|| tag.clone()),
+                rev: commit.map(|(_, rev)| rev),
+                forges: options.forges.clone(),
+                ..Default::default()
+            };
+            read_meta_from_url(origin.url.trim_end_matches(".git"), info, &origin_options);
+        }
+        _ => fill_go_git_origin(&origin, &tag, commit, info),
+    }
+
+    info.go_module_path = module.clone();
+    info.go_mod_root = origin.subdir;
+    if info.template == Template::Auto {
+        info.template = Template::go();
+    }
+    if let Some(config) = info.template.go_config_mut() {
+        config.module_path = Some(module);
+    }
+}
+
+/// Executable to record as `meta.mainProgram`: the one named like the gem,
+/// or else the only one.
+fn gem_main_program(executables: &[String], pname: &str) -> Option<String> {
+    if executables.iter().any(|exe| exe == pname) {
+        Some(pname.to_owned())
+    } else if executables.len() == 1 {
+        Some(executables[0].clone())
+    } else {
+        None
+    }
+}
+
+/// Fill version, description, homepage, license and the source hash from a
+/// RubyGems release.
+fn apply_rubygem(gem: &types::RubygemResponse, info: &mut types::ExpressionInfo) {
+    if info.pname == "CHANGE" {
+        info.pname = gem.name.clone();
+    }
+    info.fetcher = types::Fetcher::rubygems;
+    info.version = gem.version.clone();
+    info.description = match &gem.info {
+        Some(summary) => summary
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end_matches('.')
+            .to_owned(),
+        None => "CHANGE".to_owned(),
+    };
+    info.homepage = gem
+        .homepage_uri
+        .clone()
+        .or_else(|| gem.source_code_uri.clone())
+        .filter(|uri| !uri.is_empty())
+        .unwrap_or_else(|| format!("{}/gems/{}", RUBYGEMS_BASE, gem.name));
+    info.license = match gem.licenses.as_deref() {
+        Some(licenses) if !licenses.is_empty() => spdx_to_nixpkgs_license(&licenses.join(" OR ")),
+        _ => "CHANGE".to_owned(),
+    };
+    // The API reports the sha256 of the .gem file, which is what fetchurl
+    // downloads
+    info.src_sha = match &gem.sha {
+        Some(sha) => to_sri(sha),
+        None => FAKE_SRI_HASH.to_owned(),
+    };
+}
+
+/// Populate `info` from RubyGems: the latest release (or the one selected
+/// with `--tag`), its executables, and the build inputs of native gems among
+/// its runtime dependencies, which stand in for a Gemfile.lock.
+pub fn fill_rubygems_info(
+    repo: &types::RubygemsRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("rubygems.org/gems/{}", &repo.name);
+    let wanted = options
+        .tag
+        .as_deref()
+        .map(|tag| tag.strip_prefix('v').unwrap_or(tag));
+    match wanted {
+        Some(version) => eprintln!("Looking up release {} for {}", version, &location),
+        None => eprintln!("Determining latest release for {}", &location),
+    }
+    let gem = match fetch_rubygem(RUBYGEMS_BASE, repo, wanted) {
+        Ok(gem) => gem,
+        Err(e) => {
+            match wanted {
+                Some(version) => {
+                    error!(target: LOG_TARGET, "No release '{}' found for {}: {}", version, &location, e);
+                    eprintln!("Error: No release '{}' found for {}", version, &location);
+                }
+                None => {
+                    error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+                    eprintln!("Error: Could not query {}: {}", &location, e);
+                }
+            }
+            exit(1);
+        }
+    };
+
+    apply_rubygem(&gem, info);
+
+    match fetch_gem_executables(RUBYGEMS_BASE, repo, &gem.version) {
+        Ok(executables) => {
+            if let Some(program) = gem_main_program(&executables, &info.pname) {
+                info.main_program = program;
+            }
+            info.executables = executables;
+        }
+        Err(e) => eprintln!(
+            "Warning: Could not read the executables of {} {}: {}",
+            &repo.name, &gem.version, e
+        ),
+    }
+
+    let mut gems: Vec<String> = gem
+        .dependencies
+        .runtime
+        .iter()
+        .map(|dependency| dependency.name.clone())
+        .collect();
+    gems.push(gem.name.clone());
+    let (build_inputs, native_build_inputs) = crate::deps::ruby::map_gems_to_nix(&gems);
+    info.build_inputs.extend(build_inputs);
+    info.native_build_inputs.extend(native_build_inputs);
+}
+
+/// Pick `wanted` (with or without a leading "v"), or else the newest
+/// non-yanked version, skipping prereleases unless `include_prereleases`.
+fn select_crate_version<'a>(
+    versions: &'a [types::CrateVersion],
+    wanted: Option<&str>,
+    include_prereleases: bool,
+) -> Option<&'a types::CrateVersion> {
+    if let Some(wanted) = wanted {
+        let wanted = wanted.strip_prefix('v').unwrap_or(wanted);
+        return versions.iter().find(|v| v.num == wanted);
+    }
+
+    versions
+        .iter()
+        .filter(|v| !v.yanked && (include_prereleases || STABLE_RELEASE_REGEX.is_match(&v.num)))
+        .max_by(|a, b| {
+            VersionCompare::compare(&a.num, &b.num)
+                .ok()
+                .and_then(|c| c.ord())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+/// Fill version, description, homepage and license of one crate release.
+fn apply_crate_release(
+    krate: &types::CrateInfo,
+    version: &types::CrateVersion,
+    info: &mut types::ExpressionInfo,
+) {
+    if info.pname == "CHANGE" {
+        info.pname = krate.name.clone();
+    }
+    info.fetcher = types::Fetcher::crates;
+    info.version = version.num.clone();
+    info.description = match &krate.description {
+        Some(description) => description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end_matches('.')
+            .to_owned(),
+        None => "CHANGE".to_owned(),
+    };
+    info.homepage = krate
+        .homepage
+        .clone()
+        .or_else(|| krate.repository.clone())
+        .unwrap_or_else(|| format!("https://crates.io/crates/{}", krate.name));
+    info.license = match &version.license {
+        Some(license) => spdx_to_nixpkgs_license(license),
+        None => "CHANGE".to_owned(),
+    };
+}
+
+/// GitHub repository behind a registry's repository link, which may be a
+/// URL ("git+https://github.com/o/r.git", "git@github.com:o/r.git") or an
+/// npm-style shorthand ("github:o/r", "o/r").
+fn github_repo_from_link(link: &str) -> Option<types::GithubRepo> {
+    let link = link.trim().trim_start_matches("git+");
+    let path = if let Some(shorthand) = link.strip_prefix("github:") {
+        shorthand
+    } else if let Some(scp) = link.strip_prefix("git@github.com:") {
+        scp
+    } else if let Some(url) = ["https://", "http://", "git://", "ssh://git@"]
+        .iter()
+        .find_map(|scheme| link.strip_prefix(scheme))
+    {
+        url.strip_prefix("github.com/")?
+    } else if !link.contains(':') && link.matches('/').count() == 1 {
+        // npm treats a bare "owner/repo" as a GitHub shorthand
+        link
+    } else {
+        return None;
+    };
+
+    let mut parts = path.trim_end_matches('/').split('/');
+    let owner = parts.next().filter(|o| !o.is_empty())?;
+    let repo = parts
+        .next()
+        .map(|r| r.trim_end_matches(".git"))
+        .filter(|r| !r.is_empty())?;
+    Some(types::GithubRepo {
+        owner: owner.to_owned(),
+        repo: repo.to_owned(),
+    })
+}
+
+/// Switch to `fetchFromGitHub` when a registry package's `repository` is a
+/// GitHub repository of the same name with a tag for the release. Returns
+/// whether the source was switched; otherwise `fallback` is kept.
+fn follow_github_repository(
+    repository: Option<&str>,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+    fallback: &str,
+) -> bool {
+    let repository = match repository {
+        Some(repository) => repository,
+        None => {
+            eprintln!(
+                "Warning: {} has no repository link; using {}",
+                &info.pname, fallback
+            );
+            return false;
+        }
+    };
+    let github_repo = match github_repo_from_link(repository) {
+        Some(github_repo) => github_repo,
+        None => {
+            eprintln!(
+                "Warning: Only GitHub repository links can be followed, not {}; using {}",
+                repository, fallback
+            );
+            return false;
+        }
+    };
+    // fetchFromGitHub is rendered with `repo = pname`
+    if github_repo.repo != info.pname {
+        eprintln!(
+            "Warning: Repository name {} differs from the package name {}; using {}",
+            &github_repo.repo, &info.pname, fallback
+        );
+        return false;
+    }
+
+    let location = format!("github.com/{}/{}", &github_repo.owner, &github_repo.repo);
+    eprintln!("Looking up tag {} for {}", &info.version, &location);
+    let tag = match fetch_github_tags(GITHUB_API_BASE, &github_repo) {
+        Ok(tags) => find_tag(&tags, &info.version, &options.tag_scheme),
+        Err(e) => {
+            eprintln!("Warning: Could not list tags of {}: {}", &location, e);
+            None
+        }
+    };
+    let tag = match tag {
+        Some(tag) => tag,
+        None => {
+            eprintln!(
+                "Warning: No tag for {} in {}; using {}",
+                &info.version, &location, fallback
+            );
+            return false;
+        }
+    };
+
+    info.fetcher = types::Fetcher::github;
+    info.owner = github_repo.owner.clone();
+    apply_github_tag(&github_repo, info, &tag, &options.tag_scheme);
+    true
+}
+
+/// Executable to record as `meta.mainProgram`: the package itself for a
+/// single `bin`, otherwise the entry named like the package or the only one.
+fn npm_main_program(bin: &types::NpmBin, pname: &str) -> Option<String> {
+    match bin {
+        types::NpmBin::Single(_) => Some(pname.to_owned()),
+        types::NpmBin::Map(bins) if bins.contains_key(pname) => Some(pname.to_owned()),
+        types::NpmBin::Map(bins) if bins.len() == 1 => bins.keys().next().cloned(),
+        types::NpmBin::Map(_) => None,
+    }
+}
+
+/// Fill version, description, homepage, license and `mainProgram` from an
+/// npm version manifest.
+fn apply_npm_manifest(manifest: &types::NpmVersionResponse, info: &mut types::ExpressionInfo) {
+    if info.pname == "CHANGE" {
+        // nixpkgs drops the scope, e.g. "@angular/cli" -> "cli"
+        info.pname = match manifest.name.rsplit_once('/') {
+            Some((_, name)) => name.to_owned(),
+            None => manifest.name.clone(),
+        };
+    }
+    info.version = manifest.version.clone();
+    info.description = match &manifest.description {
+        Some(description) => description.trim().trim_end_matches('.').to_owned(),
+        None => "CHANGE".to_owned(),
+    };
+    info.homepage = manifest
+        .homepage
+        .clone()
+        .unwrap_or_else(|| format!("https://www.npmjs.com/package/{}", manifest.name));
+    info.license = match &manifest.license {
+        Some(types::NpmLicense::Spdx(license)) => spdx_to_nixpkgs_license(license),
+        Some(types::NpmLicense::Object { kind }) => spdx_to_nixpkgs_license(kind),
+        None => "CHANGE".to_owned(),
+    };
+    if let Some(program) = manifest
+        .bin
+        .as_ref()
+        .and_then(|bin| npm_main_program(bin, &info.pname))
+    {
+        info.main_program = program;
+    }
+}
+
+/// Populate `info` from the npm registry. The registry tarball is used as
+/// the source when it ships a `package-lock.json`, which `buildNpmPackage`
+/// needs; otherwise the release tag of the linked GitHub repository is.
+pub fn fill_npm_info(
+    repo: &types::NpmRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("npmjs.com/package/{}", &repo.name);
+    let wanted = options
+        .tag
+        .as_deref()
+        .map(|tag| tag.strip_prefix('v').unwrap_or(tag));
+    match wanted {
+        Some(version) => eprintln!("Looking up release {} for {}", version, &location),
+        None => eprintln!("Determining latest release for {}", &location),
+    }
+    let manifest = match fetch_npm_manifest(NPM_REGISTRY_BASE, repo, wanted.unwrap_or("latest")) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            match wanted {
+                Some(version) => {
+                    error!(target: LOG_TARGET, "No release '{}' found for {}: {}", version, &location, e);
+                    eprintln!("Error: No release '{}' found for {}", version, &location);
+                }
+                None => {
+                    error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+                    eprintln!("Error: Could not query {}: {}", &location, e);
+                }
+            }
+            exit(1);
+        }
+    };
+
+    apply_npm_manifest(&manifest, info);
+
+    info.fetcher = types::Fetcher::zip;
+    info.src_url = template_archive_url(&manifest.dist.tarball, &manifest.version);
+    prefetch_unpacked_sha(info, &manifest.dist.tarball);
+
+    // npm publish leaves the lockfile out unless the package opts in
+    let ships_lockfile = crate::source::materialise_source(info)
+        .is_some_and(|path| path.join("package-lock.json").is_file());
+    if ships_lockfile {
+        return;
+    }
+
+    let repository = manifest.repository.as_ref().map(|r| match r {
+        types::NpmRepository::Shorthand(link) => link.as_str(),
+        types::NpmRepository::Object { url } => url.as_str(),
+    });
+    if !follow_github_repository(repository, info, options, "the registry tarball") {
+        eprintln!(
+            "Warning: The {} tarball has no package-lock.json; buildNpmPackage needs one, e.g. copied in postPatch",
+            &repo.name
+        );
+    }
+}
+
+/// Populate `info` from crates.io: the newest non-yanked release (or the one
+/// selected with `--tag`), its metadata, and the hash of the unpacked crate
+/// as fetched by `fetchCrate`.
+pub fn fill_crates_info(
+    repo: &types::CratesRepo,
+    info: &mut types::ExpressionInfo,
+    options: &FetchOptions,
+) {
+    let location = format!("crates.io/crates/{}", &repo.name);
+    eprintln!("Determining latest release for {}", &location);
+    let response = match fetch_crate_info(CRATES_IO_API_BASE, repo) {
+        Ok(response) => response,
+        Err(e) => {
+            error!(target: LOG_TARGET, "Could not query {}: {}", &location, e);
+            eprintln!("Error: Could not query {}: {}", &location, e);
+            exit(1);
+        }
+    };
+
+    let version = select_crate_version(
+        &response.versions,
+        options.tag.as_deref(),
+        options.include_prereleases,
+    );
+    let version = match (version, &options.tag) {
+        (Some(version), _) => version,
+        (None, Some(wanted)) => {
+            error!(target: LOG_TARGET, "No release '{}' found for {}", wanted, &location);
+            eprintln!("Error: No release '{}' found for {}", wanted, &location);
+            exit(1);
+        }
+        (None, None) => {
+            eprintln!("No releases found for {}", &location);
+            return;
+        }
+    };
+    if version.yanked {
+        eprintln!("Warning: {} {} has been yanked", &repo.name, &version.num);
+    }
+
+    apply_crate_release(&response.krate, version, info);
+
+    if options.follow_repository
+        && follow_github_repository(
+            response.krate.repository.as_deref(),
+            info,
+            options,
+            "fetchCrate",
+        )
+    {
+        return;
+    }
+
+    let download_url = format!(
+        "{}/crates/{}/{}/download",
+        CRATES_IO_API_BASE, &repo.name, &version.num
+    );
+    prefetch_unpacked_sha(info, &download_url);
+}
+
+/// Populate `info` from PyPI. `version` selects an exact release; the
+/// newest stable release is used otherwise.
+pub fn fill_pypi_info(
+    pypi_repo: &types::PypiRepo,
+    info: &mut types::ExpressionInfo,
+    version: Option<&str>,
+) {
+    if info.pname == "CHANGE" {
+        info.pname = pypi_repo.project.clone();
+    }
+
+    if let Some(version) = version {
+        fill_pypi_release(PYPI_API_BASE, pypi_repo, info, version);
+        return;
+    }
+
+    eprintln!("Determining latest release for {}", &pypi_repo.project);
+    let pypi_response = fetch_pypi_project_info(pypi_repo);
+
+    let mut releases: Vec<String> = pypi_response
+        .releases
+        .keys()
+        .map(|a| a.to_owned())
+        .filter(|v| STABLE_RELEASE_REGEX.is_match(v))
+        .collect();
+
+    if !releases.is_empty() {
+        releases.sort_by(|a, b| VersionCompare::compare(&b, &a).unwrap().ord().unwrap());
+
+        let latest_version = releases.first().unwrap();
+        let dists = pypi_response.releases.get(latest_version).unwrap();
+        apply_pypi_release(pypi_repo, info, latest_version, pypi_response.info, dists);
+    } else {
+        eprintln!(
+            "No releases found for pypi.org/project/{}",
+            &pypi_repo.project
+        );
+    }
+}
+
+/// Fill `info` from the release selected with `--tag`; a leading `v` is
+/// dropped since PyPI versions never carry one.
+fn fill_pypi_release(
+    api_base: &str,
+    pypi_repo: &types::PypiRepo,
+    info: &mut types::ExpressionInfo,
+    version: &str,
+) {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    eprintln!(
+        "Looking up release {} for pypi.org/project/{}",
+        version, &pypi_repo.project
+    );
+    let release = fetch_pypi_release_info(api_base, pypi_repo, version);
+    apply_pypi_release(pypi_repo, info, version, release.info, &release.urls);
+}
+
+/// Fill version, metadata, dependencies and the sdist hash of one release.
+fn apply_pypi_release(
+    pypi_repo: &types::PypiRepo,
+    info: &mut types::ExpressionInfo,
+    version: &str,
+    pypi_info: types::Info,
+    dists: &[types::Url],
+) {
+    let release = dists.iter().find(|a| a.packagetype == "sdist");
+
+    info.version = version.to_owned();
+    info.homepage = pypi_info.home_page.unwrap_or("CHANGE".to_string());
+    info.description = pypi_info.summary.trim_end_matches(".").to_string();
+
+    info.license = PYPI_TO_NIXPKGS_LICENSE
+        .get(&*pypi_info.license)
+        .unwrap_or(&"CHANGE")
+        .to_string();
+
+    // Grab dependencies, filter out extras, normalize names
+    debug!(
+        "Python dependencies before normalization: {:?}",
+        &pypi_info.requires_dist
+    );
+    let mut dependencies: Vec<String> = pypi_info
+        .requires_dist
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| !s.contains("extra =="))
+        .map(|s| {
+            s.split(" ")
+                .next()
+                .unwrap()
+                // Remove version information
+                .chars()
+                .take_while(|&ch| ch != '!' && ch != '<' && ch != '>' && ch != '=')
+                .collect::<String>()
+                // Normalize name to adhere to Nixpkgs conventions
+                .replace(".", "-")
+                .replace("_", "-")
+        })
+        .collect();
+    dependencies.sort();
+    debug!("dependencies after normalization: {:?}", &dependencies);
+    info.propagated_build_inputs = dependencies;
+
+    match release {
+        Some(dist) => {
+            info.fetcher = types::Fetcher::pypi;
+            info.src_sha = to_sri(&dist.digests.sha256);
+        }
+        None => {
+            eprintln!(
+                "Unable to find sdist for {}. Using default template",
+                &pypi_repo.project
+            );
+        }
+    }
+}
+
+/// Prefetch the `cargoHash` (for `rust` template) or `vendorHash` (for `go`
+/// and `php` templates) by performing a build with `lib.fakeHash` and parsing the
+/// resulting hash mismatch from `nix-build`'s stderr.
+///
+/// The expression at `info` must already have a known `src_sha`. The function
+/// renders the package expression to a temporary file, invokes `nix-build`
+/// against it via `callPackage`, and extracts the SRI hash from the
+/// "got:" line that nix prints on hash mismatch.
+///
+/// Returns `None` if the build did not produce a hash mismatch (e.g. nix is
+/// not installed, the source failed to fetch, the hash placeholder was
+/// already correct, etc.). Logs progress to stderr.
+pub fn prefetch_dependency_hash(info: &types::ExpressionInfo) -> Option<String> {
+    use std::io::Write;
+
+    // Only Rust, Go, npm, pnpm, Composer and Maven packages need dependency
+    // hash prefetching.
+    match info.template {
+        Template::Rust(_)
+        | Template::Go(_)
+        | Template::Node(_)
+        | Template::Php(_)
+        | Template::Maven(_) => (),
+        _ => return None,
+    }
+
+    // Skip when the hash is already determined:
+    // - Rust with cargoLock.lockFile doesn't need cargoHash.
+    // - Go with a committed vendor/ directory uses vendorHash = null.
+    if info.use_cargo_lock_file {
+        return None;
+    }
+    if info.template.is_go() && info.vendor_hash == types::VENDOR_HASH_NULL {
+        return None;
+    }
+
+    // We can't prefetch without a real source hash to feed the builder.
+    if info.src_sha.is_empty()
+        || info
+            .src_sha
+            .starts_with("0000000000000000000000000000000000000000000000000000")
+    {
+        eprintln!("Skipping hash prefetch: src_sha is not yet known");
+        return None;
+    }
+
+    // Render the expression with a fake dependency hash so that nix
+    // is forced to fetch the dependencies and emit a "got:" line.
+    let probe_info = types::ExpressionInfo {
+        pname: info.pname.clone(),
+        version: info.version.clone(),
+        license: info.license.clone(),
+        maintainer: info.maintainer.clone(),
+        fetcher: info.fetcher.clone(),
+        template: info.template.clone(),
+        path_to_write: std::path::PathBuf::new(),
+        top_level_path: std::path::PathBuf::new(),
+        // Documentation links and meta would clutter / break the probe expression
+        // (e.g. `licenses.CHANGE` does not exist).
+        include_documentation_links: false,
+        include_meta: false,
+        tag_prefix: info.tag_prefix.clone(),
+        tag_version_separator: info.tag_version_separator.clone(),
+        src_rev: info.src_rev.clone(),
+        fetch_submodules: info.fetch_submodules,
+        fetch_lfs: info.fetch_lfs,
+        main_program: info.main_program.clone(),
+        executables: info.executables.clone(),
+        owner: info.owner.clone(),
+        src_sha: info.src_sha.clone(),
+        description: info.description.clone(),
+        homepage: info.homepage.clone(),
+        propagated_build_inputs: info.propagated_build_inputs.clone(),
+        cargo_hash: FAKE_SRI_HASH.to_owned(),
+        vendor_hash: FAKE_SRI_HASH.to_owned(),
+        npm_deps_hash: FAKE_SRI_HASH.to_owned(),
+        pnpm_deps_hash: FAKE_SRI_HASH.to_owned(),
+        project_file: String::new(),
+        domain: info.domain.clone(),
+        src_url: info.src_url.clone(),
+        // Probe expressions don't need to render the inferred deps;
+        // we want a minimal expression that just exercises src + cargo.
+        build_inputs: Vec::new(),
+        native_build_inputs: Vec::new(),
+        use_cargo_lock_file: false,
+        cargo_lock_git_deps: Vec::new(),
+        go_module_path: String::new(),
+        go_mod_root: info.go_mod_root.clone(),
+        python_format: "setuptools".to_owned(),
+        mvn_hash: FAKE_SRI_HASH.to_owned(),
+        mix_fod_hash: FAKE_SRI_HASH.to_owned(),
+        gradle_hash: FAKE_SRI_HASH.to_owned(),
+    };
+
+    let probe_expr = crate::expression::generate_expression(&probe_info);
+    let probe_text = probe_info.format(&probe_expr);
+
+    // Write to a temp file (deleted when the variable goes out of scope).
+    let tmp_dir = match tempfile_dir() {
+        Some(d) => d,
+        None => {
+            eprintln!("Skipping hash prefetch: unable to create temporary directory");
+            return None;
+        }
+    };
+    let probe_path = tmp_dir.join("probe.nix");
+    {
+        let mut f = match std::fs::File::create(&probe_path) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!(
+                    "Skipping hash prefetch: failed to write probe expression: {}",
+                    e
+                );
+                return None;
+            }
+        };
+        if let Err(e) = f.write_all(probe_text.as_bytes()) {
+            eprintln!(
+                "Skipping hash prefetch: failed to write probe expression: {}",
+                e
+            );
+            return None;
+        }
+    }
+
+    let kind = match &info.template {
+        Template::Rust(_) => "cargoHash",
+        Template::Go(_) | Template::Php(_) => "vendorHash",
+        Template::Maven(_) => "mvnHash",
+        Template::Node(config) => match config.variant {
+            crate::types::NodeVariant::Npm => "npmDepsHash",
+            crate::types::NodeVariant::Pnpm => "pnpmDepsHash",
+        },
+        _ => unreachable!(),
+    };
+    eprintln!(
+        "Prefetching {} for {} (this may take a while)...",
+        kind, &info.pname
+    );
+
+    let output = Command::new("nix-build")
+        .args(&["--no-out-link", "-E"])
+        .arg(format!(
+            "(import <nixpkgs> {{}}).callPackage {} {{}}",
+            probe_path.display()
+        ))
+        .output();
+
+    let output = match output {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("Skipping hash prefetch: failed to invoke nix-build: {}", e);
+            return None;
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    debug!(target: LOG_TARGET, "nix-build stderr: {}", stderr);
+
+    let captured = GOT_HASH_REGEX
+        .captures_iter(&stderr)
+        .filter_map(|c| c.get(1).map(|m| m.as_str().to_owned()))
+        // The first hash mismatch is the dependency hash (cargo deps / go
+        // vendor tree); subsequent hashes (if any) belong to later phases.
+        .next();
+
+    match captured {
+        Some(h) => {
+            eprintln!("Determined {} = {}", kind, &h);
+            Some(h)
+        }
+        None => {
+            eprintln!(
+                "Could not determine {} from nix-build output. The placeholder will remain.",
+                kind
+            );
+            None
+        }
+    }
+}
+
+/// Create a unique temporary directory under `$TMPDIR` (or `/tmp`).
+/// Returns `None` on failure. The directory is leaked (not deleted) by the
+/// caller so the user can inspect it on failure.
+fn tempfile_dir() -> Option<std::path::PathBuf> {
+    let base = std::env::temp_dir();
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let dir = base.join(format!("nix-template-prefetch-{}", timestamp));
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Infer the .NET project file path by materializing the source and scanning for
+/// .csproj, .fsproj, or .sln files. Returns the path relative to the source root,
+/// or None if no project file is found or the source cannot be materialized.
+pub fn infer_dotnet_project_file(info: &types::ExpressionInfo) -> Option<String> {
+    use std::path::Path;
+
+    eprintln!("Materialising source to detect .NET project file...");
+    let source_path = match crate::source::materialise_source(info) {
+        Some(p) => p,
+        None => {
+            debug!(target: LOG_TARGET, "failed to materialise source; cannot infer project file");
+            return None;
+        }
+    };
+
+    // Search for project files in order of preference:
+    // 1. .csproj files (C#)
+    // 2. .fsproj files (F#)
+    // 3. .sln files (Solution)
+
+    fn find_project_files_impl(
+        dir: &Path,
+        extension: &str,
+        depth: usize,
+    ) -> Vec<std::path::PathBuf> {
+        use std::fs;
+
+        // Base case: if depth is 0, stop recursing
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut results = Vec::new();
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some(extension) {
+                    results.push(path);
+                } else if path.is_dir() {
+                    // Recursively search subdirectories with decremented depth
+                    results.extend(find_project_files_impl(&path, extension, depth - 1));
+                }
+            }
+        }
+        results
+    }
+
+    fn find_project_files(dir: &Path, extension: &str) -> Vec<std::path::PathBuf> {
+        // Search up to 3 levels deep
+        find_project_files_impl(dir, extension, 3)
+    }
+
+    // Try .csproj first
+    let mut candidates = find_project_files(&source_path, "csproj");
+    if candidates.is_empty() {
+        // Try .fsproj
+        candidates = find_project_files(&source_path, "fsproj");
+    }
+    if candidates.is_empty() {
+        // Try .sln
+        candidates = find_project_files(&source_path, "sln");
+    }
+
+    if candidates.is_empty() {
+        eprintln!("No .NET project files (.csproj, .fsproj, .sln) found in source");
+        return None;
+    }
+
+    // Prefer files in the root directory, then shortest path
+    candidates.sort_by_key(|p| p.components().count());
+
+    let chosen = &candidates[0];
+    let relative = chosen.strip_prefix(&source_path).ok()?;
+    let relative_str = relative.to_str()?;
+
+    eprintln!("Detected project file: {}", relative_str);
+    Some(relative_str.to_owned())
+}
+
+pub fn read_meta_from_url(url: &str, info: &mut types::ExpressionInfo, options: &FetchOptions) {
+    let trimmed_url = url
+        .trim_start_matches("http://")
+        .trim_start_matches("https://");
+
+    let repo = validate_and_parse_url(trimmed_url, url, &options.forges);
+    if let (Some(tag), Ok(repo)) = (&options.tag, &repo) {
+        if !repo.supports_tag() {
+            eprintln!(
+                "Warning: Selecting release {} is not supported for {} URLs; ignoring it",
+                tag,
+                repo.host_name()
+            );
+        }
+    }
+
+    let clone_url = repo.as_ref().ok().and_then(git_clone_url);
+
+    match repo {
+        Ok(Github(repo)) => {
+            fill_github_info(&repo, info, options);
+        }
+        Ok(Gitlab(repo)) => {
+            fill_gitlab_info(&repo, info, options);
+        }
+        Ok(Pypi(pypi_repo)) => {
+            fill_pypi_info(&pypi_repo, info, options.tag.as_deref());
+        }
+        Ok(Crates(crates_repo)) => {
+            fill_crates_info(&crates_repo, info, options);
+        }
+        Ok(Npm(npm_repo)) => {
+            fill_npm_info(&npm_repo, info, options);
+        }
+        Ok(Hackage(hackage_repo)) => {
+            fill_hackage_info(&hackage_repo, info, options);
+        }
+        Ok(Rubygems(rubygems_repo)) => {
+            fill_rubygems_info(&rubygems_repo, info, options);
+        }
+        Ok(Cpan(cpan_repo)) => {
+            fill_cpan_info(&cpan_repo, info, options);
+        }
+        Ok(Cran(cran_repo)) => {
+            fill_cran_info(&cran_repo, info, options);
+        }
+        Ok(Hex(hex_repo)) => {
+            fill_hex_info(&hex_repo, info, options);
+        }
+        Ok(Packagist(packagist_repo)) => {
+            fill_packagist_info(&packagist_repo, info, options);
+        }
+        Ok(Maven(maven_repo)) => {
+            fill_maven_info(&maven_repo, info, options);
+        }
+        Ok(LuaRocks(luarocks_repo)) => {
+            fill_luarocks_info(&luarocks_repo, info, options);
+        }
+        Ok(Pub(pub_repo)) => {
+            fill_pub_info(&pub_repo, info, options);
+        }
+        Ok(Opam(opam_repo)) => {
+            fill_opam_info(&opam_repo, info, options);
+        }
+        Ok(GoModule(go_module_repo)) => {
+            fill_go_module_info(&go_module_repo, info, options);
+        }
+        Ok(Gitea(gitea_repo)) => {
+            fill_gitea_info(&gitea_repo, info, options);
+        }
+        Ok(Sourcehut(repo)) => {
+            fill_sourcehut_info(&repo, info, options);
+        }
+        Ok(Bitbucket(repo)) => {
+            fill_bitbucket_info(&repo, info, options);
+        }
+        Ok(Git(repo)) => {
+            fill_git_info(&repo, info, options);
+        }
+        Ok(Archive(archive)) => {
+            fill_archive_info(&archive, info);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+
+    if let Some(clone_url) = clone_url {
+        apply_git_features(info, &clone_url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Repo::{Gitea, Github};
+    use crate::types::{GiteaRepo, GithubRepo};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_url_parse() {
+        let repo = validate_and_parse_url(
+            "github.com/jonringer/nix-template",
+            "github.com/jonringer/nix-template",
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo,
+            Github(GithubRepo {
+                owner: "jonringer".to_string(),
+                repo: "nix-template".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_codeberg_url_parse() {
+        let repo = validate_and_parse_url(
+            "codeberg.org/forgejo/forgejo",
+            "codeberg.org/forgejo/forgejo",
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo,
+            Gitea(GiteaRepo {
+                domain: "codeberg.org".to_string(),
+                owner: "forgejo".to_string(),
+                repo: "forgejo".to_string(),
+                api_base: "https://codeberg.org/api/v1".to_string(),
+                token_env: "GITEA_TOKEN".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_gitea_com_url_parse() {
+        let repo = validate_and_parse_url(
+            "gitea.com/user/project",
+            "gitea.com/user/project",
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo,
+            Gitea(GiteaRepo {
+                domain: "gitea.com".to_string(),
+                owner: "user".to_string(),
+                repo: "project".to_string(),
+                api_base: "https://gitea.com/api/v1".to_string(),
+                token_env: "GITEA_TOKEN".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_configured_forge_url_parse() {
+        let forges: HashMap<String, types::ForgeConfig> = toml::from_str(
+            r#"
+            ["git.example.org"]
+            kind = "gitlab"
+
+            ["code.example.net"]
+            kind = "forgejo"
+            api_base = "https://code.example.net/forgejo/api/v1/"
+            token_env = "EXAMPLE_TOKEN"
+
+            ["git.srht.example.com"]
+            kind = "sourcehut"
+
+            ["gitlab.com"]
+            kind = "gitlab"
+            token_env = "WORK_GITLAB_TOKEN"
+            "#,
+        )
+        .unwrap();
+
+        let repo = validate_and_parse_url(
+            "git.example.org/group/subgroup/tool.git",
+            "https://git.example.org/group/subgroup/tool.git",
+            &forges,
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Gitlab(types::GitlabRepo {
+                domain: "git.example.org".to_string(),
+                project_path: "group/subgroup/tool".to_string(),
+                owner: "group".to_string(),
+                repo: "tool".to_string(),
+                api_base: "https://git.example.org/api/v4".to_string(),
+                token_env: "GITLAB_TOKEN".to_string(),
+            })
+        );
+
+        let repo = validate_and_parse_url(
+            "code.example.net/user/project/",
+            "https://code.example.net/user/project/",
+            &forges,
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Gitea(GiteaRepo {
+                domain: "code.example.net".to_string(),
+                owner: "user".to_string(),
+                repo: "project".to_string(),
+                api_base: "https://code.example.net/forgejo/api/v1".to_string(),
+                token_env: "EXAMPLE_TOKEN".to_string(),
+            })
+        );
+
+        let repo = validate_and_parse_url(
+            "git.srht.example.com/~user/project",
+            "https://git.srht.example.com/~user/project",
+            &forges,
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Sourcehut(types::SourcehutRepo {
+                domain: "git.srht.example.com".to_string(),
+                owner: "~user".to_string(),
+                repo: "project".to_string(),
+                api_base: "https://git.srht.example.com/api".to_string(),
+                token_env: "SRHT_TOKEN".to_string(),
+            })
+        );
+        // Declared domains take precedence over the built-in hosts
+        let repo = validate_and_parse_url(
+            "gitlab.com/group/tool",
+            "https://gitlab.com/group/tool",
+            &forges,
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Gitlab(types::GitlabRepo {
+                token_env: "WORK_GITLAB_TOKEN".to_string(),
+                ..types::GitlabRepo::new("gitlab.com", "group/tool")
+            })
+        );
+
+        assert!(validate_and_parse_url(
+            "git.srht.example.com/user/project",
+            "https://git.srht.example.com/user/project",
+            &forges
+        )
+        .is_err());
+
+        assert!(validate_and_parse_url(
+            "git.example.org/lonely",
+            "git.example.org/lonely",
+            &forges
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_sourcehut_url_parse() {
+        let repo = validate_and_parse_url(
+            "git.sr.ht/~sircmpwn/scdoc",
+            "git.sr.ht/~sircmpwn/scdoc",
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo,
+            Sourcehut(types::SourcehutRepo::new("git.sr.ht", "~sircmpwn", "scdoc"))
+        );
+
+        let repo = validate_and_parse_url(
+            "git.sr.ht/~sircmpwn/scdoc.git/",
+            "https://git.sr.ht/~sircmpwn/scdoc.git/",
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Sourcehut(types::SourcehutRepo::new("git.sr.ht", "~sircmpwn", "scdoc"))
+        );
+    }
+
+    #[test]
+    fn test_sourcehut_url_requires_tilde_owner() {
+        assert!(validate_and_parse_url(
+            "git.sr.ht/sircmpwn/scdoc",
+            "git.sr.ht/sircmpwn/scdoc",
+            &HashMap::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_bitbucket_url_parse() {
+        let repo = validate_and_parse_url(
+            "bitbucket.org/multicoreware/x265_git.git/",
+            "https://bitbucket.org/multicoreware/x265_git.git/",
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo,
+            Bitbucket(types::BitbucketRepo {
+                workspace: "multicoreware".to_string(),
+                repo: "x265_git".to_string(),
+            })
+        );
+    }
+
+    /// Serve canned JSON bodies keyed by request path from a local socket,
+    /// standing in for the Bitbucket API. `routes` receives the server's
+    /// base URL so bodies can link to further pages. Returns the base URL.
+    fn spawn_mock_api(routes: impl FnOnce(&str) -> Vec<(String, String)>) -> String {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let routes = routes(&base);
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Drain the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or("");
+                let response = match routes.iter().find(|(p, _)| p == path) {
+                    Some((_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_owned(),
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_bitbucket_tags_follow_pagination() {
+        let base = spawn_mock_api(|base| {
+            vec![
+                (
+                    "/repositories/ws/demo/refs/tags?pagelen=100".to_owned(),
+                    format!(
+                        r#"{{"values": [{{"name": "v1.0.0"}}], "next": "{}/repositories/ws/demo/refs/tags?pagelen=100&page=2"}}"#,
+                        base
+                    ),
+                ),
+                (
+                    "/repositories/ws/demo/refs/tags?pagelen=100&page=2".to_owned(),
+                    r#"{"values": [{"name": "v1.1.0"}, {"name": "v1.2.0-rc1"}]}"#.to_owned(),
+                ),
+            ]
+        });
+        let repo = types::BitbucketRepo {
+            workspace: "ws".to_string(),
+            repo: "demo".to_string(),
+        };
+
+        let tags = fetch_bitbucket_tags(&base, &repo).unwrap();
+        assert_eq!(tags, vec!["v1.0.0", "v1.1.0", "v1.2.0-rc1"]);
+    }
+
+    #[test]
+    fn test_bitbucket_repo_info_from_mock_api() {
+        let base = spawn_mock_api(|_| {
+            vec![(
+                "/repositories/ws/demo".to_owned(),
+                r#"{"name": "demo", "description": "A demo project", "website": "", "is_private": false}"#
+                    .to_owned(),
+            )]
+        });
+        let repo = types::BitbucketRepo {
+            workspace: "ws".to_string(),
+            repo: "demo".to_string(),
+        };
+
+        let parsed = fetch_bitbucket_repo_info(&base, &repo).unwrap();
+        assert_eq!(parsed.description.as_deref(), Some("A demo project"));
+        assert_eq!(parsed.website.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_generic_git_url_parse() {
+        for (url, name) in [
+            ("git://git.kernel.org/pub/scm/utils/dtc/dtc.git", "dtc"),
+            (
+                "https://git.zx2c4.com/wireguard-tools.git/",
+                "wireguard-tools",
+            ),
+            ("ssh://git@git.example.org/tools/demo", "demo"),
+            ("git@git.example.org:tools/demo.git", "demo"),
+            ("file:///srv/git/demo.git", "demo"),
+        ] {
+            let repo =
+                validate_and_parse_url(url.trim_start_matches("https://"), url, &HashMap::new())
+                    .unwrap();
+            assert_eq!(
+                repo,
+                Git(types::GitRepo {
+                    url: url.trim_end_matches('/').to_string(),
+                    repo: name.to_string(),
+                })
+            );
+        }
+
+        // Known forges keep their dedicated fetchers even with a `.git` suffix
+        let repo = validate_and_parse_url(
+            "github.com/jonringer/nix-template.git",
+            "https://github.com/jonringer/nix-template.git",
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert!(matches!(repo, Github(_)));
+    }
+
+    #[test]
+    fn test_archive_url_parse() {
+        for (url, pname, version, is_zip) in [
+            (
+                "https://ftp.gnu.org/gnu/hello/hello-2.12.1.tar.gz",
+                Some("hello"),
+                Some("2.12.1"),
+                false,
+            ),
+            (
+                "https://example.org/dl/foo-bar_v0.4.0.tar.xz",
+                Some("foo-bar"),
+                Some("0.4.0"),
+                false,
+            ),
+            (
+                "file:///tmp/libdemo-1.0.zip",
+                Some("libdemo"),
+                Some("1.0"),
+                true,
+            ),
+            ("https://example.org/v3.1.tgz", None, Some("3.1"), false),
+            ("https://example.org/latest.tar.bz2", None, None, false),
+            (
+                "https://github.com/o/r/releases/download/v1.4.0/Demo-1.4.0-x86_64.AppImage",
+                Some("Demo"),
+                Some("1.4.0"),
+                false,
+            ),
+        ] {
+            let repo =
+                validate_and_parse_url(url.trim_start_matches("https://"), url, &HashMap::new())
+                    .unwrap();
+            assert_eq!(
+                repo,
+                Archive(types::ArchiveRepo {
+                    url: url.to_string(),
+                    pname: pname.map(|p| p.to_string()),
+                    version: version.map(|v| v.to_string()),
+                    is_zip,
+                    is_appimage: url.ends_with(".AppImage"),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_archive_url() {
+        assert_eq!(
+            template_archive_url(
+                "https://ftp.gnu.org/gnu/hello/hello-2.12.1.tar.gz",
+                "2.12.1"
+            ),
+            "https://ftp.gnu.org/gnu/hello/hello-${finalAttrs.version}.tar.gz"
+        );
+        assert_eq!(
+            template_archive_url(
+                "https://github.com/o/r/releases/download/v1.2/r-1.2-linux.tar.gz",
+                "1.2"
+            ),
+            "https://github.com/o/r/releases/download/v${finalAttrs.version}/r-${finalAttrs.version}-linux.tar.gz"
+        );
+        // Only whole path segments and the filename are rewritten, not the host
+        assert_eq!(
+            template_archive_url("https://cdn1.example.org/1/pkg-1.zip", "1"),
+            "https://cdn1.example.org/${finalAttrs.version}/pkg-${finalAttrs.version}.zip"
+        );
+    }
+
+    #[test]
+    fn test_latest_version_tag() {
+        let tags: Vec<String> = ["v1.2.0", "v1.10.0", "v2.0.0-rc1", "nightly", "v1.9.0"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+
+        assert_eq!(
+            latest_version_tag(&tags, false, &TagScheme::default()).unwrap(),
+            "v1.10.0"
+        );
+        assert_eq!(
+            latest_version_tag(&tags, true, &TagScheme::default()).unwrap(),
+            "v2.0.0-rc1"
+        );
+        assert!(
+            latest_version_tag(&["nightly".to_string()], false, &TagScheme::default()).is_none()
+        );
+    }
+
+    /// JSON array of tag objects, with the fields GitHub, GitLab and Gitea
+    /// all accept
+    fn tag_page(names: impl IntoIterator<Item = String>) -> String {
+        let tags: Vec<String> = names
+            .into_iter()
+            .map(|n| format!(r#"{{"name": "{}", "target": "0123abcd"}}"#, n))
+            .collect();
+        format!("[{}]", tags.join(","))
+    }
+
+    #[test]
+    fn test_latest_forge_tag() {
+        let tags = vec![
+            "v1.9.0".to_owned(),
+            "v1.10.0".to_owned(),
+            "v2.0.0-rc1".to_owned(),
+            "nightly".to_owned(),
+        ];
+        assert_eq!(
+            latest_forge_tag(tags.clone(), false, &TagScheme::default()),
+            Some("v1.10.0".to_owned())
+        );
+        assert_eq!(
+            latest_forge_tag(tags, true, &TagScheme::default()),
+            Some("v2.0.0-rc1".to_owned())
+        );
+        assert_eq!(
+            latest_forge_tag(vec!["nightly".to_owned()], false, &TagScheme::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_tag() {
+        let tags = vec![
+            "1.2.0".to_owned(),
+            "v1.2.0".to_owned(),
+            "v1.3.0".to_owned(),
+            "release-2.0".to_owned(),
+        ];
+        assert_eq!(
+            find_tag(&tags, "v1.3.0", &TagScheme::default()),
+            Some("v1.3.0".to_owned())
+        );
+        assert_eq!(
+            find_tag(&tags, "1.3.0", &TagScheme::default()),
+            Some("v1.3.0".to_owned())
+        );
+        // A verbatim tag wins over a prefixed one with the same version
+        assert_eq!(
+            find_tag(&tags, "1.2.0", &TagScheme::default()),
+            Some("1.2.0".to_owned())
+        );
+        assert_eq!(
+            find_tag(&tags, "2.0", &TagScheme::default()),
+            Some("release-2.0".to_owned())
+        );
+        assert_eq!(find_tag(&tags, "1.4.0", &TagScheme::default()), None);
+    }
+
+    #[test]
+    fn test_tag_scheme_default_matches_version_regex() {
+        let scheme = TagScheme::default();
+        assert_eq!(
+            scheme.split("foo-2024.05"),
+            Some(SplitTag {
+                prefix: "foo-".to_owned(),
+                version: "2024.05".to_owned(),
+            })
+        );
+        assert_eq!(scheme.tag_for("v", "1.2.3"), "v1.2.3");
+    }
+
+    #[test]
+    fn test_tag_scheme_transform() {
+        let scheme = TagScheme::new(
+            Some(r"^(?P<prefix>release_)(?P<version>[0-9_]+)$"),
+            Some("underscores"),
+        )
+        .unwrap();
+        assert_eq!(
+            scheme.split("release_1_2_3"),
+            Some(SplitTag {
+                prefix: "release_".to_owned(),
+                version: "1.2.3".to_owned(),
+            })
+        );
+        assert_eq!(scheme.tag_for("release_", "1.2.3"), "release_1_2_3");
+        assert_eq!(scheme.split("v1.2.3"), None);
+
+        // Without a regex the default prefix guess is transformed too
+        let scheme = TagScheme::new(None, Some("underscores")).unwrap();
+        assert_eq!(scheme.split("R_4_3_1").unwrap().version, "4.3.1");
+        // A tag mixing separators couldn't be rebuilt from its version
+        assert_eq!(scheme.split("R_4.3_1"), None);
+    }
+
+    #[test]
+    fn test_tag_scheme_selects_component() {
+        let scheme = TagScheme::new(Some(r"^(?P<prefix>cli-v)(?P<version>.+)$"), None).unwrap();
+        let tags = vec![
+            "cli-v1.0.0".to_owned(),
+            "lib-v3.0.0".to_owned(),
+            "cli-v1.2.0".to_owned(),
+        ];
+        assert_eq!(
+            latest_forge_tag(tags.clone(), false, &scheme),
+            Some("cli-v1.2.0".to_owned())
+        );
+        assert_eq!(
+            find_tag(&tags, "1.0.0", &scheme),
+            Some("cli-v1.0.0".to_owned())
+        );
+        assert_eq!(find_tag(&tags, "3.0.0", &scheme), None);
+    }
+
+    #[test]
+    fn test_tag_scheme_rejects_invalid_options() {
+        assert!(TagScheme::new(Some(r"^v(.+)$"), None).is_err());
+        assert!(TagScheme::new(Some(r"^v(?P<version>"), None).is_err());
+        assert!(TagScheme::new(None, Some("slashes")).is_err());
+    }
+
+    #[test]
+    fn test_github_tags_follow_pagination() {
+        // 250 tags over three pages; the newest one sits on the last page
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/repos/owner/tool/tags?per_page=100&page=1".to_owned(),
+                    tag_page((0..100).map(|i| format!("v0.{}.0", i))),
+                ),
+                (
+                    "/repos/owner/tool/tags?per_page=100&page=2".to_owned(),
+                    tag_page((100..200).map(|i| format!("v0.{}.0", i))),
+                ),
+                (
+                    "/repos/owner/tool/tags?per_page=100&page=3".to_owned(),
+                    tag_page((200..250).map(|i| format!("v0.{}.0", i))),
+                ),
+            ]
+        });
+        let repo = types::GithubRepo {
+            owner: "owner".to_owned(),
+            repo: "tool".to_owned(),
+        };
+
+        let tags = fetch_github_tags(&base, &repo).unwrap();
+        assert_eq!(tags.len(), 250);
+        assert_eq!(
+            latest_forge_tag(tags, false, &TagScheme::default()),
+            Some("v0.249.0".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_gitlab_tags_follow_pagination() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/projects/group%2Ftool/repository/tags?per_page=100&page=1".to_owned(),
+                    tag_page((0..100).map(|i| format!("1.{}", i))),
+                ),
+                (
+                    "/projects/group%2Ftool/repository/tags?per_page=100&page=2".to_owned(),
+                    tag_page(vec!["2.0".to_owned()]),
+                ),
+            ]
+        });
+        let mut repo = types::GitlabRepo::new("gitlab.com", "group/tool");
+        repo.api_base = base;
+
+        let tags = fetch_gitlab_tags(&repo).unwrap();
+        assert_eq!(tags.len(), 101);
+        assert_eq!(
+            latest_forge_tag(tags, false, &TagScheme::default()),
+            Some("2.0".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_gitea_tags_follow_pagination() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/repos/user/project/tags?limit=50&page=1".to_owned(),
+                    tag_page((0..50).map(|i| format!("v3.{}", i))),
+                ),
+                (
+                    "/repos/user/project/tags?limit=50&page=2".to_owned(),
+                    tag_page(Vec::new()),
+                ),
+            ]
+        });
+        let mut repo = types::GiteaRepo::new("codeberg.org", "user", "project");
+        repo.api_base = base;
+
+        let tags = fetch_gitea_tags(&repo).unwrap();
+        assert_eq!(tags.len(), 50);
+        assert_eq!(
+            latest_forge_tag(tags, false, &TagScheme::default()),
+            Some("v3.49".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_gitea_commit_from_mock_api() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/repos/user/project/commits?limit=1&stat=false".to_owned(),
+                    r#"[{"sha": "0123456789abcdef", "commit": {"committer": {"date": "2024-05-01T12:00:00Z"}}}]"#
+                        .to_owned(),
+                ),
+                (
+                    "/repos/user/project/commits?limit=1&stat=false&sha=feature%2Fx".to_owned(),
+                    r#"[{"sha": "fedcba9876543210", "commit": {"committer": {"date": "2023-01-02T00:00:00Z"}}}]"#
+                        .to_owned(),
+                ),
+            ]
+        });
+        let mut repo = types::GiteaRepo::new("codeberg.org", "user", "project");
+        repo.api_base = base;
+
+        let commit = fetch_gitea_commit(&repo, None).unwrap();
+        assert_eq!(commit.sha, "0123456789abcdef");
+        assert_eq!(commit.date, "2024-05-01T12:00:00Z");
+
+        let commit = fetch_gitea_commit(&repo, Some("feature/x")).unwrap();
+        assert_eq!(commit.sha, "fedcba9876543210");
+    }
+
+    #[test]
+    fn test_gitlab_commit_uses_default_branch() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/projects/group%2Ftool".to_owned(),
+                    r#"{"id": 1, "name": "tool", "path": "tool", "path_with_namespace": "group/tool", "web_url": "https://gitlab.com/group/tool", "default_branch": "trunk"}"#
+                        .to_owned(),
+                ),
+                (
+                    "/projects/group%2Ftool/repository/commits/trunk".to_owned(),
+                    r#"{"id": "abc123", "committed_date": "2024-02-29T23:00:00.000+01:00"}"#
+                        .to_owned(),
+                ),
+            ]
+        });
+        let mut repo = types::GitlabRepo::new("gitlab.com", "group/tool");
+        repo.api_base = base;
+
+        let commit = fetch_gitlab_commit(&repo, None).unwrap();
+        assert_eq!(commit.sha, "abc123");
+        assert_eq!(commit.date, "2024-02-29T23:00:00.000+01:00");
+    }
+
+    #[test]
+    fn test_fetch_options_rev_implies_unstable() {
+        assert!(!FetchOptions::default().wants_unstable());
+        assert!(FetchOptions {
+            unstable: true,
+            ..Default::default()
+        }
+        .wants_unstable());
+        assert!(FetchOptions {
+            rev: Some("main".to_owned()),
+            ..Default::default()
+        }
+        .wants_unstable());
+    }
+
+    #[test]
+    fn test_version_regex() {
+        let captures = VERSION_REGEX.captures("v0.1.0").unwrap();
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(1).unwrap().as_str(), "v");
+        assert_eq!(captures.get(2).unwrap().as_str(), "0.1.0");
+
+        let captures = VERSION_REGEX.captures("azure-cli-21.1.3").unwrap();
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(1).unwrap().as_str(), "azure-cli-");
+        assert_eq!(captures.get(2).unwrap().as_str(), "21.1.3");
+
+        let captures = VERSION_REGEX.captures("zfs-2.1.0-rc7").unwrap();
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(1).unwrap().as_str(), "zfs-");
+        assert_eq!(captures.get(2).unwrap().as_str(), "2.1.0-rc7");
+    }
+
+    #[test]
+    fn test_simple_version_regex() {
+        let captures = VERSION_REGEX.captures("2.1.1").unwrap();
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(1).unwrap().as_str(), "");
+        assert_eq!(captures.get(2).unwrap().as_str(), "2.1.1");
+    }
+
+    #[test]
+    fn test_got_hash_regex_normal_hash() {
+        // Test normal SHA256 hash (44 characters in base64)
+        let input = "  got:    sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+        let captures = GOT_HASH_REGEX.captures(input).unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(
+            captures.get(1).unwrap().as_str(),
+            "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+        );
+    }
+
+    #[test]
+    fn test_got_hash_regex_prevents_redos() {
+        // Test that bounded quantifier prevents ReDoS
+        // This would cause catastrophic backtracking with unbounded quantifier
+        // The key is that the regex completes quickly even with malicious input
+        let malicious_input = format!("  got:    sha256-{}", "A".repeat(200));
+
+        // The regex will match the first 100 characters (which is fine for extraction)
+        // The important part is it doesn't hang due to catastrophic backtracking
+        let result = GOT_HASH_REGEX.captures(&malicious_input);
+
+        // It will match, but only capture up to 100 characters after "sha256-"
+        if let Some(captures) = result {
+            let captured_hash = captures.get(1).unwrap().as_str();
+            // Should capture at most "sha256-" + 100 chars
+            assert!(
+                captured_hash.len() <= 107, // "sha256-" (7 chars) + 100 chars
+                "Should not capture more than bounded amount"
+            );
+        }
+    }
+
+    #[test]
+    fn test_got_hash_regex_with_varying_whitespace() {
+        // Test with single space
+        let input = "got: sha256-abc123def456=";
+        let captures = GOT_HASH_REGEX.captures(input).unwrap();
+        assert!(captures.get(1).unwrap().as_str().starts_with("sha256-"));
+
+        // Test with multiple spaces
+        let input = "got:     sha256-xyz789=";
+        let captures = GOT_HASH_REGEX.captures(input).unwrap();
+        assert!(captures.get(1).unwrap().as_str().starts_with("sha256-"));
+    }
+
+    #[test]
+    fn test_stable_release_regex_valid_versions() {
+        // Test normal version strings
+        assert!(STABLE_RELEASE_REGEX.is_match("1.2.3"));
+        assert!(STABLE_RELEASE_REGEX.is_match("2024.1.15"));
+        assert!(STABLE_RELEASE_REGEX.is_match("0.1.0"));
+        assert!(STABLE_RELEASE_REGEX.is_match("123"));
+
+        // Test that non-stable versions don't match
+        assert!(!STABLE_RELEASE_REGEX.is_match("v1.2.3"));
+        assert!(!STABLE_RELEASE_REGEX.is_match("1.2.3-rc1"));
+        assert!(!STABLE_RELEASE_REGEX.is_match("abc"));
+    }
+
+    #[test]
+    fn test_stable_release_regex_prevents_redos() {
+        // Test that bounded quantifier prevents ReDoS from nested quantifiers
+        // The old pattern ^([0-9.]*)+$ would cause catastrophic backtracking
+        let mut malicious_input = "1".repeat(100);
+        malicious_input.push('X'); // Doesn't end with valid chars
+
+        // Should complete quickly and not match
+        let result = STABLE_RELEASE_REGEX.is_match(&malicious_input);
+        assert!(!result, "Should not match invalid input");
+
+        // Test that we still accept reasonably long valid versions
+        let long_valid = "2024.12.31.1.2.3.4.5.6.7.8.9.10.11.12";
+        assert!(
+            STABLE_RELEASE_REGEX.is_match(long_valid),
+            "Should match valid version within bounds"
+        );
+    }
+
+    #[test]
+    fn test_stable_release_regex_length_bound() {
+        // Test that versions exceeding 50 characters are rejected
+        let too_long = "1.".repeat(30); // Creates "1.1.1.1..." > 50 chars
+        assert!(
+            !STABLE_RELEASE_REGEX.is_match(&too_long),
+            "Should reject excessively long version strings"
+        );
+    }
+
+    #[test]
+    fn test_validate_url_component_valid() {
+        // Test normal valid components
+        assert!(validate_url_component("valid-repo", "test").is_ok());
+        assert!(validate_url_component("repo_123", "test").is_ok());
+        assert!(validate_url_component("1.2.3", "test").is_ok());
+        assert!(validate_url_component("v1.0.0", "test").is_ok());
+    }
+
+    #[test]
+    fn test_validate_url_component_rejects_control_chars() {
+        // Test that control characters are rejected
+        assert!(validate_url_component("foo\nbar", "test").is_err());
+        assert!(validate_url_component("foo\rbar", "test").is_err());
+        assert!(validate_url_component("foo\tbar", "test").is_err());
+        assert!(validate_url_component("foo\x00bar", "test").is_err());
+    }
+
+    #[test]
+    fn test_validate_url_component_rejects_null_bytes() {
+        // Test explicit null byte rejection
+        let with_null = "test\0value";
+        assert!(validate_url_component(with_null, "test").is_err());
+    }
+
+    #[test]
+    fn test_validate_url_component_rejects_too_long() {
+        // Test length validation (max 255)
+        let too_long = "a".repeat(256);
+        assert!(validate_url_component(&too_long, "test").is_err());
+
+        // Test that 255 is OK
+        let max_length = "a".repeat(255);
+        assert!(validate_url_component(&max_length, "test").is_ok());
+    }
+
+    #[test]
+    fn test_validate_url_component_rejects_empty() {
+        // Test that empty strings are rejected
+        assert!(validate_url_component("", "test").is_err());
+        assert!(validate_url_component("   ", "test").is_err());
+    }
+
+    #[test]
+    fn test_validate_github_repo_valid() {
+        // Test valid GitHub repo
+        let repo = types::GithubRepo {
+            owner: "octocat".to_string(),
+            repo: "Hello-World".to_string(),
+        };
+        assert!(validate_github_repo(&repo).is_ok());
+    }
+
+    #[test]
+    fn test_validate_github_repo_invalid_owner() {
+        // Test invalid owner with newline
+        let repo = types::GithubRepo {
+            owner: "bad\nowner".to_string(),
+            repo: "Hello-World".to_string(),
+        };
+        assert!(validate_github_repo(&repo).is_err());
+    }
+
+    #[test]
+    fn test_validate_version_components_valid() {
+        // Test valid version components
+        assert!(validate_version_components("1.2.3", "v").is_ok());
+        assert!(validate_version_components("2024.1.15", "").is_ok());
+        assert!(validate_version_components("0.1.0", "version-").is_ok());
+    }
+
+    #[test]
+    fn test_validate_version_components_invalid() {
+        // Test invalid version with control characters
+        assert!(validate_version_components("1.2.3\n4", "v").is_err());
+        assert!(validate_version_components("1.2.3", "v\r").is_err());
+
+        // Test empty version (tag_prefix can be empty though)
+        assert!(validate_version_components("", "v").is_err());
+    }
+
+    #[test]
+    fn test_validate_version_components_allows_empty_prefix() {
+        // Tag prefix can be empty (no prefix)
+        assert!(validate_version_components("1.2.3", "").is_ok());
+    }
+
+    #[test]
+    fn test_crates_url_parse() {
+        let repo = validate_and_parse_url(
+            "crates.io/crates/ripgrep",
+            "https://crates.io/crates/ripgrep",
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repo,
+            Crates(types::CratesRepo {
+                name: "ripgrep".to_string()
+            })
+        );
+        assert!(validate_and_parse_url(
+            "crates.io/search?q=ripgrep",
+            "https://crates.io/search?q=ripgrep",
+            &HashMap::new(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_spdx_to_nixpkgs_license() {
+        assert_eq!(spdx_to_nixpkgs_license("MIT"), "mit");
+        assert_eq!(spdx_to_nixpkgs_license("MIT OR Apache-2.0"), "mit asl20");
+        assert_eq!(spdx_to_nixpkgs_license("MIT/Apache-2.0"), "mit asl20");
+        assert_eq!(
+            spdx_to_nixpkgs_license("(Apache-2.0 WITH LLVM-exception) OR MIT"),
+            "asl20 mit"
+        );
+        assert_eq!(spdx_to_nixpkgs_license("Unlicense OR MIT"), "unlicense mit");
+        assert_eq!(spdx_to_nixpkgs_license("MIT AND Frobnicate-1.0"), "CHANGE");
+        assert_eq!(spdx_to_nixpkgs_license(""), "CHANGE");
+    }
+
+    fn crate_version(num: &str, yanked: bool) -> types::CrateVersion {
+        types::CrateVersion {
+            num: num.to_owned(),
+            yanked,
+            license: Some("MIT".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_select_crate_version() {
+        let versions = vec![
+            crate_version("2.0.0-rc.1", false),
+            crate_version("1.10.0", true),
+            crate_version("1.9.0", false),
+            crate_version("1.2.0", false),
+        ];
+
+        let latest = select_crate_version(&versions, None, false).unwrap();
+        assert_eq!(latest.num, "1.9.0");
+        let latest = select_crate_version(&versions, None, true).unwrap();
+        assert_eq!(latest.num, "2.0.0-rc.1");
+        // An explicitly requested release is honoured even when yanked
+        let wanted = select_crate_version(&versions, Some("v1.10.0"), false).unwrap();
+        assert_eq!(wanted.num, "1.10.0");
+        assert!(select_crate_version(&versions, Some("3.0.0"), false).is_none());
+    }
+
+    #[test]
+    fn test_fetch_crate_info_skips_yanked_release() {
+        let base = spawn_mock_api(|_| {
+            vec![(
+                "/crates/ripgrep".to_owned(),
+                r#"{
+                    "crate": {
+                        "name": "ripgrep",
+                        "description": "ripgrep is a line-oriented search tool",
+                        "homepage": null,
+                        "repository": "https://github.com/BurntSushi/ripgrep"
+                    },
+                    "versions": [
+                        {"num": "14.1.1", "yanked": true, "license": "MIT"},
+                        {"num": "14.1.0", "yanked": false, "license": "Unlicense OR MIT"}
+                    ]
+                }"#
+                .to_owned(),
+            )]
+        });
+        let repo = types::CratesRepo {
+            name: "ripgrep".to_owned(),
+        };
+
+        let response = fetch_crate_info(&base, &repo).unwrap();
+        assert_eq!(response.krate.name, "ripgrep");
+        assert_eq!(response.krate.homepage, None);
+        let version = select_crate_version(&response.versions, None, false).unwrap();
+        assert_eq!(version.num, "14.1.0");
+        assert_eq!(
+            spdx_to_nixpkgs_license(version.license.as_deref().unwrap()),
+            "unlicense mit"
+        );
+
+        let missing = types::CratesRepo {
+            name: "no-such-crate".to_owned(),
+        };
+        assert!(fetch_crate_info(&base, &missing).is_err());
+    }
+
+    #[test]
+    fn test_pypi_release_strips_v_prefix() {
+        let base = spawn_mock_api(|_| {
+            vec![(
+                "/demo/1.2.3/json".to_owned(),
+                r#"{
+                    "info": {
+                        "author": null, "author_email": null, "bugtrack_url": null,
+                        "classifiers": [], "description": "", "description_content_type": "",
+                        "docs_url": null, "download_url": null,
+                        "home_page": "https://example.org/demo", "license": "MIT License",
+                        "name": "demo", "package_url": "", "platform": null,
+                        "project_url": "", "project_urls": null, "release_url": "",
+                        "requires_dist": null, "requires_python": "",
+                        "summary": "A demo.", "version": "1.2.3",
+                        "yanked": false, "yanked_reason": null
+                    },
+                    "urls": []
+                }"#
+                .to_owned(),
+            )]
+        });
+        let repo = types::PypiRepo {
+            project: "demo".to_owned(),
+        };
+
+        let mut info = unfilled_info();
+        fill_pypi_release(&base, &repo, &mut info, "v1.2.3");
+        assert_eq!(info.version, "1.2.3");
+        assert_eq!(info.description, "A demo");
+        assert_eq!(info.license, "mit");
+    }
+
+    #[test]
+    fn test_npm_url_parse() {
+        let repo = validate_and_parse_url(
+            "www.npmjs.com/package/prettier",
+            "https://www.npmjs.com/package/prettier",
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Npm(types::NpmRepo {
+                name: "prettier".to_string()
+            })
+        );
+
+        let repo = validate_and_parse_url(
+            "www.npmjs.com/package/@angular/cli",
+            "https://www.npmjs.com/package/@angular/cli",
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            repo,
+            Npm(types::NpmRepo {
+                name: "@angular/cli".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_github_repo_from_link() {
+        let expected = Some(types::GithubRepo {
+            owner: "prettier".to_owned(),
+            repo: "prettier".to_owned(),
+        });
+        for link in [
+            "git+https://github.com/prettier/prettier.git",
+            "https://github.com/prettier/prettier",
+            "git://github.com/prettier/prettier.git",
+            "git+ssh://git@github.com/prettier/prettier.git",
+            "git@github.com:prettier/prettier.git",
+            "github:prettier/prettier",
+            "prettier/prettier",
+        ] {
+            assert_eq!(github_repo_from_link(link), expected, "{}", link);
+        }
+        assert_eq!(
+            github_repo_from_link("https://gitlab.com/prettier/prettier"),
+            None
+        );
+        assert_eq!(github_repo_from_link("bitbucket:o/r"), None);
+    }
+
+    #[test]
+    fn test_npm_main_program() {
+        let single = types::NpmBin::Single("./bin/cli.js".to_owned());
+        assert_eq!(npm_main_program(&single, "demo"), Some("demo".to_owned()));
+
+        let mut bins = std::collections::BTreeMap::new();
+        bins.insert("ng".to_owned(), "./bin/ng.js".to_owned());
+        let only = types::NpmBin::Map(bins.clone());
+        assert_eq!(npm_main_program(&only, "cli"), Some("ng".to_owned()));
+
+        bins.insert("ng-xi18n".to_owned(), "./bin/xi18n.js".to_owned());
+        let several = types::NpmBin::Map(bins.clone());
+        assert_eq!(npm_main_program(&several, "cli"), None);
+        bins.insert("cli".to_owned(), "./bin/cli.js".to_owned());
+        let named = types::NpmBin::Map(bins);
+        assert_eq!(npm_main_program(&named, "cli"), Some("cli".to_owned()));
+    }
+
+    #[test]
+    fn test_fetch_npm_manifest_for_scoped_package() {
+        let base = spawn_mock_api(|_| {
+            vec![(
+                "/@demo%2ftool/latest".to_owned(),
+                r#"{
+                    "name": "@demo/tool",
+                    "version": "2.1.0",
+                    "description": "Demo tool.",
+                    "license": {"type": "MIT"},
+                    "repository": {"type": "git", "url": "git+https://github.com/demo/tool.git"},
+                    "bin": {"tool": "bin/tool.js"},
+                    "dist": {"tarball": "https://registry.npmjs.org/@demo/tool/-/tool-2.1.0.tgz"}
+                }"#
+                .to_owned(),
+            )]
+        });
+        let repo = types::NpmRepo {
+            name: "@demo/tool".to_owned(),
+        };
 
-    match captured {
-        Some(h) => {
-            eprintln!("Determined {} = {}", kind, &h);
-            Some(h)
+        let manifest = fetch_npm_manifest(&base, &repo, "latest").unwrap();
+        assert_eq!(manifest.version, "2.1.0");
+        assert!(matches!(
+            &manifest.license,
+            Some(types::NpmLicense::Object { kind }) if kind == "MIT"
+        ));
+        assert!(matches!(
+            &manifest.repository,
+            Some(types::NpmRepository::Object { url }) if url == "git+https://github.com/demo/tool.git"
+        ));
+        assert_eq!(
+            npm_main_program(manifest.bin.as_ref().unwrap(), "tool"),
+            Some("tool".to_owned())
+        );
+        assert_eq!(
+            template_archive_url(&manifest.dist.tarball, &manifest.version),
+            "https://registry.npmjs.org/@demo/tool/-/tool-${finalAttrs.version}.tgz"
+        );
+        assert!(fetch_npm_manifest(&base, &repo, "9.9.9").is_err());
+    }
+
+    #[test]
+    fn test_hackage_url_parse() {
+        for (url, version) in [
+            ("hackage.haskell.org/package/base64-bytestring", None),
+            (
+                "hackage.haskell.org/package/base64-bytestring-1.2.1.0",
+                Some("1.2.1.0"),
+            ),
+            ("hackage.haskell.org/package/base64-bytestring/", None),
+        ] {
+            assert_eq!(
+                validate_and_parse_url(url, url, &HashMap::new()).unwrap(),
+                Hackage(types::HackageRepo {
+                    package: "base64-bytestring".to_string(),
+                    version: version.map(|v| v.to_string()),
+                }),
+                "{}",
+                url
+            );
         }
-        None => {
-            eprintln!(
-                "Could not determine {} from nix-build output. The placeholder will remain.",
-                kind
+    }
+
+    #[test]
+    fn test_rubygems_url_parse() {
+        for url in &[
+            "rubygems.org/gems/rails",
+            "rubygems.org/gems/rails/",
+            "rubygems.org/gems/rails/versions/7.1.3",
+        ] {
+            assert_eq!(
+                validate_and_parse_url(url, url, &HashMap::new()).unwrap(),
+                Rubygems(types::RubygemsRepo {
+                    name: "rails".to_string()
+                })
             );
-            None
         }
     }
-}
 
-/// Create a unique temporary directory under `$TMPDIR` (or `/tmp`).
-/// Returns `None` on failure. The directory is leaked (not deleted) by the
-/// caller so the user can inspect it on failure.
-fn tempfile_dir() -> Option<std::path::PathBuf> {
-    let base = std::env::temp_dir();
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .ok()?
-        .as_secs();
-    let dir = base.join(format!("nix-template-prefetch-{}", timestamp));
-    std::fs::create_dir_all(&dir).ok()?;
-    Some(dir)
-}
+    #[test]
+    fn test_fetch_rubygem() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/api/v1/gems/demo.json".to_owned(),
+                    r#"{"name":"demo","version":"2.0.0","licenses":["MIT"],"info":"A demo\n  gem.","homepage_uri":"https://demo.example","source_code_uri":null,"sha":"abc","dependencies":{"development":[],"runtime":[{"name":"nokogiri","requirements":">= 1.10"}]}}"#.to_owned(),
+                ),
+                (
+                    "/api/v2/rubygems/demo/versions/1.0.0.json".to_owned(),
+                    r#"{"name":"demo","number":"1.0.0","licenses":[],"info":"A demo gem","homepage_uri":"","source_code_uri":"https://github.com/o/demo","sha":"def","dependencies":{"runtime":[]}}"#.to_owned(),
+                ),
+            ]
+        });
+        let repo = types::RubygemsRepo {
+            name: "demo".to_owned(),
+        };
 
-/// Infer the .NET project file path by materializing the source and scanning for
-/// .csproj, .fsproj, or .sln files. Returns the path relative to the source root,
-/// or None if no project file is found or the source cannot be materialized.
-pub fn infer_dotnet_project_file(info: &types::ExpressionInfo) -> Option<String> {
-    use std::path::Path;
+        let latest = fetch_rubygem(&base, &repo, None).unwrap();
+        assert_eq!(latest.version, "2.0.0");
+        assert_eq!(latest.licenses, Some(vec!["MIT".to_owned()]));
+        assert_eq!(latest.dependencies.runtime[0].name, "nokogiri");
 
-    eprintln!("Materialising source to detect .NET project file...");
-    let source_path = match crate::source::materialise_source(info) {
-        Some(p) => p,
-        None => {
-            debug!(target: LOG_TARGET, "failed to materialise source; cannot infer project file");
-            return None;
-        }
-    };
+        let pinned = fetch_rubygem(&base, &repo, Some("1.0.0")).unwrap();
+        assert_eq!(pinned.version, "1.0.0");
+        assert_eq!(pinned.sha.as_deref(), Some("def"));
+        assert!(fetch_rubygem(&base, &repo, Some("9.9")).is_err());
+    }
 
-    // Search for project files in order of preference:
-    // 1. .csproj files (C#)
-    // 2. .fsproj files (F#)
-    // 3. .sln files (Solution)
+    #[test]
+    fn test_gem_main_program() {
+        let exes = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            gem_main_program(&exes(&["rails", "rake"]), "rails"),
+            Some("rails".to_owned())
+        );
+        assert_eq!(
+            gem_main_program(&exes(&["rspec"]), "rspec-core"),
+            Some("rspec".to_owned())
+        );
+        assert_eq!(gem_main_program(&exes(&["a", "b"]), "c"), None);
+        assert_eq!(gem_main_program(&[], "c"), None);
+    }
 
-    fn find_project_files_impl(
-        dir: &Path,
-        extension: &str,
-        depth: usize,
-    ) -> Vec<std::path::PathBuf> {
-        use std::fs;
+    #[test]
+    fn test_metacpan_url_parse() {
+        assert_eq!(
+            validate_and_parse_url("metacpan.org/dist/Try-Tiny", "", &HashMap::new()).unwrap(),
+            Cpan(types::CpanRepo {
+                name: "Try-Tiny".to_string(),
+                module: false,
+            })
+        );
+        assert_eq!(
+            validate_and_parse_url("metacpan.org/pod/Moose::Role/", "", &HashMap::new()).unwrap(),
+            Cpan(types::CpanRepo {
+                name: "Moose::Role".to_string(),
+                module: true,
+            })
+        );
+    }
 
-        // Base case: if depth is 0, stop recursing
-        if depth == 0 {
-            return Vec::new();
-        }
+    #[test]
+    fn test_fetch_cpan_release() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/module/Demo::Role".to_owned(),
+                    r#"{"distribution":"Demo"}"#.to_owned(),
+                ),
+                (
+                    "/release/Demo".to_owned(),
+                    r#"{"distribution":"Demo","version":"1.2","author":"ME","download_url":"https://cpan.metacpan.org/authors/id/M/ME/ME/Demo-1.2.tar.gz","checksum_sha256":"abc","abstract":"A demo.","license":["perl_5"],"resources":{},"metadata":{"prereqs":{"configure":{"requires":{"Module::Build::Tiny":"0.034"}},"runtime":{"requires":{"perl":"5.008","Try::Tiny":"0"}}}}}"#.to_owned(),
+                ),
+                (
+                    "/release/ME/Demo-1.1".to_owned(),
+                    r#"{"distribution":"Demo","version":"1.1","author":"ME","download_url":"https://cpan.metacpan.org/authors/id/M/ME/ME/Demo-1.1.tar.gz","license":["unknown"]}"#.to_owned(),
+                ),
+            ]
+        });
 
-        let mut results = Vec::new();
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some(extension) {
-                    results.push(path);
-                } else if path.is_dir() {
-                    // Recursively search subdirectories with decremented depth
-                    results.extend(find_project_files_impl(&path, extension, depth - 1));
-                }
-            }
+        let module = fetch_cpan_module(&base, "Demo::Role").unwrap();
+        assert_eq!(module.distribution, "Demo");
+
+        let latest = fetch_cpan_release(&base, "Demo", None).unwrap();
+        assert_eq!(latest.version, "1.2");
+        assert_eq!(
+            cpan_mirror_url(&latest.download_url),
+            "mirror://cpan/authors/id/M/ME/ME/Demo-1.2.tar.gz"
+        );
+        assert_eq!(
+            cpan_licenses_to_nixpkgs(&latest.license),
+            "artistic1 gpl1Plus"
+        );
+        assert!(cpan_uses_module_build(&latest.metadata));
+        assert_eq!(
+            crate::deps::perl::meta_json_requires(&latest.metadata),
+            Some(vec!["Try::Tiny".to_owned()])
+        );
+
+        let older = fetch_cpan_release(&base, "Demo", Some(("ME", "1.1"))).unwrap();
+        assert_eq!(cpan_licenses_to_nixpkgs(&older.license), "CHANGE");
+        assert!(!cpan_uses_module_build(&older.metadata));
+        assert!(fetch_cpan_release(&base, "Demo", Some(("ME", "0.1"))).is_err());
+    }
+
+    #[test]
+    fn test_cran_and_bioconductor_url_parse() {
+        for url in &[
+            "cran.r-project.org/package=data.table",
+            "cran.r-project.org/web/packages/data.table/index.html",
+            "cloud.r-project.org/web/packages/data.table/",
+        ] {
+            assert_eq!(
+                validate_and_parse_url(url, url, &HashMap::new()).unwrap(),
+                Cran(types::CranRepo {
+                    package: "data.table".to_string(),
+                    bioconductor: false,
+                })
+            );
+        }
+        for url in &[
+            "bioconductor.org/packages/DESeq2",
+            "www.bioconductor.org/packages/release/bioc/html/DESeq2.html",
+        ] {
+            assert_eq!(
+                validate_and_parse_url(url, url, &HashMap::new()).unwrap(),
+                Cran(types::CranRepo {
+                    package: "DESeq2".to_string(),
+                    bioconductor: true,
+                })
+            );
         }
-        results
     }
 
-    fn find_project_files(dir: &Path, extension: &str) -> Vec<std::path::PathBuf> {
-        // Search up to 3 levels deep
-        find_project_files_impl(dir, extension, 3)
+    #[test]
+    fn test_fetch_r_package_version() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/src/contrib/PACKAGES".to_owned(),
+                    "Package: A3\nVersion: 1.0.0\nDepends: R (>= 2.15.0)\n\nPackage: demo\nVersion: 2.1-3\nImports: Rcpp\n".to_owned(),
+                ),
+                (
+                    "/config.yaml".to_owned(),
+                    "devel_version: \"3.21\"\nrelease_version: \"3.20\"\n".to_owned(),
+                ),
+            ]
+        });
+
+        assert_eq!(
+            fetch_r_package_version(&base, "demo").unwrap(),
+            Some("2.1-3".to_owned())
+        );
+        assert_eq!(fetch_r_package_version(&base, "missing").unwrap(), None);
+        assert_eq!(fetch_bioconductor_release(&base).unwrap(), "3.20");
     }
 
-    // Try .csproj first
-    let mut candidates = find_project_files(&source_path, "csproj");
-    if candidates.is_empty() {
-        // Try .fsproj
-        candidates = find_project_files(&source_path, "fsproj");
+    #[test]
+    fn test_r_license_to_nixpkgs() {
+        assert_eq!(r_license_to_nixpkgs("GPL-2 | GPL-3"), "gpl2Only gpl3Only");
+        assert_eq!(r_license_to_nixpkgs("MIT + file LICENSE"), "mit");
+        assert_eq!(r_license_to_nixpkgs("GPL (>= 2)"), "gpl2Plus");
+        assert_eq!(r_license_to_nixpkgs("file LICENSE"), "CHANGE");
     }
-    if candidates.is_empty() {
-        // Try .sln
-        candidates = find_project_files(&source_path, "sln");
+
+    #[test]
+    fn test_sourcehut_lfs_uses_fetchgit() {
+        let mut info = unfilled_info();
+        info.pname = "demo".to_owned();
+        info.template = Template::stdenv();
+        info.fetcher = types::Fetcher::sourcehut;
+        info.fetch_submodules = true;
+        use_fetchgit_if_needed(&mut info, "https://git.sr.ht/~owner/demo");
+        assert_eq!(info.fetcher, types::Fetcher::sourcehut);
+
+        info.fetch_lfs = true;
+        use_fetchgit_if_needed(&mut info, "https://git.sr.ht/~owner/demo");
+        let out = info.format(&crate::expression::generate_expression(&info));
+        assert!(!out.contains("fetchFromSourcehut"));
+        assert!(out.contains(
+            "  src = fetchgit {\n    url = \"https://git.sr.ht/~owner/demo\";\n    rev = finalAttrs.version;\n    hash = \"\";\n    fetchSubmodules = true;\n    fetchLFS = true;\n  };"
+        ));
     }
 
-    if candidates.is_empty() {
-        eprintln!("No .NET project files (.csproj, .fsproj, .sln) found in source");
-        return None;
+    /// ExpressionInfo as the CLI builds it before querying a URL
+    fn unfilled_info() -> types::ExpressionInfo {
+        types::ExpressionInfo {
+            pname: "CHANGE".to_owned(),
+            version: "0.0.1".to_owned(),
+            license: "CHANGE".to_owned(),
+            maintainer: String::new(),
+            fetcher: types::Fetcher::github,
+            template: Template::Auto,
+            path_to_write: std::path::PathBuf::new(),
+            top_level_path: std::path::PathBuf::new(),
+            include_documentation_links: false,
+            include_meta: true,
+            tag_prefix: String::new(),
+            tag_version_separator: String::new(),
+            src_rev: String::new(),
+            fetch_submodules: false,
+            fetch_lfs: false,
+            main_program: String::new(),
+            executables: Vec::new(),
+            owner: "CHANGE".to_owned(),
+            src_sha: String::new(),
+            description: "CHANGE".to_owned(),
+            homepage: "CHANGE".to_owned(),
+            propagated_build_inputs: Vec::new(),
+            cargo_hash: FAKE_SRI_HASH.to_owned(),
+            vendor_hash: FAKE_SRI_HASH.to_owned(),
+            npm_deps_hash: FAKE_SRI_HASH.to_owned(),
+            pnpm_deps_hash: FAKE_SRI_HASH.to_owned(),
+            project_file: "CHANGE".to_owned(),
+            domain: "CHANGE".to_owned(),
+            src_url: "CHANGE".to_owned(),
+            build_inputs: Vec::new(),
+            native_build_inputs: Vec::new(),
+            use_cargo_lock_file: false,
+            cargo_lock_git_deps: Vec::new(),
+            go_module_path: String::new(),
+            go_mod_root: String::new(),
+            python_format: "setuptools".to_owned(),
+            mvn_hash: FAKE_SRI_HASH.to_owned(),
+            mix_fod_hash: FAKE_SRI_HASH.to_owned(),
+            gradle_hash: FAKE_SRI_HASH.to_owned(),
+        }
+    }
+    #[test]
+    fn test_hex_url_parse() {
+        assert_eq!(
+            validate_and_parse_url(
+                "hex.pm/packages/bcrypt_elixir",
+                "https://hex.pm/packages/bcrypt_elixir",
+                &HashMap::new()
+            )
+            .unwrap(),
+            Hex(types::HexRepo {
+                name: "bcrypt_elixir".to_string(),
+                version: None,
+            })
+        );
+        assert_eq!(
+            validate_and_parse_url(
+                "hex.pm/packages/jason/1.4.1",
+                "hex.pm/packages/jason/1.4.1",
+                &HashMap::new()
+            )
+            .unwrap(),
+            Hex(types::HexRepo {
+                name: "jason".to_string(),
+                version: Some("1.4.1".to_string()),
+            })
+        );
     }
 
-    // Prefer files in the root directory, then shortest path
-    candidates.sort_by_key(|p| p.components().count());
+    #[test]
+    fn test_fetch_and_apply_hex_release() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/packages/bcrypt_elixir".to_owned(),
+                    r#"{"name":"bcrypt_elixir","html_url":"https://hex.pm/packages/bcrypt_elixir","latest_stable_version":"3.1.0","latest_version":"3.2.0-rc.0","meta":{"description":"Bcrypt password hashing algorithm for Elixir.","licenses":["BSD-3-Clause"],"links":{"GitHub":"https://github.com/riverrun/bcrypt_elixir"}}}"#.to_owned(),
+                ),
+                (
+                    "/packages/bcrypt_elixir/releases/3.1.0".to_owned(),
+                    r#"{"version":"3.1.0","requirements":{"comeonin":{"app":"comeonin","optional":false,"requirement":"~> 5.3"},"elixir_make":{"app":"elixir_make","optional":false,"requirement":"~> 0.6"},"benchee":{"app":"benchee","optional":true,"requirement":"~> 1.0"}},"meta":{"build_tools":["make","mix"]}}"#.to_owned(),
+                ),
+            ]
+        });
 
-    let chosen = &candidates[0];
-    let relative = chosen.strip_prefix(&source_path).ok()?;
-    let relative_str = relative.to_str()?;
+        let package = fetch_hex_package(&base, "bcrypt_elixir").unwrap();
+        assert_eq!(package.latest_stable_version.as_deref(), Some("3.1.0"));
+        let release = fetch_hex_release(&base, "bcrypt_elixir", "3.1.0").unwrap();
+        assert!(fetch_hex_release(&base, "bcrypt_elixir", "9.9.9").is_err());
 
-    eprintln!("Detected project file: {}", relative_str);
-    Some(relative_str.to_owned())
-}
+        let mut info = unfilled_info();
+        apply_hex_release(&package, &release, &mut info);
+        assert_eq!(info.pname, "bcrypt_elixir");
+        assert_eq!(info.version, "3.1.0");
+        assert_eq!(info.fetcher, types::Fetcher::hex);
+        assert_eq!(
+            info.description,
+            "Bcrypt password hashing algorithm for Elixir"
+        );
+        assert_eq!(info.homepage, "https://github.com/riverrun/bcrypt_elixir");
+        assert_eq!(info.license, "bsd3");
+        assert_eq!(
+            info.propagated_build_inputs,
+            vec!["comeonin", "elixir_make"]
+        );
+        assert_eq!(info.build_inputs, vec!["libsodium"]);
+        assert_eq!(info.template, Template::elixir_library());
+    }
 
-pub fn read_meta_from_url(url: &str, info: &mut types::ExpressionInfo, include_prereleases: bool) {
-    let trimmed_url = url
-        .trim_start_matches("http://")
-        .trim_start_matches("https://");
+    #[test]
+    fn test_apply_hex_contents() {
+        let contents = tempfile_dir().unwrap().join("hex-contents");
+        std::fs::create_dir_all(&contents).unwrap();
+        std::fs::write(
+            contents.join("mix.exs"),
+            "defmodule Demo.MixProject do\n  def project, do: [app: :demo, releases: releases()]\n\n  defp releases do\n    [demo: []]\n  end\nend\n",
+        )
+        .unwrap();
+        std::fs::write(
+            contents.join("mix.lock"),
+            "%{\n  \"rustler\": {:hex, :rustler, \"0.34.0\", \"e9a\", [:mix], [], \"hexpm\", \"1d0\"},\n}\n",
+        )
+        .unwrap();
 
-    match validate_and_parse_url(trimmed_url, url) {
-        Ok(Github(repo)) => {
-            fill_github_info(&repo, info);
-        }
-        Ok(Gitlab(repo)) => {
-            fill_gitlab_info(&repo, info, include_prereleases);
-        }
-        Ok(Pypi(pypi_repo)) => {
-            fill_pypi_info(&pypi_repo, info);
-        }
-        Ok(Gitea(gitea_repo)) => {
-            fill_gitea_info(&gitea_repo, info);
-        }
-        Err(e) => {
-            eprintln!("{}", e);
-            exit(1);
-        }
+        let mut info = unfilled_info();
+        info.template = Template::elixir_library();
+        apply_hex_contents(&contents, &mut info);
+        assert_eq!(info.template, Template::elixir_release());
+        assert_eq!(info.native_build_inputs, vec!["cargo", "rustc"]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::Repo::{Gitea, Github};
-    use crate::types::{GiteaRepo, GithubRepo};
-    use pretty_assertions::assert_eq;
+    #[test]
+    fn test_packagist_url_parse() {
+        assert_eq!(
+            validate_and_parse_url(
+                "packagist.org/packages/phpstan/phpstan",
+                "https://packagist.org/packages/phpstan/phpstan",
+                &HashMap::new()
+            )
+            .unwrap(),
+            Packagist(types::PackagistRepo {
+                vendor: "phpstan".to_owned(),
+                name: "phpstan".to_owned(),
+            })
+        );
+        assert!(validate_and_parse_url(
+            "packagist.org/packages/phpstan",
+            "https://packagist.org/packages/phpstan",
+            &HashMap::new()
+        )
+        .is_err());
+    }
 
     #[test]
-    fn test_url_parse() {
-        let repo = validate_and_parse_url(
-            "github.com/jonringer/nix-template",
-            "github.com/jonringer/nix-template",
+    fn test_fetch_and_apply_packagist_version() {
+        let base = spawn_mock_api(|_| {
+            vec![(
+                "/packages/psy/psysh.json".to_owned(),
+                r#"{"package": {
+                    "name": "psy/psysh",
+                    "description": "An interactive shell for modern PHP.",
+                    "repository": "https://github.com/bobthecow/psysh",
+                    "versions": {
+                        "dev-main": {"version": "dev-main", "license": ["MIT"]},
+                        "v0.12.3": {
+                            "version": "v0.12.3",
+                            "license": ["MIT"],
+                            "description": "An interactive shell for modern PHP.",
+                            "homepage": "https://psysh.org",
+                            "source": {"type": "git", "url": "https://github.com/bobthecow/psysh.git", "reference": "b6b6cce"}
+                        },
+                        "v0.12.10": {"version": "v0.12.10", "license": ["MIT"]},
+                        "v0.13.0-beta1": {"version": "v0.13.0-beta1", "license": ["MIT"]}
+                    }
+                }}"#
+                .to_owned(),
+            )]
+        });
+        let repo = types::PackagistRepo {
+            vendor: "psy".to_owned(),
+            name: "psysh".to_owned(),
+        };
+        let package = fetch_packagist_package(&base, &repo).unwrap();
+
+        let latest = select_packagist_version(&package, None, false).unwrap();
+        assert_eq!(latest.version, "v0.12.10");
+        let prerelease = select_packagist_version(&package, None, true).unwrap();
+        assert_eq!(prerelease.version, "v0.13.0-beta1");
+        let tagged = select_packagist_version(&package, Some("0.12.3"), false).unwrap();
+        assert_eq!(tagged.version, "v0.12.3");
+        assert!(select_packagist_version(&package, Some("1.0.0"), false).is_none());
+
+        let mut info = unfilled_info();
+        apply_packagist_version(&package, tagged, &mut info);
+        assert_eq!(info.description, "An interactive shell for modern PHP");
+        assert_eq!(info.license, "mit");
+        assert_eq!(info.homepage, "https://psysh.org");
+        assert_eq!(info.template, Template::php());
+    }
+
+    #[test]
+    fn test_apply_composer_json() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(
+            source.path().join("composer.json"),
+            r#"{"require": {"php": "^8.2", "ext-intl": "*", "ext-json": "*"}}"#,
         )
         .unwrap();
 
-        assert_eq!(
-            repo,
-            Github(GithubRepo {
-                owner: "jonringer".to_string(),
-                repo: "nix-template".to_string()
+        let mut info = unfilled_info();
+        info.template = Template::php();
+        apply_composer_json(source.path(), &mut info);
+        let config = info.template.php_config().unwrap();
+        assert_eq!(config.version.as_deref(), Some("82"));
+        assert!(config.extensions.contains(&"intl".to_owned()));
+    }
+
+    #[test]
+    fn test_maven_url_parse() {
+        let commons_text = |version: Option<&str>| {
+            Maven(types::MavenRepo {
+                group_id: "org.apache.commons".to_owned(),
+                artifact_id: "commons-text".to_owned(),
+                version: version.map(str::to_owned),
             })
+        };
+        assert_eq!(
+            validate_and_parse_url(
+                "org.apache.commons:commons-text",
+                "org.apache.commons:commons-text",
+                &HashMap::new()
+            )
+            .unwrap(),
+            commons_text(None)
+        );
+        assert_eq!(
+            validate_and_parse_url(
+                "org.apache.commons:commons-text:1.12.0",
+                "org.apache.commons:commons-text:1.12.0",
+                &HashMap::new()
+            )
+            .unwrap(),
+            commons_text(Some("1.12.0"))
+        );
+        assert_eq!(
+            validate_and_parse_url(
+                "central.sonatype.com/artifact/org.apache.commons/commons-text/1.12.0",
+                "https://central.sonatype.com/artifact/org.apache.commons/commons-text/1.12.0",
+                &HashMap::new()
+            )
+            .unwrap(),
+            commons_text(Some("1.12.0"))
         );
+        // scp-style git URLs aren't coordinates
+        assert!(parse_maven_coordinates("git@example.org:project/repo.git").is_none());
+        assert!(parse_maven_coordinates("localhost:repo").is_none());
     }
 
     #[test]
-    fn test_codeberg_url_parse() {
-        let repo = validate_and_parse_url(
-            "codeberg.org/forgejo/forgejo",
-            "codeberg.org/forgejo/forgejo",
+    fn test_fetch_and_select_maven_version() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/com/google/guava/guava/maven-metadata.xml".to_owned(),
+                    r#"<metadata>
+                      <groupId>com.google.guava</groupId>
+                      <artifactId>guava</artifactId>
+                      <versioning>
+                        <latest>34.0.0-rc1</latest>
+                        <versions>
+                          <version>32.1.3-jre</version>
+                          <version>33.2.1-jre</version>
+                          <version>33.0.0-jre</version>
+                          <version>34.0.0-rc1</version>
+                        </versions>
+                      </versioning>
+                    </metadata>"#
+                        .to_owned(),
+                ),
+                (
+                    "/com/google/guava/guava/33.2.1-jre/guava-33.2.1-jre.pom".to_owned(),
+                    "<project><artifactId>guava</artifactId></project>".to_owned(),
+                ),
+            ]
+        });
+        let repo = types::MavenRepo {
+            group_id: "com.google.guava".to_owned(),
+            artifact_id: "guava".to_owned(),
+            version: None,
+        };
+        let versions = fetch_maven_versions(&base, &repo).unwrap();
+        assert_eq!(versions.len(), 4);
+
+        let latest = select_maven_version(&versions, None, false).unwrap();
+        assert_eq!(latest, "33.2.1-jre");
+        let prerelease = select_maven_version(&versions, None, true).unwrap();
+        assert_eq!(prerelease, "34.0.0-rc1");
+        let tagged = select_maven_version(&versions, Some("v33.0.0-jre"), false).unwrap();
+        assert_eq!(tagged, "33.0.0-jre");
+        assert!(select_maven_version(&versions, Some("1.0"), false).is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let pom = fetch_maven_pom(&base, &repo, &latest, dir.path()).unwrap();
+        assert_eq!(pom, dir.path().join("guava-33.2.1-jre.pom"));
+        assert!(std::fs::read_to_string(&pom).unwrap().contains("guava"));
+    }
+
+    #[test]
+    fn test_normalize_scm_url() {
+        assert_eq!(
+            normalize_scm_url("git@github.com:apache/commons-text.git"),
+            "https://github.com/apache/commons-text"
+        );
+        assert_eq!(
+            normalize_scm_url("git://github.com/FasterXML/jackson-databind.git"),
+            "https://github.com/FasterXML/jackson-databind"
+        );
+        assert_eq!(
+            normalize_scm_url("https://gitbox.apache.org/repos/asf/commons-text/"),
+            "https://gitbox.apache.org/repos/asf/commons-text"
+        );
+    }
+
+    #[test]
+    fn test_apply_pom() {
+        let dir = tempfile::tempdir().unwrap();
+        let pom = dir.path().join("demo-1.0.0.pom");
+        std::fs::write(
+            &pom,
+            r#"<project>
+              <artifactId>demo</artifactId>
+              <version>1.0.0</version>
+              <description>A demo library.</description>
+              <url>https://demo.example.org</url>
+              <licenses>
+                <license><name>The Apache Software License, Version 2.0</name></license>
+              </licenses>
+              <properties>
+                <maven.compiler.source>17</maven.compiler.source>
+              </properties>
+              <dependencies>
+                <dependency>
+                  <groupId>org.xerial</groupId>
+                  <artifactId>sqlite-jdbc</artifactId>
+                </dependency>
+              </dependencies>
+            </project>"#,
         )
         .unwrap();
+        let metadata = crate::deps::maven::parse_pom_metadata(&pom).unwrap();
 
+        let mut info = unfilled_info();
+        apply_pom(&pom, &metadata, &mut info);
+        assert_eq!(info.description, "A demo library");
+        assert_eq!(info.homepage, "https://demo.example.org");
+        assert_eq!(info.license, "asl20");
         assert_eq!(
-            repo,
-            Gitea(GiteaRepo {
-                domain: "codeberg.org".to_string(),
-                owner: "forgejo".to_string(),
-                repo: "forgejo".to_string(),
-            })
+            info.template.maven_config().unwrap().jdk_version.as_deref(),
+            Some("17")
+        );
+        assert_eq!(info.build_inputs, vec!["sqlite"]);
+    }
+
+    #[test]
+    fn test_select_release_assets() {
+        let names = [
+            "tool-v1.2.0-x86_64-unknown-linux-gnu.tar.gz",
+            "tool-v1.2.0-x86_64-unknown-linux-musl.tar.gz",
+            "tool-v1.2.0-aarch64-unknown-linux-gnu.tar.gz",
+            "tool-v1.2.0-aarch64-apple-darwin.tar.gz",
+            "tool-v1.2.0-universal-apple-darwin.tar.gz",
+            "tool-v1.2.0-x86_64-pc-windows-msvc.zip",
+            "tool-v1.2.0-x86_64-unknown-linux-gnu.tar.gz.sha256",
+            "tool_1.2.0_amd64.deb",
+        ];
+        let (selected, archives) = select_release_assets(&names);
+        assert!(archives);
+        assert_eq!(
+            selected,
+            vec![
+                ("x86_64-linux", "tool-v1.2.0-x86_64-unknown-linux-gnu.tar.gz"),
+                ("aarch64-linux", "tool-v1.2.0-aarch64-unknown-linux-gnu.tar.gz"),
+                ("x86_64-darwin", "tool-v1.2.0-universal-apple-darwin.tar.gz"),
+                ("aarch64-darwin", "tool-v1.2.0-aarch64-apple-darwin.tar.gz"),
+            ]
+        );
+
+        let (selected, archives) = select_release_assets(&["tool-linux-amd64", "tool-darwin-arm64"]);
+        assert!(!archives);
+        assert_eq!(
+            selected,
+            vec![
+                ("x86_64-linux", "tool-linux-amd64"),
+                ("aarch64-darwin", "tool-darwin-arm64"),
+            ]
         );
+        assert!(select_release_assets(&["checksums.txt", "tool.exe"]).0.is_empty());
     }
 
     #[test]
-    fn test_gitea_com_url_parse() {
-        let repo =
-            validate_and_parse_url("gitea.com/user/project", "gitea.com/user/project").unwrap();
-
+    fn test_select_appimage_asset() {
+        let names = [
+            "Demo-1.4.0-arm64.AppImage",
+            "Demo-1.4.0.AppImage",
+            "Demo-1.4.0.AppImage.zsync",
+            "Demo-1.4.0.tar.gz",
+        ];
+        assert_eq!(select_appimage_asset(&names), Some("Demo-1.4.0.AppImage"));
         assert_eq!(
-            repo,
-            Gitea(GiteaRepo {
-                domain: "gitea.com".to_string(),
-                owner: "user".to_string(),
-                repo: "project".to_string(),
-            })
+            select_appimage_asset(&["Demo-1.4.0-arm64.AppImage"]),
+            Some("Demo-1.4.0-arm64.AppImage")
         );
+        assert_eq!(select_appimage_asset(&["Demo-1.4.0.tar.gz"]), None);
     }
 
     #[test]
-    fn test_version_regex() {
-        let captures = VERSION_REGEX.captures("v0.1.0").unwrap();
-        assert_eq!(captures.len(), 3);
-        assert_eq!(captures.get(1).unwrap().as_str(), "v");
-        assert_eq!(captures.get(2).unwrap().as_str(), "0.1.0");
-
-        let captures = VERSION_REGEX.captures("azure-cli-21.1.3").unwrap();
-        assert_eq!(captures.len(), 3);
-        assert_eq!(captures.get(1).unwrap().as_str(), "azure-cli-");
-        assert_eq!(captures.get(2).unwrap().as_str(), "21.1.3");
-
-        let captures = VERSION_REGEX.captures("zfs-2.1.0-rc7").unwrap();
-        assert_eq!(captures.len(), 3);
-        assert_eq!(captures.get(1).unwrap().as_str(), "zfs-");
-        assert_eq!(captures.get(2).unwrap().as_str(), "2.1.0-rc7");
+    fn test_luarocks_url_parse() {
+        assert_eq!(
+            validate_and_parse_url(
+                "luarocks.org/modules/gvvaughan/luaposix/36.2.1-1",
+                "https://luarocks.org/modules/gvvaughan/luaposix/36.2.1-1",
+                &HashMap::new()
+            )
+            .unwrap(),
+            LuaRocks(types::LuaRocksRepo {
+                user: "gvvaughan".to_owned(),
+                name: "luaposix".to_owned(),
+                version: Some("36.2.1-1".to_owned()),
+            })
+        );
+        assert!(validate_and_parse_url(
+            "luarocks.org/modules/gvvaughan",
+            "https://luarocks.org/modules/gvvaughan",
+            &HashMap::new()
+        )
+        .is_err());
     }
 
     #[test]
-    fn test_simple_version_regex() {
-        let captures = VERSION_REGEX.captures("2.1.1").unwrap();
-        assert_eq!(captures.len(), 3);
-        assert_eq!(captures.get(1).unwrap().as_str(), "");
-        assert_eq!(captures.get(2).unwrap().as_str(), "2.1.1");
+    fn test_fetch_and_select_luarocks_version() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/manifests/gvvaughan/manifest".to_owned(),
+                    r#"repository = {
+                       luaposix = {
+                          ["35.1-1"] = { { arch = "rockspec" } },
+                          ["36.2.1-1"] = { { arch = "rockspec" } },
+                          ["36.2.1-2"] = { { arch = "rockspec" } },
+                          ["37.0rc1-1"] = { { arch = "rockspec" } },
+                          ["scm-1"] = { { arch = "rockspec" } }
+                       }
+                    }"#
+                    .to_owned(),
+                ),
+                (
+                    "/manifests/gvvaughan/luaposix-36.2.1-2.rockspec".to_owned(),
+                    r#"package = "luaposix""#.to_owned(),
+                ),
+            ]
+        });
+        let repo = types::LuaRocksRepo {
+            user: "gvvaughan".to_owned(),
+            name: "luaposix".to_owned(),
+            version: None,
+        };
+        let versions = fetch_luarocks_versions(&base, &repo).unwrap();
+        assert_eq!(versions.len(), 5);
+
+        let latest = select_luarocks_version(&versions, None, false).unwrap();
+        assert_eq!(latest, "36.2.1-2");
+        let prerelease = select_luarocks_version(&versions, None, true).unwrap();
+        assert_eq!(prerelease, "37.0rc1-1");
+        let tagged = select_luarocks_version(&versions, Some("v35.1"), false).unwrap();
+        assert_eq!(tagged, "35.1-1");
+        let revision = select_luarocks_version(&versions, Some("36.2.1-1"), false).unwrap();
+        assert_eq!(revision, "36.2.1-1");
+        assert!(select_luarocks_version(&versions, Some("1.0"), false).is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let rockspec = fetch_rockspec(&base, &repo, &latest, dir.path()).unwrap();
+        assert_eq!(rockspec, dir.path().join("luaposix-36.2.1-2.rockspec"));
     }
 
     #[test]
-    fn test_got_hash_regex_normal_hash() {
-        // Test normal SHA256 hash (44 characters in base64)
-        let input = "  got:    sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
-        let captures = GOT_HASH_REGEX.captures(input).unwrap();
-        assert_eq!(captures.len(), 2);
+    fn test_apply_rockspec() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("luasec-1.3.2-1.rockspec");
+        std::fs::write(
+            &path,
+            r#"
+package = "LuaSec"
+version = "1.3.2-1"
+description = {
+   summary = "A binding for OpenSSL library to provide TLS/SSL communication over LuaSocket.",
+   homepage = "https://github.com/brunoos/luasec/wiki",
+   license = "MIT"
+}
+dependencies = { "lua >= 5.1", "luasocket" }
+external_dependencies = { OPENSSL = { header = "openssl/ssl.h" } }
+"#,
+        )
+        .unwrap();
+        let rockspec = crate::deps::lua::parse_rockspec(&path).unwrap();
+
+        let mut info = unfilled_info();
+        apply_rockspec(&path, &rockspec, &mut info);
         assert_eq!(
-            captures.get(1).unwrap().as_str(),
-            "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+            info.description,
+            "A binding for OpenSSL library to provide TLS/SSL communication over LuaSocket"
         );
+        assert_eq!(info.homepage, "https://github.com/brunoos/luasec/wiki");
+        assert_eq!(info.license, "mit");
+        let config = info.template.lua_config().unwrap();
+        assert_eq!(config.variant, types::LuaVariant::Package);
+        assert_eq!(config.version, types::LuaVersion::Lua51);
+        assert_eq!(info.propagated_build_inputs, vec!["luasocket"]);
+        assert_eq!(info.build_inputs, vec!["openssl"]);
     }
 
     #[test]
-    fn test_got_hash_regex_prevents_redos() {
-        // Test that bounded quantifier prevents ReDoS
-        // This would cause catastrophic backtracking with unbounded quantifier
-        // The key is that the regex completes quickly even with malicious input
-        let malicious_input = format!("  got:    sha256-{}", "A".repeat(200));
-
-        // The regex will match the first 100 characters (which is fine for extraction)
-        // The important part is it doesn't hang due to catastrophic backtracking
-        let result = GOT_HASH_REGEX.captures(&malicious_input);
-
-        // It will match, but only capture up to 100 characters after "sha256-"
-        if let Some(captures) = result {
-            let captured_hash = captures.get(1).unwrap().as_str();
-            // Should capture at most "sha256-" + 100 chars
-            assert!(
-                captured_hash.len() <= 107, // "sha256-" (7 chars) + 100 chars
-                "Should not capture more than bounded amount"
-            );
-        }
+    fn test_pub_url_parse() {
+        assert_eq!(
+            validate_and_parse_url(
+                "pub.dev/packages/dart_style",
+                "https://pub.dev/packages/dart_style",
+                &HashMap::new()
+            )
+            .unwrap(),
+            Pub(types::PubRepo {
+                name: "dart_style".to_owned(),
+                version: None,
+            })
+        );
+        assert_eq!(
+            validate_and_parse_url(
+                "pub.dev/packages/dart_style/versions/2.3.6",
+                "https://pub.dev/packages/dart_style/versions/2.3.6",
+                &HashMap::new()
+            )
+            .unwrap(),
+            Pub(types::PubRepo {
+                name: "dart_style".to_owned(),
+                version: Some("2.3.6".to_owned()),
+            })
+        );
+        assert!(validate_and_parse_url(
+            "pub.dev/publishers/dart.dev",
+            "https://pub.dev/publishers/dart.dev",
+            &HashMap::new()
+        )
+        .is_err());
     }
 
     #[test]
-    fn test_got_hash_regex_with_varying_whitespace() {
-        // Test with single space
-        let input = "got: sha256-abc123def456=";
-        let captures = GOT_HASH_REGEX.captures(input).unwrap();
-        assert!(captures.get(1).unwrap().as_str().starts_with("sha256-"));
+    fn test_fetch_and_apply_pub_version() {
+        let base = spawn_mock_api(|_| {
+            vec![(
+                "/packages/dart_style".to_owned(),
+                r#"{
+                    "name": "dart_style",
+                    "latest": {
+                        "version": "2.3.6",
+                        "pubspec": {
+                            "description": "Opinionated, automatic Dart source code formatter.",
+                            "repository": "https://github.com/dart-lang/dart_style"
+                        },
+                        "archive_url": "https://pub.dev/api/archives/dart_style-2.3.6.tar.gz"
+                    },
+                    "versions": []
+                }"#
+                .to_owned(),
+            )]
+        });
+        let package = fetch_pub_package(&base, "dart_style").unwrap();
 
-        // Test with multiple spaces
-        let input = "got:     sha256-xyz789=";
-        let captures = GOT_HASH_REGEX.captures(input).unwrap();
-        assert!(captures.get(1).unwrap().as_str().starts_with("sha256-"));
+        let mut info = unfilled_info();
+        apply_pub_version(&package, &package.latest, &mut info);
+        assert_eq!(info.pname, "dart_style");
+        assert_eq!(info.version, "2.3.6");
+        assert_eq!(
+            info.description,
+            "Opinionated, automatic Dart source code formatter"
+        );
+        assert_eq!(info.homepage, "https://github.com/dart-lang/dart_style");
+        assert_eq!(info.fetcher, types::Fetcher::pubdev);
+        assert_eq!(info.template, Template::dart());
     }
 
     #[test]
-    fn test_stable_release_regex_valid_versions() {
-        // Test normal version strings
-        assert!(STABLE_RELEASE_REGEX.is_match("1.2.3"));
-        assert!(STABLE_RELEASE_REGEX.is_match("2024.1.15"));
-        assert!(STABLE_RELEASE_REGEX.is_match("0.1.0"));
-        assert!(STABLE_RELEASE_REGEX.is_match("123"));
+    fn test_apply_pubspec() {
+        let contents = tempfile::tempdir().unwrap();
+        let pubspec = contents.path().join("pubspec.yaml");
+        std::fs::write(
+            &pubspec,
+            "name: demo\nenvironment:\n  sdk: '>=3.0.0 <4.0.0'\nexecutables:\n  demo: main\n",
+        )
+        .unwrap();
 
-        // Test that non-stable versions don't match
-        assert!(!STABLE_RELEASE_REGEX.is_match("v1.2.3"));
-        assert!(!STABLE_RELEASE_REGEX.is_match("1.2.3-rc1"));
-        assert!(!STABLE_RELEASE_REGEX.is_match("abc"));
+        let mut info = unfilled_info();
+        info.template = Template::dart();
+        apply_pubspec(&pubspec, &mut info);
+        assert_eq!(info.main_program, "demo");
+        let config = info.template.dart_config().unwrap();
+        assert!(!config.flutter);
+        assert_eq!(config.executables, vec!["demo"]);
+        assert_eq!(config.dart_version.as_deref(), Some(">=3.0.0 <4.0.0"));
+
+        std::fs::write(
+            &pubspec,
+            "name: demo\ndependencies:\n  flutter:\n    sdk: flutter\n",
+        )
+        .unwrap();
+        apply_pubspec(&pubspec, &mut info);
+        assert!(info.template.dart_config().unwrap().flutter);
     }
 
-    #[test]
-    fn test_stable_release_regex_prevents_redos() {
-        // Test that bounded quantifier prevents ReDoS from nested quantifiers
-        // The old pattern ^([0-9.]*)+$ would cause catastrophic backtracking
-        let mut malicious_input = "1".repeat(100);
-        malicious_input.push('X'); // Doesn't end with valid chars
+    const ZARITH_OPAM: &str = r#"opam-version: "2.0"
+synopsis: "Arbitrary precision integers."
+license: "LGPL-2.0-only WITH OCaml-LGPL-linking-exception"
+homepage: "https://github.com/ocaml/Zarith"
+depends: [
+  "ocaml" {>= "4.07.0"}
+  "ocamlfind" {build}
+  "conf-gmp"
+  "alcotest" {with-test}
+]
+url {
+  src: "https://github.com/ocaml/Zarith/archive/release-1.13.tar.gz"
+  checksum: "sha256=a5826d33fea0103ad6e66f92583d8e075fb77976de893ffdd73ada0409b3f83b"
+}
+"#;
 
-        // Should complete quickly and not match
-        let result = STABLE_RELEASE_REGEX.is_match(&malicious_input);
-        assert!(!result, "Should not match invalid input");
+    #[test]
+    fn test_opam_url_parse() {
+        assert_eq!(
+            validate_and_parse_url(
+                "opam.ocaml.org/packages/zarith/zarith.1.13",
+                "https://opam.ocaml.org/packages/zarith/zarith.1.13",
+                &HashMap::new()
+            )
+            .unwrap(),
+            Opam(types::OpamRepo {
+                name: "zarith".to_owned(),
+                version: Some("1.13".to_owned()),
+                repository: None,
+            })
+        );
+        assert!(validate_and_parse_url(
+            "opam.ocaml.org/packages/zarith/gmp.1.13",
+            "https://opam.ocaml.org/packages/zarith/gmp.1.13",
+            &HashMap::new()
+        )
+        .is_err());
 
-        // Test that we still accept reasonably long valid versions
-        let long_valid = "2024.12.31.1.2.3.4.5.6.7.8.9.10.11.12";
-        assert!(
-            STABLE_RELEASE_REGEX.is_match(long_valid),
-            "Should match valid version within bounds"
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("packages/zarith/zarith.1.13")).unwrap();
+        let url = format!(
+            "file://{}/packages/zarith/zarith.1.13",
+            root.path().display()
+        );
+        assert_eq!(
+            validate_and_parse_url(&url, &url, &HashMap::new()).unwrap(),
+            Opam(types::OpamRepo {
+                name: "zarith".to_owned(),
+                version: Some("1.13".to_owned()),
+                repository: Some(root.path().to_string_lossy().into_owned()),
+            })
         );
+        // Anything else below file:// is still a git repository
+        let url = format!("file://{}/packages/gmp", root.path().display());
+        assert!(matches!(
+            validate_and_parse_url(&url, &url, &HashMap::new()),
+            Ok(Git(_))
+        ));
     }
 
     #[test]
-    fn test_stable_release_regex_length_bound() {
-        // Test that versions exceeding 50 characters are rejected
-        let too_long = "1.".repeat(30); // Creates "1.1.1.1..." > 50 chars
-        assert!(
-            !STABLE_RELEASE_REGEX.is_match(&too_long),
-            "Should reject excessively long version strings"
+    fn test_local_opam_repository() {
+        let root = tempfile::tempdir().unwrap();
+        for version in ["1.12", "1.13"] {
+            let dir = root
+                .path()
+                .join(format!("packages/zarith/zarith.{}", version));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("opam"), ZARITH_OPAM).unwrap();
+        }
+        let repository = OpamRepository::Local(root.path());
+        let mut versions = list_opam_versions(&repository, "zarith").unwrap();
+        versions.sort();
+        assert_eq!(versions, vec!["1.12", "1.13"]);
+
+        let repo = types::OpamRepo {
+            name: "zarith".to_owned(),
+            version: None,
+            repository: Some(root.path().to_string_lossy().into_owned()),
+        };
+        let opam_path = root.path().join("packages/zarith/zarith.1.13/opam");
+        let mut info = unfilled_info();
+        let sha256 = apply_opam_file(&repo, "1.13", &opam_path, &mut info);
+        assert_eq!(
+            sha256.as_deref(),
+            Some("a5826d33fea0103ad6e66f92583d8e075fb77976de893ffdd73ada0409b3f83b")
+        );
+        assert_eq!(info.pname, "zarith");
+        assert_eq!(info.version, "1.13");
+        assert_eq!(info.description, "Arbitrary precision integers");
+        assert_eq!(info.homepage, "https://github.com/ocaml/Zarith");
+        assert_eq!(info.fetcher, types::Fetcher::url);
+        assert_eq!(
+            info.src_url,
+            "https://github.com/ocaml/Zarith/archive/release-${finalAttrs.version}.tar.gz"
         );
+        assert!(info.propagated_build_inputs.is_empty());
+        assert_eq!(info.build_inputs, vec!["gmp"]);
+        assert_eq!(info.native_build_inputs, vec!["findlib"]);
+        assert!(info.template.is_ocaml());
     }
 
     #[test]
-    fn test_validate_url_component_valid() {
-        // Test normal valid components
-        assert!(validate_url_component("valid-repo", "test").is_ok());
-        assert!(validate_url_component("repo_123", "test").is_ok());
-        assert!(validate_url_component("1.2.3", "test").is_ok());
-        assert!(validate_url_component("v1.0.0", "test").is_ok());
+    fn test_remote_opam_repository() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/repos/ocaml/opam-repository/contents/packages/zarith".to_owned(),
+                    r#"[{"name": "zarith.1.13", "type": "dir"}, {"name": "zarith.1.14~rc1", "type": "dir"}]"#.to_owned(),
+                ),
+                (
+                    "/packages/zarith/zarith.1.13/opam".to_owned(),
+                    ZARITH_OPAM.to_owned(),
+                ),
+            ]
+        });
+        let repository = OpamRepository::Remote {
+            api_base: &base,
+            raw_base: &base,
+        };
+        assert_eq!(
+            list_opam_versions(&repository, "zarith").unwrap(),
+            vec!["1.13", "1.14~rc1"]
+        );
+        assert_eq!(
+            read_opam_file(&repository, "zarith", "1.13").unwrap(),
+            ZARITH_OPAM
+        );
     }
 
     #[test]
-    fn test_validate_url_component_rejects_control_chars() {
-        // Test that control characters are rejected
-        assert!(validate_url_component("foo\nbar", "test").is_err());
-        assert!(validate_url_component("foo\rbar", "test").is_err());
-        assert!(validate_url_component("foo\tbar", "test").is_err());
-        assert!(validate_url_component("foo\x00bar", "test").is_err());
+    fn test_go_module_url_parse() {
+        assert_eq!(
+            validate_and_parse_url(
+                "pkg.go.dev/golang.org/x/tools/gopls",
+                "https://pkg.go.dev/golang.org/x/tools/gopls",
+                &HashMap::new()
+            )
+            .unwrap(),
+            GoModule(types::GoModuleRepo {
+                path: "golang.org/x/tools/gopls".to_string(),
+                version: None,
+            })
+        );
+        assert_eq!(
+            validate_and_parse_url(
+                "pkg.go.dev/github.com/junegunn/fzf@v0.54.0",
+                "pkg.go.dev/github.com/junegunn/fzf@v0.54.0",
+                &HashMap::new()
+            )
+            .unwrap(),
+            GoModule(types::GoModuleRepo {
+                path: "github.com/junegunn/fzf".to_string(),
+                version: Some("v0.54.0".to_string()),
+            })
+        );
     }
 
     #[test]
-    fn test_validate_url_component_rejects_null_bytes() {
-        // Test explicit null byte rejection
-        let with_null = "test\0value";
-        assert!(validate_url_component(with_null, "test").is_err());
+    fn test_go_proxy_base() {
+        assert_eq!(
+            go_proxy_base(None).as_deref(),
+            Some("https://proxy.golang.org")
+        );
+        assert_eq!(
+            go_proxy_base(Some("direct")).as_deref(),
+            Some("https://proxy.golang.org")
+        );
+        assert_eq!(
+            go_proxy_base(Some("https://goproxy.io/,direct")).as_deref(),
+            Some("https://goproxy.io")
+        );
+        assert_eq!(
+            go_proxy_base(Some("direct|file:///srv/goproxy,off")).as_deref(),
+            Some("file:///srv/goproxy")
+        );
+        assert_eq!(go_proxy_base(Some("off")), None);
+        assert_eq!(go_proxy_base(Some("direct,off|file:///srv/goproxy")), None);
     }
 
     #[test]
-    fn test_validate_url_component_rejects_too_long() {
-        // Test length validation (max 255)
-        let too_long = "a".repeat(256);
-        assert!(validate_url_component(&too_long, "test").is_err());
+    fn test_fetch_go_module_info_from_file_proxy() {
+        let proxy = tempfile_dir().unwrap().join("goproxy");
+        let module_dir = proxy.join("github.com/!burnt!sushi/toml/@v");
+        std::fs::create_dir_all(&module_dir).unwrap();
+        std::fs::write(
+            proxy.join("github.com/!burnt!sushi/toml/@latest"),
+            r#"{"Version":"v1.4.0","Time":"2024-06-01T10:00:00Z","Origin":{"VCS":"git","URL":"https://github.com/BurntSushi/toml","Hash":"0123456789abcdef0123456789abcdef01234567"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            module_dir.join("v1.3.2.info"),
+            r#"{"Version":"v1.3.2","Time":"2023-06-01T10:00:00Z"}"#,
+        )
+        .unwrap();
+        let proxy = format!("file://{}", proxy.display());
 
-        // Test that 255 is OK
-        let max_length = "a".repeat(255);
-        assert!(validate_url_component(&max_length, "test").is_ok());
-    }
+        // A package below the module root resolves to the module
+        let (module, latest) =
+            fetch_go_module_info(&proxy, "github.com/BurntSushi/toml/cmd/tomlv", None).unwrap();
+        assert_eq!(module, "github.com/BurntSushi/toml");
+        assert_eq!(latest.version, "v1.4.0");
+        assert_eq!(
+            go_module_origin(&module, &latest),
+            Some(GoModuleOrigin {
+                url: "https://github.com/BurntSushi/toml".to_owned(),
+                subdir: String::new(),
+            })
+        );
 
-    #[test]
-    fn test_validate_url_component_rejects_empty() {
-        // Test that empty strings are rejected
-        assert!(validate_url_component("", "test").is_err());
-        assert!(validate_url_component("   ", "test").is_err());
+        let (_, pinned) =
+            fetch_go_module_info(&proxy, "github.com/BurntSushi/toml", Some("v1.3.2")).unwrap();
+        assert_eq!(pinned.version, "v1.3.2");
+        assert!(pinned.origin.is_none());
+        assert!(
+            fetch_go_module_info(&proxy, "github.com/BurntSushi/toml", Some("v9.9.9")).is_err()
+        );
     }
 
     #[test]
-    fn test_validate_github_repo_valid() {
-        // Test valid GitHub repo
-        let repo = types::GithubRepo {
-            owner: "octocat".to_string(),
-            repo: "Hello-World".to_string(),
-        };
-        assert!(validate_github_repo(&repo).is_ok());
+    fn test_go_module_origin_mapping() {
+        assert_eq!(
+            go_escape_path("github.com/Azure/azure-sdk"),
+            "github.com/!azure/azure-sdk"
+        );
+        assert_eq!(
+            go_known_host_origin("golang.org/x/tools/gopls"),
+            Some(GoModuleOrigin {
+                url: "https://go.googlesource.com/tools".to_owned(),
+                subdir: "gopls".to_owned(),
+            })
+        );
+        assert_eq!(
+            go_known_host_origin("github.com/go-git/go-git/v5"),
+            Some(GoModuleOrigin {
+                url: "https://github.com/go-git/go-git".to_owned(),
+                subdir: String::new(),
+            })
+        );
+        assert_eq!(go_known_host_origin("example.com/tool"), None);
+
+        let html = r#"<html><head>
+<meta name="go-import" content="mvdan.cc/sh/v3 mod https://proxy.example">
+<meta name="go-import" content="mvdan.cc/sh/v3 git https://github.com/mvdan/sh">
+</head></html>"#;
+        assert_eq!(
+            parse_go_import_meta(html, "mvdan.cc/sh/v3"),
+            Some(GoModuleOrigin {
+                url: "https://github.com/mvdan/sh".to_owned(),
+                subdir: String::new(),
+            })
+        );
+        assert_eq!(parse_go_import_meta(html, "mvdan.cc/shfmt"), None);
+
+        assert_eq!(go_package_pname("github.com/go-git/go-git/v5"), "go-git");
+        assert_eq!(go_package_pname("golang.org/x/tools/gopls"), "gopls");
     }
 
     #[test]
-    fn test_validate_github_repo_invalid_owner() {
-        // Test invalid owner with newline
-        let repo = types::GithubRepo {
-            owner: "bad\nowner".to_string(),
-            repo: "Hello-World".to_string(),
-        };
-        assert!(validate_github_repo(&repo).is_err());
+    fn test_go_pseudo_version_commit() {
+        let expected = Some(("2024-05-01".to_owned(), "0123456789ab".to_owned()));
+        assert_eq!(
+            go_pseudo_version_commit("v0.0.0-20240501120000-0123456789ab"),
+            expected
+        );
+        assert_eq!(
+            go_pseudo_version_commit("v1.2.4-0.20240501120000-0123456789ab"),
+            expected
+        );
+        assert_eq!(
+            go_pseudo_version_commit("v1.2.4-rc.1.0.20240501120000-0123456789ab"),
+            expected
+        );
+        assert_eq!(go_pseudo_version_commit("v1.2.3"), None);
     }
 
     #[test]
-    fn test_validate_version_components_valid() {
-        // Test valid version components
-        assert!(validate_version_components("1.2.3", "v").is_ok());
-        assert!(validate_version_components("2024.1.15", "").is_ok());
-        assert!(validate_version_components("0.1.0", "version-").is_ok());
+    fn test_select_hackage_version() {
+        let versions = types::HackagePreferredResponse {
+            normal_version: vec!["0.9.1".to_owned(), "0.10.2".to_owned()],
+            deprecated_version: vec!["0.11.0".to_owned()],
+        };
+        assert_eq!(
+            select_hackage_version(&versions, None),
+            Some("0.10.2".to_owned())
+        );
+        assert_eq!(
+            select_hackage_version(&versions, Some("0.11.0")),
+            Some("0.11.0".to_owned())
+        );
+        assert_eq!(select_hackage_version(&versions, Some("1.0")), None);
     }
 
     #[test]
-    fn test_validate_version_components_invalid() {
-        // Test invalid version with control characters
-        assert!(validate_version_components("1.2.3\n4", "v").is_err());
-        assert!(validate_version_components("1.2.3", "v\r").is_err());
+    fn test_fetch_hackage_versions_and_cabal_file() {
+        let base = spawn_mock_api(|_| {
+            vec![
+                (
+                    "/package/demo/preferred".to_owned(),
+                    r#"{"normal-version":["1.2.0","1.1.0"]}"#.to_owned(),
+                ),
+                (
+                    "/package/demo-1.2.0/demo.cabal".to_owned(),
+                    "name: demo\nversion: 1.2.0\nsynopsis: Demo tool.\nlicense: BSD3\n\nexecutable demo\n  main-is: Main.hs\n".to_owned(),
+                ),
+            ]
+        });
+        let repo = types::HackageRepo {
+            package: "demo".to_owned(),
+            version: None,
+        };
 
-        // Test empty version (tag_prefix can be empty though)
-        assert!(validate_version_components("", "v").is_err());
-    }
+        let versions = fetch_hackage_versions(&base, &repo).unwrap();
+        assert!(versions.deprecated_version.is_empty());
+        let version = select_hackage_version(&versions, None).unwrap();
+        assert_eq!(version, "1.2.0");
 
-    #[test]
-    fn test_validate_version_components_allows_empty_prefix() {
-        // Tag prefix can be empty (no prefix)
-        assert!(validate_version_components("1.2.3", "").is_ok());
+        let cabal = fetch_hackage_cabal_file(&base, &repo, &version).unwrap();
+        assert_eq!(
+            crate::deps::haskell::extract_cabal_field(&cabal, "synopsis"),
+            Some("Demo tool.".to_owned())
+        );
+        assert_eq!(CABAL_TO_NIXPKGS_LICENSE.get("BSD3"), Some(&"bsd3"));
+        assert!(fetch_hackage_cabal_file(&base, &repo, "9.9").is_err());
     }
 }
@@ -1317,3 +1317,177 @@ fn test_r_template_basic() {
     // Snapshot the output
     insta::assert_snapshot!("r_basic_template", stdout);
 }
+
+/// Create a bare git repository under `dir` whose only commit carries the
+/// given tags, returning its `file://` URL.
+fn bare_repo_with_tags(dir: &std::path::Path, tags: &[&str]) -> String {
+    let git = |args: &[&str], cwd: &std::path::Path| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.org")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.org")
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    };
+
+    let work = dir.join("work");
+    fs::create_dir(&work).unwrap();
+    git(&["init", "-q"], &work);
+    git(&["commit", "-q", "--allow-empty", "-m", "init"], &work);
+    for tag in tags {
+        git(&["tag", tag], &work);
+    }
+    git(&["clone", "-q", "--bare", "work", "libdemo.git"], dir);
+
+    format!("file://{}", dir.join("libdemo.git").display())
+}
+
+/// Test that a plain git URL resolves the latest tag via `git ls-remote`
+/// and renders a fetchgit block
+#[test]
+fn test_generic_git_url_uses_fetchgit() {
+    let temp_dir = TempDir::new().unwrap();
+    let url = bare_repo_with_tags(
+        temp_dir.path(),
+        &["v1.2.0", "v1.10.0", "v2.0.0-rc1", "nightly"],
+    );
+
+    let mut cmd = Command::cargo_bin("nix-template").unwrap();
+    let output = cmd
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .args([
+            "template",
+            "stdenv",
+            "--from-url",
+            &url,
+            "--maintainer",
+            "",
+            "-s",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(", fetchgit"));
+    assert!(stdout.contains("pname = \"libdemo\";"));
+    assert!(stdout.contains("version = \"1.10.0\";"));
+    assert!(stdout.contains(&format!(
+        "src = fetchgit {{\n    url = \"{}\";\n    rev = \"v${{finalAttrs.version}}\";\n    hash = ",
+        url
+    )));
+}
+
+/// Test that --tag-regex and --version-transform pick the right component
+/// tag and render a rev that rebuilds it
+#[test]
+fn test_tag_regex_with_version_transform() {
+    let temp_dir = TempDir::new().unwrap();
+    let url = bare_repo_with_tags(
+        temp_dir.path(),
+        &["release_1_2_3", "release_1_10_0", "tools_9_0_0"],
+    );
+
+    let mut cmd = Command::cargo_bin("nix-template").unwrap();
+    let output = cmd
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .args([
+            "template",
+            "stdenv",
+            "--from-url",
+            &url,
+            "--tag-regex",
+            "^(?P<prefix>release_)(?P<version>[0-9_]+)$",
+            "--version-transform",
+            "underscores",
+            "--maintainer",
+            "",
+            "-s",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("version = \"1.10.0\";"), "{}", stdout);
+    assert!(
+        stdout.contains(
+            "rev = \"release_${lib.replaceStrings [ \".\" ] [ \"_\" ] finalAttrs.version}\";"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+/// Test that a local release tarball infers pname/version from its filename
+/// and renders the url with the version templated out
+#[test]
+fn test_archive_url_uses_fetchurl() {
+    let temp_dir = TempDir::new().unwrap();
+    let source_dir = temp_dir.path().join("hello-2.12.1");
+    fs::create_dir(&source_dir).unwrap();
+    fs::write(source_dir.join("README"), "hello\n").unwrap();
+    let status = std::process::Command::new("tar")
+        .args(["-czf", "hello-2.12.1.tar.gz", "hello-2.12.1"])
+        .current_dir(temp_dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let dir_url = format!("file://{}", temp_dir.path().display());
+
+    let mut cmd = Command::cargo_bin("nix-template").unwrap();
+    let output = cmd
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .args([
+            "template",
+            "stdenv",
+            "--from-url",
+            &format!("{}/hello-2.12.1.tar.gz", dir_url),
+            "--maintainer",
+            "",
+            "-s",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "Command failed: {:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(", fetchurl"));
+    assert!(stdout.contains("pname = \"hello\";"));
+    assert!(stdout.contains("version = \"2.12.1\";"));
+    assert!(stdout.contains(&format!(
+        "src = fetchurl {{\n    url = \"{}/hello-${{finalAttrs.version}}.tar.gz\";",
+        dir_url
+    )));
+}
+
+#[test]
+fn test_tag_conflicts_with_unstable() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cmd = Command::cargo_bin("nix-template").unwrap();
+    let output = cmd
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .args([
+            "template",
+            "stdenv",
+            "--from-url",
+            "https://github.com/jonringer/nix-template",
+            "--tag",
+            "v0.4.0",
+            "--unstable",
+            "-s",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("cannot be used with"), "{}", stderr);
+}